@@ -0,0 +1,169 @@
+//! Time spent rendering a 200-post timeline's content: converting post
+//! HTML the way the app did before (with `html2text`) against parsing it
+//! now, caching posts (which parses them once), and building each post's
+//! view from a warm cache against one that has to parse on every `view`
+//! call.
+//!
+//! Run with `cargo bench --bench content`.
+
+// The app is a binary with no library target for a benchmark to link
+// against, so the benchmark compiles the app's source files itself, with
+// `#[path]` pointing each module at its file under `src/`. The modules
+// refer to each other through `crate::`, so every one `main.rs` declares
+// has to be declared here too, and kept in step when modules are added or
+// renamed. Most of them go unused here.
+#![allow(dead_code)]
+
+#[path = "../src/app.rs"]
+mod app;
+#[path = "../src/cache.rs"]
+mod cache;
+#[path = "../src/client.rs"]
+mod client;
+#[path = "../src/config.rs"]
+mod config;
+#[path = "../src/error.rs"]
+mod error;
+#[path = "../src/features/mod.rs"]
+mod features;
+#[path = "../src/i18n.rs"]
+mod i18n;
+#[path = "../src/oauth.rs"]
+mod oauth;
+#[path = "../src/persistence.rs"]
+mod persistence;
+#[path = "../src/session_store.rs"]
+mod session_store;
+#[path = "../src/settings.rs"]
+mod settings;
+#[path = "../src/streaming.rs"]
+mod streaming;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use megalodon::entities::Status;
+
+use cache::Cache;
+use features::status::{self, html, StatusOptions};
+
+const TIMELINE_LEN: usize = 200;
+
+/// A typical post body as Mastodon sends it: paragraphs, a mention, a link
+/// and a couple of hashtags.
+fn post(index: usize) -> String {
+    format!(
+        r#"<p>Post number {index}: just shipped a new release of the app! Thanks to <span class="h-card"><a href="https://example.social/@friend" class="u-url mention">@<span>friend</span></a></span> for the help.</p><p>Changelog: <a href="https://example.org/releases/{index}" rel="nofollow noopener" target="_blank"><span class="invisible">https://</span><span class="ellipsis">example.org/releases/</span><span class="invisible">{index}</span></a></p><p><a href="https://example.social/tags/rust" class="mention hashtag" rel="tag">#<span>rust</span></a> <a href="https://example.social/tags/cosmic" class="mention hashtag" rel="tag">#<span>cosmic</span></a></p>"#
+    )
+}
+
+/// A status with [`post`]'s body, as the app stores it.
+fn status(index: usize) -> Status {
+    let account = serde_json::json!({
+        "id": "1",
+        "username": "author",
+        "acct": "author",
+        "display_name": "Author",
+        "locked": false,
+        "bot": false,
+        "created_at": "2024-01-01T00:00:00Z",
+        "followers_count": 10,
+        "following_count": 10,
+        "statuses_count": TIMELINE_LEN,
+        "note": "",
+        "url": "https://example.social/@author",
+        "avatar": "",
+        "avatar_static": "",
+        "header": "",
+        "header_static": "",
+        "emojis": [],
+        "fields": [],
+    });
+    serde_json::from_value(serde_json::json!({
+        "id": index.to_string(),
+        "uri": format!("https://example.social/users/author/statuses/{index}"),
+        "url": format!("https://example.social/@author/{index}"),
+        "account": account,
+        "content": post(index),
+        "created_at": "2024-01-01T00:00:00Z",
+        "emojis": [],
+        "replies_count": 0,
+        "reblogs_count": 0,
+        "favourites_count": 0,
+        "sensitive": false,
+        "spoiler_text": "",
+        "visibility": "public",
+        "media_attachments": [],
+        "mentions": [{
+            "id": "2",
+            "username": "friend",
+            "url": "https://example.social/@friend",
+            "acct": "friend",
+        }],
+        "tags": [
+            { "name": "rust", "url": "https://example.social/tags/rust", "history": [] },
+            { "name": "cosmic", "url": "https://example.social/tags/cosmic", "history": [] },
+        ],
+        "emoji_reactions": [],
+        "quote": false,
+        "bookmarked": false,
+    }))
+    .expect("sample status should deserialize")
+}
+
+fn timeline_content(c: &mut Criterion) {
+    let statuses: Vec<Status> = (0..TIMELINE_LEN).map(status).collect();
+    let mut cache = Cache::new();
+    for status in &statuses {
+        cache.insert_status(status.clone());
+    }
+    let uncached = Cache::new();
+
+    let mut group = c.benchmark_group("200-post timeline");
+    group.bench_function("html2text", |b| {
+        b.iter(|| {
+            for status in &statuses {
+                black_box(
+                    html2text::config::rich()
+                        .string_from_read(black_box(status.content.as_bytes()), 700),
+                );
+            }
+        })
+    });
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            for status in &statuses {
+                black_box(html::parse(
+                    black_box(&status.content),
+                    &status.mentions,
+                    &status.emojis,
+                ));
+            }
+        })
+    });
+    group.bench_function("cache", |b| {
+        b.iter(|| {
+            let mut cache = Cache::new();
+            for status in &statuses {
+                cache.insert_status(black_box(status.clone()));
+            }
+            black_box(cache)
+        })
+    });
+    group.bench_function("view, cached", |b| {
+        b.iter(|| {
+            for status in &statuses {
+                black_box(status::status(status, StatusOptions::all(), &cache));
+            }
+        })
+    });
+    group.bench_function("view, parsing every frame", |b| {
+        b.iter(|| {
+            for status in &statuses {
+                black_box(status::status(status, StatusOptions::all(), &uncached));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, timeline_content);
+criterion_main!(benches);
@@ -4,12 +4,14 @@ view = View
 
 ## Navbar
 home = Home
+deck = Deck
 notifications = Notifications
 search = Search
 favorites = Favorites
 bookmarks = Bookmarks
 hashtags = Hashtags
 lists = Lists
+outbox = Outbox
 explore = Explore
 local = Local
 federated = Federated
@@ -28,6 +30,15 @@ continue = Continue
 confirm-authorization = Confirm authorization
 confirm-authorization-description = Copy the authorization code from the browser and paste it here.
 authorization-code = Authorization code
+waiting-for-browser = Approve the login in your browser. Toot will continue automatically.
+
+## Saved accounts
+unlock-accounts = Unlock saved accounts
+unlock-accounts-description = No system keyring is available, so your accounts are saved in an encrypted file. Enter its passphrase to sign back in.
+set-passphrase = Protect saved accounts
+set-passphrase-description = No system keyring is available. Choose a passphrase to encrypt your saved accounts; you'll need it each time Toot starts.
+passphrase = Passphrase
+unlock = Unlock
 
 ## Context
 about = About
@@ -55,3 +66,85 @@ delete-status = Delete
 delete-status-question = Delete this post?
 delete-status-description = This cannot be undone.
 delete = Delete
+
+## Posts
+show-more = Show more
+show-less = Show less
+show-sensitive-media = Sensitive content · Show
+show-hidden-media = Media hidden · Show
+hide-media = Hide media
+alt-badge = ALT
+no-alt-badge = No ALT
+media-without-alt-text = Media without alt text
+translate = Translate
+translating = Translating…
+translated = Translated by { $provider }
+translated-from = Translated from { $language } by { $provider }
+show-original = Show original
+edited = · edited
+edited-at = Edited { $time }
+posted-via = via { $application }
+
+## Timestamps
+# Dates and times use chrono's strftime syntax:
+# https://docs.rs/chrono/latest/chrono/format/strftime/
+timestamp-now = now
+timestamp-minutes = { $count }m
+timestamp-hours = { $count }h
+timestamp-days = { $count }d
+timestamp-date = %b %-d
+timestamp-date-year = %b %-d, %Y
+timestamp-date-time = %b %-d, %H:%M
+timestamp-full = %A, %B %-d, %Y at %H:%M
+
+## Domain blocks
+block = Block
+block-domain = Block { $domain }
+unblock-domain = Unblock { $domain }
+block-domain-question = Block { $domain }?
+block-domain-description = You won't see posts or notifications from this server, and your followers from it will be removed.
+block-domain-checking = Checking for followers on this server…
+block-domain-no-followers = None of your followers are on this server.
+block-domain-followers = You will lose { $count ->
+        [one] 1 follower
+       *[other] { $count } followers
+    }:
+
+## Profiles
+feature-on-profile = Feature on profile
+
+## Lists
+new-list-title = New list title
+create = Create
+edit = Edit
+save = Save
+pin-to-sidebar = Pin to sidebar
+unpin = Unpin
+back-to-lists = ← Back to lists
+list-settings = List settings
+list-title = Title
+show-replies = Show replies
+replies-followed = Replies to any followed user
+replies-list = Replies to list members
+replies-none = No replies
+exclusive-list = Hide members' posts from Home
+list-members = Members
+remove = Remove
+search-followed-accounts = Search accounts you follow
+add-members = Add members
+add = Add
+add-to-list = Add to list…
+add-to-list-requires-follow = Only accounts you follow can be added to lists.
+delete-list-question = Delete the list "{ $title }"?
+delete-list-description = Its members won't be notified, and you'll still follow them.
+add-to-list-title = Add @{ $acct } to lists
+add-to-list-empty = You haven't created any lists yet.
+done = Done
+
+## Offline
+offline = You're offline, showing saved posts.
+offline-pending = { $count ->
+        [one] 1 action will be sent when you're back online.
+       *[other] { $count } actions will be sent when you're back online.
+    }
+may-not-have-been-sent = The server didn't answer in time, so your post may not have been sent. Check your profile before posting it again.
@@ -1,28 +1,30 @@
 // SPDX-License-Identifier: {{LICENSE}}
 
 use crate::cache::Cache;
-use crate::client::{Client, Session, Sessions};
-use crate::config::TootConfig;
+use crate::client::{Client, Session, Sessions, Software};
+use crate::config::{Pin, TimestampStyle, TootConfig};
+use crate::error::Error;
 use crate::features::compose;
-use crate::features::status::StatusOptions;
+use crate::features::outbox::{self, Action};
+use crate::features::status::{emoji, translate, StatusOptions};
 use crate::features::timeline::{Timeline, TimelineKind};
 use crate::features::{
-    accounts, hashtags, lists, notifications, search, settings, status, timeline,
+    accounts, deck, hashtags, lists, media, notifications, search, settings, status, timeline,
 };
 use crate::fl;
+use crate::oauth::{self, Registration};
+use crate::session_store::{EncryptedFileStore, KeyringStore, SessionStore};
 use cosmic::app::{context_drawer, Core, Task};
 use cosmic::cosmic_config;
 use cosmic::iced::alignment::{Horizontal, Vertical};
 use cosmic::iced::{Length, Subscription};
 use cosmic::widget::about::About;
-use cosmic::widget::image::Handle;
 use cosmic::widget::menu::{ItemHeight, ItemWidth};
 use cosmic::widget::toaster::{Toast, ToastId, Toasts};
 use cosmic::widget::{self, menu, nav_bar};
 use cosmic::{Application, ApplicationExt, Apply, Element};
 use megalodon::entities::{Account, Notification, Status};
-use megalodon::megalodon::PostStatusInputOptions;
-use megalodon::oauth::AppData;
+use megalodon::SearchInputOptions;
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -42,30 +44,39 @@ const SUPPORT: &str = "https://github.com/edfloreshz/toot/issues";
 pub enum Page {
     #[default]
     Home,
+    /// Deck mode's columns, side by side.
+    Deck,
     Notifications,
     Search,
     Favorites,
     Bookmarks,
     Hashtags,
     Lists,
+    /// Actions waiting to be sent once back online.
+    Outbox,
     Explore,
     Local,
     Federated,
+    /// A list or hashtag timeline the user pinned as its own page.
+    Pinned(Pin),
 }
 
 impl Display for Page {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Page::Home => write!(f, "{}", fl!("home")),
+            Page::Deck => write!(f, "{}", fl!("deck")),
             Page::Notifications => write!(f, "{}", fl!("notifications")),
             Page::Search => write!(f, "{}", fl!("search")),
             Page::Favorites => write!(f, "{}", fl!("favorites")),
             Page::Bookmarks => write!(f, "{}", fl!("bookmarks")),
             Page::Hashtags => write!(f, "{}", fl!("hashtags")),
             Page::Lists => write!(f, "{}", fl!("lists")),
+            Page::Outbox => write!(f, "{}", fl!("outbox")),
             Page::Explore => write!(f, "{}", fl!("explore")),
             Page::Local => write!(f, "{}", fl!("local")),
             Page::Federated => write!(f, "{}", fl!("federated")),
+            Page::Pinned(pin) => write!(f, "{}", pin.label()),
         }
     }
 }
@@ -83,6 +94,7 @@ impl Page {
 
     pub fn variants() -> Vec<Page> {
         vec![
+            Self::Deck,
             Self::Home,
             Self::Notifications,
             Self::Search,
@@ -90,6 +102,7 @@ impl Page {
             Self::Bookmarks,
             Self::Hashtags,
             Self::Lists,
+            Self::Outbox,
             Self::Explore,
             Self::Local,
             Self::Federated,
@@ -99,15 +112,18 @@ impl Page {
     pub fn icon(&self) -> &str {
         match self {
             Page::Home => "user-home-symbolic",
+            Page::Deck => "view-grid-symbolic",
             Page::Notifications => "emblem-important-symbolic",
             Page::Search => "folder-saved-search-symbolic",
             Page::Favorites => "starred-symbolic",
             Page::Bookmarks => "bookmark-new-symbolic",
             Page::Hashtags => "lang-include-symbolic",
             Page::Lists => "view-list-symbolic",
+            Page::Outbox => "mail-outbox-symbolic",
             Page::Explore => "find-location-symbolic",
             Page::Local => "network-server-symbolic",
             Page::Federated => "network-workgroup-symbolic",
+            Page::Pinned(pin) => pin.icon(),
         }
     }
 }
@@ -124,11 +140,24 @@ pub struct AppModel {
     handler: Option<cosmic_config::Config>,
     instance: String,
     code: String,
-    registration: Option<AppData>,
+    /// The app registered with the instance being logged into, until the
+    /// authorization code comes back from the browser.
+    registration: Option<Registration>,
+    /// The wait for the browser's redirect during a login, aborted (closing
+    /// the loopback listener) if the user cancels it.
+    authorize_wait: Option<cosmic::iced::task::Handle>,
     mastodon: Client,
     /// All saved accounts (including the currently active one) and which
-    /// index is active, persisted to the keychain as a whole.
+    /// index is active, persisted to `session_store` as a whole.
     sessions: Sessions,
+    /// Where `sessions` is saved. `None` without a keyring until the user
+    /// unlocks or sets up the encrypted file fallback.
+    session_store: Option<Box<dyn SessionStore>>,
+    /// Whether the server could be reached last time it was tried. While
+    /// not, feeds show their cached content without fetching and actions go
+    /// to the outbox.
+    online: bool,
+    outbox: outbox::Outbox,
     cache: Cache,
     /// URLs waiting to be fetched, in priority order (newest posts' images
     /// are enqueued first). Drained at [`MAX_CONCURRENT_IMAGE_FETCHES`] at a time.
@@ -141,6 +170,24 @@ pub struct AppModel {
     /// dialog's character counter. Defaults to Mastodon's standard limit
     /// until the real value is fetched after login.
     max_characters: u32,
+    /// Text typed into Settings' "Blocked domains" input.
+    domain_block_input: String,
+    /// Unsaved edits of Settings' translation service URL and API key,
+    /// saved when submitted or unfocused.
+    translation_url_draft: Option<String>,
+    translation_api_key_draft: Option<String>,
+    /// The image cache's size on disk, shown in Settings. `None` until it's
+    /// been measured.
+    image_cache_usage: Option<u64>,
+    /// Whether an animated emoji is on screen, so animation ticks run.
+    /// Refreshed after every update rather than on every `subscription`
+    /// call, since finding what's on screen walks every visible post.
+    animating: bool,
+    /// An unsaved edit of a profile's private note: (account id, draft).
+    note_draft: Option<(String, String)>,
+    /// The conversation around the status open in the context drawer, by
+    /// id: (replies it follows, replies to it). Empty until fetched.
+    thread: (Vec<String>, Vec<String>),
     home: Timeline,
     notifications: notifications::Notifications,
     explore: Timeline,
@@ -148,6 +195,12 @@ pub struct AppModel {
     federated: Timeline,
     favorites: Timeline,
     bookmarks: Timeline,
+    /// Timelines for the active account's pinned lists/hashtags, each kept
+    /// (and cached, streamed, and counted) independently of the Lists and
+    /// Hashtags pages.
+    pinned: HashMap<Pin, Timeline>,
+    /// Columns shown instead of the single active page in deck mode.
+    deck: deck::Deck,
     hashtags: hashtags::Hashtags,
     lists: lists::Lists,
     search: search::Search,
@@ -156,14 +209,28 @@ pub struct AppModel {
 #[derive(Debug, Clone)]
 pub enum Message {
     Open(String),
+    /// Open a link in the app if it points at a post or profile on the
+    /// fediverse, or else in the browser.
+    OpenLink(String),
+    /// Show a status a link resolved to in the context drawer.
+    OpenStatus(Status),
+    /// Open a mentioned account's profile, by id.
+    OpenMention(String),
+    /// Open a hashtag's timeline, by name.
+    OpenTag(String),
     ToggleContextPage(ContextPage),
+    /// Show a page in the context drawer, leaving it open if it's already
+    /// showing.
+    OpenContextPage(ContextPage),
     ToggleContextDrawer,
     UpdateConfig(TootConfig),
     InstanceEdit,
     RegisterMastodonClient,
     CompleteRegistration,
     StoreMastodonData(Client),
-    StoreRegistration(Option<AppData>),
+    StoreRegistration(Option<Registration>),
+    /// The loopback listener caught the browser's redirect.
+    AuthorizationReceived(String),
     Home(timeline::Message),
     Notifications(notifications::Message),
     Explore(timeline::Message),
@@ -171,18 +238,43 @@ pub enum Message {
     Federated(timeline::Message),
     Favorites(timeline::Message),
     Bookmarks(timeline::Message),
+    Pinned(Pin, timeline::Message),
+    /// Pin a list/hashtag timeline to the sidebar, or unpin it if it already is.
+    TogglePin(Pin),
+    Deck(deck::Message),
     Hashtags(hashtags::Message),
     Lists(lists::Message),
     Search(search::Message),
+    Outbox(outbox::Message),
     Settings(settings::Message),
     Account(accounts::Message),
     Status(status::Message),
     Fetch(Vec<String>),
+    /// Open a status's media in the viewer: (status id, index of the
+    /// attachment to show first).
+    ViewMedia(String, usize),
+    Media(media::Message),
+    /// Show a toast that isn't an error.
+    Notice(String),
+    /// The conversation around a status: (status id, its context).
+    Thread(String, megalodon::entities::Context),
+    /// Expand (`true`) or collapse the content warnings of every post in
+    /// the open thread.
+    ExpandThread(bool),
+    /// A status's translation arrived: (status id, translation).
+    Translated(String, translate::Translation),
+    /// A status couldn't be translated: (status id, why).
+    TranslationFailed(String, String),
     CacheStatus(Status),
     CacheNotification(Notification),
     CacheRelationship(megalodon::entities::Relationship),
     CacheRelationships(Vec<megalodon::entities::Relationship>),
-    CacheHandle(String, Handle),
+    /// A downloaded image, with its frames if it's an animated emoji.
+    CacheHandle(String, crate::cache::Image),
+    /// Periodic tick while animated emoji are on screen: show their next frames.
+    AnimationTick,
+    /// Move relative post times on.
+    ClockTick,
     /// An image download failed; drop it from the in-flight set and let the
     /// next queued one start.
     ImageFetchFailed(String),
@@ -195,14 +287,43 @@ pub enum Message {
     SetAccount(Account),
     /// The instance's max status length, fetched right after login.
     SetMaxCharacters(u32),
+    /// Whether the instance can translate posts.
+    SetServerTranslation(bool),
     /// A status was deleted on the server; remove it from the cache.
     StatusDeleted(String),
+    /// The instances the authenticated user has blocked, fetched after login.
+    SetBlockedDomains(Vec<String>),
+    /// A domain block was added or removed on the server: (domain, blocked).
+    DomainBlockChanged(String, bool),
+    /// The `acct`s of the user's followers on a domain about to be blocked,
+    /// shown in the confirmation dialog: (domain, follower accts).
+    SetDomainFollowers(String, Vec<String>),
+    /// The lists an account is on, for the "Add to list…" dialog:
+    /// (account id, list ids).
+    SetAccountLists(String, HashSet<String>),
+    /// Add or remove the "Add to list…" dialog's account to/from a list:
+    /// (list id, currently a member).
+    ToggleListMembership(String, bool),
     /// A recoverable error to surface to the user as a toast instead of
     /// only logging it and silently dropping the failed action.
     Error(String),
+    /// A request came back 401: the active session's token was revoked or
+    /// expired, so ask the user to log in again.
+    SessionExpired,
+    /// A request couldn't reach the server.
+    Offline,
+    /// A change couldn't reach the server and wasn't queued: go offline and
+    /// show the error, since it won't be retried.
+    OfflineError(String),
+    /// The server answered again after being unreachable.
+    Online,
     CloseToast(ToastId),
     /// Periodic tick: flush every feed's cache to disk if anything changed.
     FlushCache,
+    /// Periodic tick: trim the image cache on disk to its configured limits.
+    PruneImageCache,
+    /// The image cache's measured size on disk, in bytes.
+    ImageCacheUsage(u64),
     None,
 }
 
@@ -219,11 +340,25 @@ pub enum Dialog {
     Compose(compose::State),
     SwitchInstance(String),
     Login(String),
+    /// Enter the passphrase for saved accounts in the encrypted file.
+    Unlock(String),
+    /// Choose a passphrase for a new encrypted file of saved accounts.
+    SetPassphrase(String),
+    /// Waiting for the browser to redirect back to the loopback listener.
+    Authorize,
     Code(String),
     Logout,
     DeleteStatus(String),
-    /// Show an image attachment full-size: (cached preview URL, original URL).
-    Image(String, String),
+    /// Confirm blocking a domain: (domain, followers on it that would be
+    /// lost — `None` while still being checked).
+    BlockDomain(String, Option<Vec<String>>),
+    /// Confirm deleting a list: (list id, title).
+    DeleteList(String, String),
+    /// Pick which lists an account belongs to: (account, ids of the lists it's
+    /// already on — `None` while still being fetched).
+    AddToList(Account, Option<HashSet<String>>),
+    /// Browse a post's media full-window.
+    Media(media::Viewer),
 }
 
 pub struct Flags {
@@ -250,40 +385,35 @@ impl Application for AppModel {
 
         let instance = instance(flags.config.server.clone());
 
-        let sessions = match keytar::get_password(Self::APP_ID, "mastodon-data") {
-            Ok(data) if data.success => Sessions::parse(&data.password).unwrap_or_default(),
-            Ok(_) => Sessions::default(),
+        let (session_store, sessions) = match KeyringStore.load() {
+            Ok(sessions) => (
+                Some(Box::new(KeyringStore) as Box<dyn SessionStore>),
+                sessions,
+            ),
             Err(err) => {
-                tracing::error!("{err}");
-                Sessions::default()
+                tracing::warn!("{err}");
+                (None, None)
             }
         };
+        // Nothing in the keyring but an encrypted file from a run without
+        // one: ask for its passphrase once the window is up.
+        let unlock = sessions.is_none() && EncryptedFileStore::exists();
+        let sessions = sessions.unwrap_or_default();
 
         let mastodon = match sessions.active_session() {
-            Some(session) => Client::new(session.base_url.clone(), Some(session.token.clone())),
-            None => Client::new(instance.clone(), None),
+            Some(session) => Client::new(
+                session.software,
+                session.base_url.clone(),
+                Some(session.token.clone()),
+            ),
+            None => Client::new(Software::default(), instance.clone(), None),
         };
 
-        let variants = (!mastodon.is_authenticated())
-            .then(Page::public_variants)
-            .unwrap_or_else(Page::variants);
-
-        for page in variants {
-            let id = nav
-                .insert()
-                .text(page.to_string())
-                .icon(widget::icon::from_name(page.icon()))
-                .data::<Page>(page.clone())
-                .id();
-
-            if page == Page::default() {
-                nav.activate(id);
-            }
-
-            if mastodon.is_authenticated() && page == Page::Explore {
-                nav.divider_above_set(id, true);
-            }
-        }
+        let pins = match sessions.active_session() {
+            Some(session) => flags.config.pins(&session.base_url).to_vec(),
+            None => Vec::new(),
+        };
+        populate_nav(&mut nav, &mastodon, &pins, flags.config.deck_mode);
 
         let about = About::default()
             .name(fl!("app-title"))
@@ -299,26 +429,48 @@ impl Application for AppModel {
             nav,
             context_page: ContextPage::default(),
             key_binds: HashMap::new(),
-            dialog_pages: VecDeque::new(),
+            dialog_pages: if unlock {
+                VecDeque::from([Dialog::Unlock(String::new())])
+            } else {
+                VecDeque::new()
+            },
             dialog_editor: widget::text_editor::Content::default(),
             config: flags.config.clone(),
             handler: flags.handler,
             instance: flags.config.server,
             code: String::new(),
             registration: None,
+            authorize_wait: None,
             mastodon: mastodon.clone(),
             sessions,
+            session_store,
+            online: true,
+            outbox: outbox::Outbox::new(mastodon.clone()),
             cache: {
                 let mut cache = Cache::new();
                 cache.hide_boosts = flags.config.hide_boosts;
                 cache.hide_replies = flags.config.hide_replies;
                 cache.feed_density = flags.config.feed_density;
+                cache.play_animations = flags.config.play_animated_emoji;
+                cache.sensitive_media = flags.config.sensitive_media;
+                cache.expand_warnings = flags.config.expand_content_warnings;
+                cache.flag_missing_alt_text = flags.config.flag_missing_alt_text;
+                cache.translation_service = !flags.config.translation_url.trim().is_empty();
+                cache.timestamp_style = flags.config.timestamp_style;
+                cache.software = mastodon.software;
                 cache
             },
             image_queue: VecDeque::new(),
             image_inflight: HashSet::new(),
             toasts: Toasts::new(Message::CloseToast),
             max_characters: 500,
+            domain_block_input: String::new(),
+            translation_url_draft: None,
+            translation_api_key_draft: None,
+            image_cache_usage: None,
+            animating: false,
+            thread: (Vec::new(), Vec::new()),
+            note_draft: None,
             home: Timeline::new(mastodon.clone(), TimelineKind::Home),
             notifications: notifications::Notifications::new(mastodon.clone()),
             explore: Timeline::new(mastodon.clone(), TimelineKind::Public),
@@ -326,12 +478,15 @@ impl Application for AppModel {
             federated: Timeline::new(mastodon.clone(), TimelineKind::Federated),
             favorites: Timeline::new(mastodon.clone(), TimelineKind::Favorites),
             bookmarks: Timeline::new(mastodon.clone(), TimelineKind::Bookmarks),
+            pinned: HashMap::new(),
+            deck: deck::Deck::new(mastodon.clone()),
             hashtags: hashtags::Hashtags::new(mastodon.clone()),
             lists: lists::Lists::new(mastodon.clone()),
             search: search::Search::new(mastodon.clone()),
         };
 
         app.nav.activate_position(0);
+        app.load_cached_relationships();
 
         let mut tasks = vec![
             app.update_title(),
@@ -342,7 +497,13 @@ impl Application for AppModel {
             app.federated.load_cached(),
             app.favorites.load_cached(),
             app.bookmarks.load_cached(),
+            app.sync_pinned(),
+            app.load_deck(),
+            // Whatever was still queued when the app last closed.
+            app.outbox.replay(),
+            app.prune_image_cache(),
         ];
+        app.update_outbox_text();
         if mastodon.is_authenticated() {
             tasks.push(fetch_session_info(mastodon));
         }
@@ -420,7 +581,7 @@ impl Application for AppModel {
     fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<Self::Message> {
         self.nav.activate(id);
         let mut tasks = vec![];
-        match self.nav.data::<Page>(id).unwrap() {
+        match self.nav.data::<Page>(id).cloned().unwrap() {
             Page::Home => tasks.push(
                 self.home
                     .update(timeline::Message::SetClient(self.mastodon.clone())),
@@ -452,6 +613,7 @@ impl Application for AppModel {
                 self.lists
                     .update(lists::Message::SetClient(self.mastodon.clone())),
             ),
+            Page::Deck | Page::Outbox => (),
             Page::Explore => tasks.push(
                 self.explore
                     .update(timeline::Message::SetClient(self.mastodon.clone())),
@@ -464,8 +626,17 @@ impl Application for AppModel {
                 self.federated
                     .update(timeline::Message::SetClient(self.mastodon.clone())),
             ),
+            Page::Pinned(pin) => {
+                if let Some(timeline) = self.pinned.get_mut(&pin) {
+                    timeline.mark_read();
+                    tasks.push(timeline.update(timeline::Message::SetClient(self.mastodon.clone())));
+                }
+                self.update_pin_text(&pin);
+            }
         };
         tasks.push(self.update_title());
+        tasks.push(self.fetch_visible_images());
+        self.refresh_animating();
         Task::batch(tasks)
     }
 
@@ -490,9 +661,21 @@ impl Application for AppModel {
                     .title(self.context_page.title())
             }
             ContextPage::Settings => {
-                let content =
-                    settings::view(&self.config, &self.sessions.sessions, self.sessions.active)
-                        .map(Message::Settings);
+                let content = settings::view(
+                    &self.config,
+                    &self.sessions.sessions,
+                    self.sessions.active,
+                    &self.cache.blocked_domains,
+                    &self.domain_block_input,
+                    self.translation_url_draft
+                        .as_deref()
+                        .unwrap_or(&self.config.translation_url),
+                    self.translation_api_key_draft
+                        .as_deref()
+                        .unwrap_or(&self.sessions.translation_api_key),
+                    self.image_cache_usage,
+                )
+                .map(Message::Settings);
                 context_drawer::context_drawer(content, Message::ToggleContextDrawer)
                     .title(self.context_page.title())
             }
@@ -500,11 +683,11 @@ impl Application for AppModel {
     }
 
     fn dialog(&self) -> Option<Element<'_, Self::Message>> {
-        // The image viewer is a full-window overlay (like cosmic-files' gallery
+        // The media viewer is a full-window overlay (like cosmic-files' gallery
         // view), not a small centered dialog box, so it's built and returned
         // before the `widget::dialog()`-based match below.
-        if let Some(Dialog::Image(preview_url, original_url)) = self.dialog_pages.front() {
-            return Some(self.view_image(preview_url.clone(), original_url.clone()));
+        if let Some(Dialog::Media(viewer)) = self.dialog_pages.front() {
+            return Some(viewer.view(&self.cache).map(Message::Media));
         }
 
         let dialog_page = self.dialog_pages.front()?;
@@ -529,10 +712,18 @@ impl Application for AppModel {
             }
             Dialog::SwitchInstance(instance) => self.switch_instance(instance.clone()),
             Dialog::Login(instance) => self.login(instance.clone()),
+            Dialog::Authorize => self.authorize(),
+            Dialog::Unlock(passphrase) => self.unlock(passphrase.clone()),
+            Dialog::SetPassphrase(passphrase) => self.set_passphrase(passphrase.clone()),
             Dialog::Code(code) => self.code(code.clone()),
             Dialog::Logout => self.logout(),
             Dialog::DeleteStatus(id) => self.delete_status(id.clone()),
-            Dialog::Image(..) => unreachable!("handled above"),
+            Dialog::BlockDomain(domain, followers) => self.block_domain(domain, followers.as_ref()),
+            Dialog::DeleteList(_, title) => self.delete_list(title),
+            Dialog::AddToList(account, memberships) => {
+                self.add_to_list(account, memberships.as_ref())
+            }
+            Dialog::Media(..) => unreachable!("handled above"),
         };
 
         Some(dialog.into())
@@ -545,6 +736,7 @@ impl Application for AppModel {
 
         if self.core.window.show_context {
             self.core.window.show_context = false;
+            self.refresh_animating();
         }
 
         Task::none()
@@ -562,6 +754,10 @@ impl Application for AppModel {
         let content = match self.nav.active_data::<Page>() {
             Some(page) => match page {
                 Page::Home => self.home.view(&self.cache).map(Message::Home),
+                Page::Deck => self
+                    .deck
+                    .view(&self.cache, self.lists.lists())
+                    .map(Message::Deck),
                 Page::Notifications => self
                     .notifications
                     .view(&self.cache)
@@ -573,7 +769,14 @@ impl Application for AppModel {
                 Page::Bookmarks => self.bookmarks.view(&self.cache).map(Message::Bookmarks),
                 Page::Hashtags => self.hashtags.view(&self.cache).map(Message::Hashtags),
                 Page::Lists => self.lists.view(&self.cache).map(Message::Lists),
+                Page::Outbox => self.outbox.view().map(Message::Outbox),
                 Page::Search => self.search.view(&self.cache).map(Message::Search),
+                Page::Pinned(pin) => match self.pinned.get(pin) {
+                    Some(timeline) => timeline
+                        .view(&self.cache)
+                        .map(move |message| Message::Pinned(pin.clone(), message)),
+                    None => widget::text("Select a page").into(),
+                },
             },
             None => widget::text("Select a page").into(),
         }
@@ -583,7 +786,7 @@ impl Application for AppModel {
         .align_x(Horizontal::Center)
         .align_y(Vertical::Center);
 
-        widget::toaster(&self.toasts, content)
+        widget::toaster(&self.toasts, self.with_offline_banner(content.into()))
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
@@ -593,6 +796,12 @@ impl Application for AppModel {
             .map(|update| Message::UpdateConfig(update.config))];
 
         match self.nav.active_data::<Page>() {
+            // Offline, feeds keep their cached content instead of fetching;
+            // only the reconnect probe runs.
+            _ if !self.online => {
+                subscriptions.push(outbox::reconnect(self.mastodon.clone()))
+            }
+            Some(Page::Deck) => subscriptions.push(self.deck.subscription().map(Message::Deck)),
             Some(Page::Home) => subscriptions.push(self.home.subscription().map(Message::Home)),
             Some(Page::Notifications) => subscriptions.push(
                 self.notifications
@@ -612,6 +821,7 @@ impl Application for AppModel {
                 subscriptions.push(self.hashtags.subscription().map(Message::Hashtags))
             }
             Some(Page::Lists) => subscriptions.push(self.lists.subscription().map(Message::Lists)),
+            Some(Page::Outbox) => (),
             Some(Page::Explore) => {
                 subscriptions.push(self.explore.subscription().map(Message::Explore))
             }
@@ -619,28 +829,84 @@ impl Application for AppModel {
             Some(Page::Federated) => {
                 subscriptions.push(self.federated.subscription().map(Message::Federated))
             }
+            Some(Page::Pinned(pin)) => {
+                if let Some(timeline) = self.pinned.get(pin) {
+                    let pin = pin.clone();
+                    subscriptions.push(
+                        timeline
+                            .subscription()
+                            .with(pin)
+                            .map(|(pin, message)| Message::Pinned(pin, message)),
+                    );
+                }
+            }
             None => (),
         };
 
-        if self.mastodon.is_authenticated() {
+        if self.online && self.mastodon.is_authenticated() {
             subscriptions.push(crate::streaming::stream_user_events(self.mastodon.clone()));
+            // Pinned pages stream even while inactive, to keep their unread
+            // counts current.
+            for pin in self.pinned.keys() {
+                subscriptions.push(
+                    crate::streaming::stream_timeline(self.mastodon.clone(), pin.kind())
+                        .with(pin.clone())
+                        .map(|(pin, message)| Message::Pinned(pin, message)),
+                );
+            }
         }
 
         subscriptions.push(
             cosmic::iced::time::every(std::time::Duration::from_secs(30))
                 .map(|_| Message::FlushCache),
         );
+        subscriptions.push(
+            cosmic::iced::time::every(std::time::Duration::from_secs(600))
+                .map(|_| Message::PruneImageCache),
+        );
+        if self.cache.timestamp_style == TimestampStyle::Relative {
+            subscriptions.push(
+                cosmic::iced::time::every(std::time::Duration::from_secs(30))
+                    .map(|_| Message::ClockTick),
+            );
+        }
+        if self.animating {
+            subscriptions.push(
+                cosmic::iced::time::every(std::time::Duration::from_millis(40))
+                    .map(|_| Message::AnimationTick),
+            );
+        }
+        if let Some(Dialog::Media(viewer)) = self.dialog_pages.front() {
+            subscriptions.push(viewer.subscription().map(Message::Media));
+        }
 
         Subscription::batch(subscriptions)
     }
 
     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
+        // Ticks change neither what's on screen nor which images animate.
+        let refresh_animating = !matches!(message, Message::AnimationTick | Message::ClockTick);
         let mut tasks = vec![];
         match message {
             Message::Home(message) => {
+                // Streamed updates also feed the deck's Home column, if any.
+                if let timeline::Message::PrependStatus(_) | timeline::Message::DeleteStatus(_) =
+                    &message
+                {
+                    tasks.push(self.deck.update(deck::Message::Column(
+                        deck::ColumnKind::Home,
+                        deck::ColumnMessage::Timeline(message.clone()),
+                    )));
+                }
                 tasks.push(self.home.update(message));
             }
             Message::Notifications(message) => {
+                if let notifications::Message::PrependNotification(_) = &message {
+                    tasks.push(self.deck.update(deck::Message::Column(
+                        deck::ColumnKind::Notifications,
+                        deck::ColumnMessage::Notifications(message.clone()),
+                    )));
+                }
                 tasks.push(self.notifications.update(message));
             }
             Message::Explore(message) => {
@@ -658,10 +924,62 @@ impl Application for AppModel {
             Message::Bookmarks(message) => {
                 tasks.push(self.bookmarks.update(message));
             }
+            Message::Pinned(pin, message) => {
+                let active = self.nav.active_data::<Page>() == Some(&Page::Pinned(pin.clone()));
+                if let Some(timeline) = self.pinned.get_mut(&pin) {
+                    tasks.push(timeline.update(message));
+                    if active {
+                        timeline.mark_read();
+                    }
+                    self.update_pin_text(&pin);
+                }
+            }
+            Message::TogglePin(pin) => tasks.push(self.toggle_pin(pin)),
+            Message::Deck(message) => {
+                let changes_layout = deck::Deck::changes_layout(&message);
+                tasks.push(self.deck.update(message));
+                if changes_layout {
+                    let mut layouts = self.config.deck.clone();
+                    layouts.insert(self.mastodon.base_url.clone(), self.deck.layout());
+                    match self.handler {
+                        Some(ref handler) => {
+                            if let Err(err) = self.config.set_deck(handler, layouts) {
+                                tracing::error!("{err}");
+                            }
+                        }
+                        None => self.config.deck = layouts,
+                    }
+                }
+            }
             Message::Hashtags(message) => {
                 tasks.push(self.hashtags.update(message));
             }
             Message::Lists(message) => {
+                // Keep an open "Add to list…" dialog's checkboxes in sync with
+                // membership changes, whichever page made them.
+                match &message {
+                    lists::Message::MemberAdded(list_id, account) => {
+                        self.update_list_membership(list_id, &account.id, true)
+                    }
+                    lists::Message::MemberRemoved(list_id, account_id) => {
+                        self.update_list_membership(list_id, account_id, false)
+                    }
+                    lists::Message::Saved(list) => {
+                        self.rename_list_pin(&list.id, &list.title);
+                    }
+                    lists::Message::Deleted(id) => {
+                        let pin = self
+                            .config
+                            .pins(&self.mastodon.base_url)
+                            .iter()
+                            .find(|pin| matches!(pin, Pin::List(pin_id, _) if pin_id == id))
+                            .cloned();
+                        if let Some(pin) = pin {
+                            tasks.push(self.toggle_pin(pin));
+                        }
+                    }
+                    _ => (),
+                }
                 tasks.push(self.lists.update(message));
             }
             Message::Search(message) => {
@@ -684,6 +1002,23 @@ impl Application for AppModel {
                         }
                     }
                 }
+                settings::Message::ToggleDeckMode(enabled) => {
+                    match self.handler {
+                        Some(ref handler) => {
+                            if let Err(err) = self.config.set_deck_mode(handler, enabled) {
+                                tracing::error!("{err}");
+                            }
+                        }
+                        None => self.config.deck_mode = enabled,
+                    }
+                    if enabled && self.mastodon.is_authenticated() && !self.lists.is_loaded() {
+                        tasks.push(self.lists.load());
+                    }
+                    // Add or remove the Deck page, and open it when turned on.
+                    self.update_navbar();
+                    let id = self.nav_id(&Page::Deck).unwrap_or(self.nav.active());
+                    tasks.push(self.on_nav_select(id));
+                }
                 settings::Message::SetFeedDensity(density) => {
                     self.cache.feed_density = density;
                     if let Some(ref handler) = self.handler {
@@ -692,6 +1027,80 @@ impl Application for AppModel {
                         }
                     }
                 }
+                settings::Message::SetTimestampStyle(style) => {
+                    self.cache.timestamp_style = style;
+                    self.cache.now = chrono::Utc::now();
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_timestamp_style(handler, style) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                }
+                settings::Message::SetSensitiveMedia(sensitive) => {
+                    self.cache.sensitive_media = sensitive;
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_sensitive_media(handler, sensitive) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                }
+                settings::Message::ToggleExpandContentWarnings(expand) => {
+                    self.cache.set_expand_warnings(expand);
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_expand_content_warnings(handler, expand) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                }
+                settings::Message::TranslationUrlInput(url) => {
+                    self.translation_url_draft = Some(url);
+                }
+                settings::Message::TranslationApiKeyInput(key) => {
+                    self.translation_api_key_draft = Some(key);
+                }
+                settings::Message::SaveTranslationUrl => {
+                    if let Some(url) = self.translation_url_draft.take() {
+                        self.cache.translation_service = !url.trim().is_empty();
+                        if let Some(ref handler) = self.handler {
+                            if let Err(err) = self.config.set_translation_url(handler, url) {
+                                tracing::error!("{err}");
+                            }
+                        }
+                    }
+                }
+                settings::Message::SaveTranslationApiKey => {
+                    if let Some(key) = self.translation_api_key_draft.take() {
+                        self.sessions.translation_api_key = key;
+                        if let Err(err) = self.persist_sessions() {
+                            tasks.push(cosmic::task::message(Message::Error(format!(
+                                "Couldn't save the API key: {err}"
+                            ))));
+                        }
+                    }
+                }
+                settings::Message::ToggleFlagMissingAltText(flag) => {
+                    self.cache.flag_missing_alt_text = flag;
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_flag_missing_alt_text(handler, flag) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                }
+                settings::Message::TogglePlayAnimatedEmoji(play) => {
+                    self.cache.play_animations = play;
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_play_animated_emoji(handler, play) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                    if play {
+                        // Animated emoji already loaded were decoded still;
+                        // reload the ones on screen (from disk) for their
+                        // frames.
+                        self.cache.clear_animated_handles();
+                        tasks.push(self.fetch_visible_images());
+                    }
+                }
                 settings::Message::SetThemeMode(mode) => {
                     if let Some(ref handler) = self.handler {
                         if let Err(err) = self.config.set_theme_mode(handler, mode) {
@@ -709,100 +1118,148 @@ impl Application for AppModel {
                         )))),
                     );
                 }
+                settings::Message::DomainBlockInput(input) => self.domain_block_input = input,
+                settings::Message::AddDomainBlock => {
+                    let domain = self
+                        .domain_block_input
+                        .trim()
+                        .trim_start_matches("https://")
+                        .trim_start_matches("http://")
+                        .trim_end_matches('/')
+                        .to_lowercase();
+                    if !domain.is_empty() {
+                        self.domain_block_input.clear();
+                        tasks.push(self.confirm_block_domain(domain));
+                    }
+                }
+                settings::Message::RemoveDomainBlock(domain) => {
+                    tasks.push(update_domain_block(self.mastodon.clone(), domain, true));
+                }
+                settings::Message::SetImageCacheLimit(limit) => {
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_image_cache_limit(handler, limit) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                    tasks.push(self.prune_image_cache());
+                }
+                settings::Message::SetImageCacheAge(age) => {
+                    if let Some(ref handler) = self.handler {
+                        if let Err(err) = self.config.set_image_cache_age(handler, age) {
+                            tracing::error!("{err}");
+                        }
+                    }
+                    tasks.push(self.prune_image_cache());
+                }
+                settings::Message::ClearImageCache => {
+                    self.image_cache_usage = None;
+                    let clear = cosmic::task::future(async move {
+                        let cleared =
+                            tokio::task::spawn_blocking(crate::persistence::clear_image_cache)
+                                .await
+                                .map_err(std::io::Error::other)
+                                .and_then(|cleared| cleared);
+                        match cleared {
+                            Ok(()) => Message::None,
+                            Err(err) => failure("Couldn't clear the image cache", err),
+                        }
+                    });
+                    // Whatever couldn't be deleted still counts, so measure
+                    // the cache again rather than assume it's empty.
+                    tasks.push(clear.chain(self.prune_image_cache()));
+                }
             },
             Message::Account(message) => match message {
                 accounts::Message::Follow(id, following) => {
-                    let mastodon = self.mastodon.clone();
-                    tasks.push(cosmic::task::future(async move {
-                        let result = if following {
-                            mastodon.unfollow_account(id).await
-                        } else {
-                            mastodon.follow_account(id, None).await
-                        };
-                        match result {
-                            Ok(response) => Message::CacheRelationship(response.json),
-                            Err(err) => Message::Error(format!("Couldn't update follow: {err}")),
-                        }
-                    }))
+                    tasks.push(self.dispatch(Action::Follow(id, following)))
                 }
                 accounts::Message::Mute(id, muting) => {
-                    let mastodon = self.mastodon.clone();
-                    tasks.push(cosmic::task::future(async move {
-                        let result = if muting {
-                            mastodon.unmute_account(id).await
-                        } else {
-                            mastodon.mute_account(id, true).await
-                        };
-                        match result {
-                            Ok(response) => Message::CacheRelationship(response.json),
-                            Err(err) => Message::Error(format!("Couldn't update mute: {err}")),
-                        }
-                    }))
+                    tasks.push(self.dispatch(Action::Mute(id, muting)))
                 }
                 accounts::Message::Block(id, blocking) => {
+                    tasks.push(self.dispatch(Action::Block(id, blocking)))
+                }
+                accounts::Message::BlockDomain(domain, true) => {
+                    tasks.push(update_domain_block(self.mastodon.clone(), domain, true));
+                }
+                accounts::Message::BlockDomain(domain, false) => {
+                    tasks.push(self.confirm_block_domain(domain));
+                }
+                accounts::Message::Endorse(id, endorsed) => {
+                    tasks.push(self.dispatch(Action::Endorse(id, endorsed)))
+                }
+                accounts::Message::AddToList(account) => {
                     let mastodon = self.mastodon.clone();
+                    let id = account.id.clone();
+                    self.dialog_pages
+                        .push_back(Dialog::AddToList(account, None));
+                    if !self.lists.is_loaded() {
+                        tasks.push(self.lists.load());
+                    }
                     tasks.push(cosmic::task::future(async move {
-                        let result = if blocking {
-                            mastodon.unblock_account(id).await
-                        } else {
-                            mastodon.block_account(id).await
-                        };
-                        match result {
-                            Ok(response) => Message::CacheRelationship(response.json),
-                            Err(err) => Message::Error(format!("Couldn't update block: {err}")),
+                        match mastodon.get_account_lists(id.clone()).await {
+                            Ok(response) => Message::SetAccountLists(
+                                id,
+                                response.json.into_iter().map(|list| list.id).collect(),
+                            ),
+                            Err(err) => failure("Couldn't load lists", err),
                         }
-                    }))
+                    }));
+                }
+                accounts::Message::NoteInput(id, text) => self.note_draft = Some((id, text)),
+                accounts::Message::SaveNote(id) => {
+                    if let Some((_, note)) = self.note_draft.take_if(|(draft_id, _)| *draft_id == id)
+                    {
+                        tasks.push(self.dispatch(Action::Note(id, note)))
+                    }
                 }
                 _ => tasks.push(accounts::update(message)),
             },
             Message::Status(message) => match message {
                 status::Message::Favorite(status_id, favorited) => {
-                    let mastodon = self.mastodon.clone();
-                    tasks.push(cosmic::task::future(async move {
-                        let result = if favorited {
-                            mastodon.unfavourite_status(status_id).await
-                        } else {
-                            mastodon.favourite_status(status_id).await
-                        };
-                        match result {
-                            Ok(response) => Message::CacheStatus(response.json),
-                            Err(err) => Message::Error(format!("Couldn't update favorite: {err}")),
-                        }
-                    }))
+                    tasks.push(self.dispatch(Action::Favorite(status_id, favorited)))
+                }
+                status::Message::React(status_id, emoji, reacted) => {
+                    tasks.push(self.dispatch(Action::React(status_id, emoji, reacted)))
                 }
                 status::Message::Boost(status_id, boosted) => {
-                    let mastodon = self.mastodon.clone();
-                    tasks.push(cosmic::task::future(async move {
-                        let result = if boosted {
-                            mastodon.unreblog_status(status_id).await
-                        } else {
-                            mastodon.reblog_status(status_id).await
-                        };
-                        match result {
-                            Ok(response) => Message::CacheStatus(response.json),
-                            Err(err) => Message::Error(format!("Couldn't update boost: {err}")),
-                        }
-                    }))
+                    tasks.push(self.dispatch(Action::Boost(status_id, boosted)))
                 }
                 status::Message::Bookmark(status_id, bookmarked) => {
-                    let mastodon = self.mastodon.clone();
-                    tasks.push(cosmic::task::future(async move {
-                        let result = if bookmarked {
-                            mastodon.unbookmark_status(status_id).await
-                        } else {
-                            mastodon.bookmark_status(status_id).await
-                        };
-                        match result {
-                            Ok(response) => Message::CacheStatus(response.json),
-                            Err(err) => Message::Error(format!("Couldn't update bookmark: {err}")),
+                    tasks.push(self.dispatch(Action::Bookmark(status_id, bookmarked)))
+                }
+                status::Message::ShowContent(status_id, shown) => {
+                    self.cache.show_content(status_id, shown);
+                }
+                status::Message::ShowMedia(status_id, true) => {
+                    self.cache.shown_media.insert(status_id);
+                }
+                status::Message::ShowMedia(status_id, false) => {
+                    self.cache.shown_media.remove(&status_id);
+                }
+                status::Message::Translate(status_id, true) => {
+                    if self.cache.has_translation(&status_id) {
+                        self.cache.shown_translations.insert(status_id);
+                    } else if let Some(status) = self.cache.statuses.get(&status_id).cloned() {
+                        if self.cache.translating.insert(status_id) {
+                            tasks.push(self.translate(status));
                         }
-                    }))
+                    }
+                }
+                status::Message::Translate(status_id, false) => {
+                    self.cache.shown_translations.remove(&status_id);
+                }
+                status::Message::ShowAltText(media_id, true) => {
+                    self.cache.shown_alt_text.insert(media_id);
+                }
+                status::Message::ShowAltText(media_id, false) => {
+                    self.cache.shown_alt_text.remove(&media_id);
                 }
                 _ => tasks.push(status::update(message)),
             },
-            Message::CacheHandle(url, handle) => {
+            Message::CacheHandle(url, image) => {
                 self.image_inflight.remove(&url);
-                self.cache.insert_handle(url, handle);
+                self.cache.insert_image(url, image);
                 tasks.push(self.drain_image_queue());
             }
             Message::ImageFetchFailed(url) => {
@@ -812,6 +1269,38 @@ impl Application for AppModel {
             Message::CacheStatus(status) => {
                 self.cache.insert_status(status.clone());
             }
+            Message::Thread(id, context) => {
+                if self.context_page == ContextPage::Status(id) {
+                    let urls = context
+                        .ancestors
+                        .iter()
+                        .chain(&context.descendants)
+                        .flat_map(crate::cache::extract_status_images)
+                        .collect();
+                    let ids = |statuses: &[Status]| {
+                        statuses.iter().map(|status| status.id.clone()).collect()
+                    };
+                    self.thread = (ids(&context.ancestors), ids(&context.descendants));
+                    for status in context.ancestors.into_iter().chain(context.descendants) {
+                        self.cache.insert_status(status);
+                    }
+                    tasks.push(cosmic::task::message(Message::Fetch(urls)));
+                }
+            }
+            Message::Translated(id, translation) => {
+                self.cache.insert_translation(id, translation);
+            }
+            Message::TranslationFailed(id, err) => {
+                self.cache.translating.remove(&id);
+                tasks.push(cosmic::task::message(Message::Error(format!(
+                    "Couldn't translate post: {err}"
+                ))));
+            }
+            Message::ExpandThread(expand) => {
+                for id in self.thread_ids() {
+                    self.cache.show_content(id, expand);
+                }
+            }
             Message::CacheNotification(notification) => {
                 self.cache.insert_notification(notification.clone());
             }
@@ -825,8 +1314,9 @@ impl Application for AppModel {
             }
             Message::Fetch(urls) => {
                 for url in urls {
-                    if !self.cache.handles.contains_key(&url)
-                        && !self.image_inflight.contains(&url)
+                    if self.cache.handles.contains_key(&url) {
+                        self.cache.touch_handle(&url);
+                    } else if !self.image_inflight.contains(&url)
                         && !self.image_queue.contains(&url)
                     {
                         self.image_queue.push_back(url);
@@ -844,53 +1334,85 @@ impl Application for AppModel {
             }
             Message::RegisterMastodonClient => {
                 let instance = self.instance();
+                let saved = self.sessions.apps.get(&instance).cloned();
                 tasks.push(cosmic::task::future(async move {
-                    let client = Client::new(instance, None);
-                    let options = megalodon::megalodon::AppInputOptions {
-                        scopes: Some(
-                            ["read", "write", "follow"]
-                                .into_iter()
-                                .map(String::from)
-                                .collect(),
-                        ),
-                        ..Default::default()
-                    };
-                    match client.register_app("Toot".to_string(), &options).await {
-                        Ok(app_data) => Message::StoreRegistration(Some(app_data)),
-                        Err(err) => Message::Error(format!("Couldn't register with server: {err}")),
+                    match oauth::register(instance, saved).await {
+                        Ok(registration) => Message::StoreRegistration(Some(registration)),
+                        Err(err) => action_failure("Couldn't register with server", err),
                     }
                 }));
             }
-            Message::StoreRegistration(registration) => {
-                if let Some(ref registration) = registration {
-                    if let Some(url) = registration.url.clone() {
+            Message::StoreRegistration(mut registration) => {
+                if let Some(ref mut registration) = registration {
+                    if let Some(url) = registration.authorize_url() {
                         if let Err(err) = open::that_detached(url) {
                             tracing::error!("{err}");
                         }
                     }
+                    // The wait owns the listener, so aborting it stops listening.
+                    let dialog = match registration.loopback.take() {
+                        Some(loopback) => {
+                            let state = (registration.software != Software::Misskey)
+                                .then(|| registration.state.clone());
+                            let (wait, handle) = cosmic::task::future(async move {
+                                match loopback.wait_for_code(state.as_deref()).await {
+                                    Ok(code) => Message::AuthorizationReceived(code),
+                                    Err(err) => action_failure("Couldn't complete login", err),
+                                }
+                            })
+                            .abortable();
+                            if let Some(previous) = self.authorize_wait.replace(handle) {
+                                previous.abort();
+                            }
+                            tasks.push(wait);
+                            Dialog::Authorize
+                        }
+                        None => Dialog::Code(String::new()),
+                    };
+                    tasks.push(self.update(Message::Dialog(DialogAction::Open(dialog))));
                 }
                 self.registration = registration;
             }
+            Message::AuthorizationReceived(code) => {
+                // Ignore a late callback after the user cancelled the login.
+                if matches!(self.dialog_pages.front(), Some(Dialog::Authorize)) {
+                    self.authorize_wait = None;
+                    self.dialog_pages.pop_front();
+                    self.code = code;
+                    tasks.push(self.update(Message::CompleteRegistration));
+                }
+            }
             Message::CompleteRegistration => {
                 if let Some(registration) = self.registration.take() {
+                    self.sessions
+                        .apps
+                        .insert(registration.base_url.clone(), registration.app.clone());
                     let code = self.code.clone();
-                    let instance = self.instance();
+                    let instance = registration.base_url.clone();
                     let task = cosmic::task::future(async move {
-                        let client = Client::new(instance.clone(), None);
-                        match client
-                            .fetch_access_token(
-                                registration.client_id,
-                                registration.client_secret,
-                                code,
-                                megalodon::default::NO_REDIRECT.to_string(),
-                            )
-                            .await
-                        {
+                        let software = registration.software;
+                        let token = match &registration.pkce {
+                            Some(pkce) => {
+                                oauth::fetch_token_with_pkce(&registration, pkce, &code).await
+                            }
+                            None => Client::new(software, instance.clone(), None)
+                                .fetch_access_token(
+                                    registration.app.client_id.clone(),
+                                    registration.app.client_secret.clone(),
+                                    code,
+                                    registration.app.redirect_uri.clone(),
+                                )
+                                .await
+                                .map(|token| token.access_token)
+                                .map_err(Into::into),
+                        };
+                        match token {
                             Ok(token) => Message::StoreMastodonData(Client::new(
+                                software,
                                 instance,
-                                Some(token.access_token),
+                                Some(token),
                             )),
-                            Err(err) => Message::Error(format!("Couldn't complete login: {err}")),
+                            Err(err) => action_failure("Couldn't complete login", err),
                         }
                     });
                     tasks.push(task);
@@ -900,14 +1422,21 @@ impl Application for AppModel {
                 let session = Session {
                     base_url: mastodon.base_url.clone(),
                     token: mastodon.token.clone().unwrap_or_default(),
+                    software: mastodon.software,
                 };
                 self.sessions.upsert_active(session);
                 match self.persist_sessions() {
                     Ok(_) => {
                         self.mastodon = mastodon;
+                        self.cache.software = self.mastodon.software;
+                        tasks.push(self.sync_pinned());
+                        tasks.push(self.load_deck());
                         self.update_navbar();
                         tasks.push(self.on_nav_select(self.nav.active()));
                         tasks.push(self.update_all_clients());
+                        self.update_outbox_text();
+                        // Actions held back by an expired session.
+                        tasks.push(self.outbox.replay());
                         tasks.push(fetch_session_info(self.mastodon.clone()));
                     }
                     Err(err) => tasks.push(cosmic::task::message(Message::Error(format!(
@@ -916,7 +1445,8 @@ impl Application for AppModel {
                 }
             }
             Message::UpdateMastodonInstance => {
-                self.mastodon = Client::new(self.instance(), None);
+                self.mastodon = Client::new(Software::default(), self.instance(), None);
+                self.cache.software = self.mastodon.software;
             }
             Message::SetAccount(account) => {
                 self.cache.me = Some(account);
@@ -924,39 +1454,87 @@ impl Application for AppModel {
             Message::SetMaxCharacters(max_characters) => {
                 self.max_characters = max_characters;
             }
+            Message::SetServerTranslation(enabled) => {
+                self.cache.server_translation = enabled;
+            }
             Message::StatusDeleted(id) => {
-                self.cache.statuses.remove(&id);
+                self.cache.remove_status(&id);
+            }
+            Message::SetBlockedDomains(domains) => {
+                self.cache.blocked_domains = domains.into_iter().collect();
+            }
+            Message::DomainBlockChanged(domain, blocked) => {
+                if blocked {
+                    self.cache.blocked_domains.insert(domain);
+                } else {
+                    self.cache.blocked_domains.remove(&domain);
+                }
+            }
+            Message::SetAccountLists(account_id, list_ids) => {
+                for dialog in self.dialog_pages.iter_mut() {
+                    if let Dialog::AddToList(account, memberships) = dialog {
+                        if account.id == account_id {
+                            *memberships = Some(list_ids.clone());
+                        }
+                    }
+                }
+            }
+            Message::ToggleListMembership(list_id, member) => {
+                if let Some(Dialog::AddToList(account, _)) = self.dialog_pages.front() {
+                    let mastodon = self.mastodon.clone();
+                    tasks.push(if member {
+                        lists::remove_member(mastodon, list_id, account.id.clone())
+                    } else {
+                        lists::add_member(mastodon, list_id, account.clone())
+                    });
+                }
+            }
+            Message::SetDomainFollowers(domain, followers) => {
+                for dialog in self.dialog_pages.iter_mut() {
+                    if let Dialog::BlockDomain(pending, lost) = dialog {
+                        if *pending == domain {
+                            *lost = Some(followers.clone());
+                        }
+                    }
+                }
             }
             Message::Open(url) => {
                 if let Err(err) = open::that_detached(url) {
                     tracing::error!("{err}")
                 }
             }
+            Message::OpenLink(url) => tasks.push(self.open_link(url)),
+            Message::OpenStatus(status) => {
+                let id = status.id.clone();
+                let urls = crate::cache::extract_status_images(&status);
+                self.cache.insert_status(status);
+                tasks.push(cosmic::task::message(Message::Fetch(urls)));
+                tasks.push(self.update(Message::OpenContextPage(ContextPage::Status(id))));
+            }
+            Message::OpenMention(id) => {
+                let mastodon = self.mastodon.clone();
+                tasks.push(cosmic::task::future(async move {
+                    match mastodon.get_account(id).await {
+                        Ok(response) => {
+                            Message::ToggleContextPage(ContextPage::Account(response.json))
+                        }
+                        Err(err) => failure("Couldn't load profile", err),
+                    }
+                }));
+            }
+            Message::OpenTag(name) => tasks.push(self.open_tag(name)),
             Message::ToggleContextPage(context_page) => {
                 if self.context_page == context_page {
                     self.core.window.show_context = !self.core.window.show_context;
                 } else {
-                    if let ContextPage::Account(account) = &context_page {
-                        if self.mastodon.is_authenticated() {
-                            let mastodon = self.mastodon.clone();
-                            let id = account.id.clone();
-                            tasks.push(cosmic::task::future(async move {
-                                match mastodon.get_relationships(vec![id]).await {
-                                    Ok(response) => response
-                                        .json
-                                        .into_iter()
-                                        .next()
-                                        .map(Message::CacheRelationship)
-                                        .unwrap_or(Message::None),
-                                    Err(err) => {
-                                        Message::Error(format!("Couldn't load relationship: {err}"))
-                                    }
-                                }
-                            }));
-                        }
-                    }
-                    self.context_page = context_page;
+                    tasks.push(self.open_context_page(context_page));
+                }
+            }
+            Message::OpenContextPage(context_page) => {
+                if self.context_page == context_page {
                     self.core.window.show_context = true;
+                } else {
+                    tasks.push(self.open_context_page(context_page));
                 }
             }
             Message::ToggleContextDrawer => {
@@ -976,34 +1554,23 @@ impl Application for AppModel {
                     self.dialog_pages[0] = dialog_page;
                 }
                 DialogAction::Close => {
-                    self.dialog_pages.pop_front();
+                    if let Some(Dialog::Authorize) = self.dialog_pages.pop_front() {
+                        if let Some(wait) = self.authorize_wait.take() {
+                            wait.abort();
+                        }
+                    }
                 }
                 DialogAction::Complete => {
                     if let Some(dialog_page) = self.dialog_pages.pop_front() {
                         match dialog_page {
                             Dialog::Compose(state) => {
-                                let text = self.dialog_editor.text();
-                                let mastodon = self.mastodon.clone();
-                                tasks.push(cosmic::task::future(async move {
-                                    let options = PostStatusInputOptions {
-                                        in_reply_to_id: state.in_reply_to_id,
-                                        spoiler_text: state
-                                            .content_warning
-                                            .then_some(state.spoiler_text),
-                                        visibility: Some(state.visibility),
-                                        ..Default::default()
-                                    };
-                                    match mastodon.post_status(text, Some(&options)).await {
-                                        Ok(response) => match response.json {
-                                            megalodon::megalodon::PostStatusOutput::Status(
-                                                status,
-                                            ) => Message::CacheStatus(status),
-                                            megalodon::megalodon::PostStatusOutput::ScheduledStatus(
-                                                _,
-                                            ) => Message::None,
-                                        },
-                                        Err(err) => Message::Error(format!("Couldn't post: {err}")),
-                                    }
+                                tasks.push(self.dispatch(Action::Post {
+                                    text: self.dialog_editor.text(),
+                                    in_reply_to_id: state.in_reply_to_id,
+                                    spoiler_text: state
+                                        .content_warning
+                                        .then_some(state.spoiler_text),
+                                    visibility: state.visibility,
                                 }));
                             }
                             Dialog::DeleteStatus(id) => {
@@ -1011,9 +1578,7 @@ impl Application for AppModel {
                                 tasks.push(cosmic::task::future(async move {
                                     match mastodon.delete_status(id.clone()).await {
                                         Ok(_) => Message::StatusDeleted(id),
-                                        Err(err) => {
-                                            Message::Error(format!("Couldn't delete post: {err}"))
-                                        }
+                                        Err(err) => action_failure("Couldn't delete post", err),
                                     }
                                 }));
                             }
@@ -1026,9 +1591,21 @@ impl Application for AppModel {
                                 self.instance = instance;
                                 tasks.push(self.update(Message::InstanceEdit));
                                 tasks.push(self.update(Message::RegisterMastodonClient));
-                                tasks.push(self.update(Message::Dialog(DialogAction::Open(
-                                    Dialog::Code(String::new()),
-                                ))))
+                            }
+                            Dialog::Authorize => {}
+                            Dialog::Unlock(passphrase) => {
+                                tasks.push(self.unlock_sessions(passphrase));
+                            }
+                            Dialog::SetPassphrase(passphrase) => {
+                                self.session_store =
+                                    EncryptedFileStore::new(passphrase).map(|store| {
+                                        Box::new(store) as Box<dyn SessionStore>
+                                    });
+                                if let Err(err) = self.persist_sessions() {
+                                    tasks.push(cosmic::task::message(Message::Error(format!(
+                                        "Couldn't save session: {err}"
+                                    ))));
+                                }
                             }
                             Dialog::Code(code) => {
                                 self.code = code;
@@ -1037,7 +1614,24 @@ impl Application for AppModel {
                             Dialog::Logout => {
                                 tasks.push(self.remove_account(self.sessions.active));
                             }
-                            Dialog::Image(..) => {}
+                            Dialog::DeleteList(id, _) => {
+                                let mastodon = self.mastodon.clone();
+                                tasks.push(cosmic::task::future(async move {
+                                    match mastodon.delete_list(id.clone()).await {
+                                        Ok(_) => Message::Lists(lists::Message::Deleted(id)),
+                                        Err(err) => action_failure("Couldn't delete list", err),
+                                    }
+                                }));
+                            }
+                            Dialog::AddToList(..) => {}
+                            Dialog::BlockDomain(domain, _) => {
+                                tasks.push(update_domain_block(
+                                    self.mastodon.clone(),
+                                    domain,
+                                    false,
+                                ));
+                            }
+                            Dialog::Media(..) => {}
                         }
                     }
                 }
@@ -1046,7 +1640,38 @@ impl Application for AppModel {
                 self.dialog_editor.perform(action);
             }
             Message::UpdateConfig(config) => {
+                let deck_mode_changed = config.deck_mode != self.config.deck_mode;
                 self.config = config;
+                if deck_mode_changed {
+                    self.update_navbar();
+                }
+            }
+            Message::ViewMedia(id, index) => {
+                if let Some(status) = self.cache.statuses.get(&id) {
+                    match status.media_attachments.get(index) {
+                        Some(media) if media::plays_externally(media) => {
+                            tasks.push(cosmic::task::message(Message::Open(media.url.clone())))
+                        }
+                        _ => {
+                            let viewer =
+                                media::Viewer::new(status.media_attachments.clone(), index);
+                            tasks.push(viewer.load());
+                            self.dialog_pages.push_back(Dialog::Media(viewer));
+                        }
+                    }
+                }
+            }
+            Message::Media(message) => {
+                if let Some(Dialog::Media(viewer)) = self.dialog_pages.front_mut() {
+                    tasks.push(viewer.update(message));
+                }
+            }
+            Message::Notice(message) => {
+                tasks.push(
+                    self.toasts
+                        .push(Toast::new(message))
+                        .map(cosmic::Action::App),
+                );
             }
             Message::Error(message) => {
                 tracing::error!("{message}");
@@ -1056,27 +1681,177 @@ impl Application for AppModel {
                         .map(cosmic::Action::App),
                 );
             }
+            Message::SessionExpired => {
+                let prompting = self
+                    .dialog_pages
+                    .iter()
+                    .any(|dialog| matches!(dialog, Dialog::Login(_) | Dialog::Authorize));
+                if !prompting && self.mastodon.is_authenticated() {
+                    tasks.push(self.update(Message::Error(Error::Unauthorized.to_string())));
+                    self.dialog_pages.push_back(Dialog::Login(self.mastodon.base_url.clone()));
+                }
+            }
+            Message::Outbox(message) => {
+                tasks.push(self.outbox.update(message));
+                self.update_outbox_text();
+            }
+            Message::Offline => self.online = false,
+            Message::OfflineError(message) => {
+                self.online = false;
+                tasks.push(self.update(Message::Error(message)));
+            }
+            Message::Online => {
+                self.online = true;
+                tasks.push(self.outbox.replay());
+            }
             Message::CloseToast(id) => {
                 self.toasts.remove(id);
             }
-            Message::FlushCache => self.flush_cache_to_disk(),
+            Message::FlushCache => {
+                self.flush_cache_to_disk();
+                let visible = self.visible_image_urls();
+                self.cache.evict_handles(&visible);
+                let (statuses, notifications) = self.referenced_ids();
+                self.cache.evict_statuses(statuses, notifications);
+            }
+            Message::PruneImageCache => tasks.push(self.prune_image_cache()),
+            Message::AnimationTick => self.cache.advance_animations(),
+            Message::ClockTick => self.cache.now = chrono::Utc::now(),
+            Message::ImageCacheUsage(usage) => self.image_cache_usage = Some(usage),
             Message::None => (),
         }
+        if refresh_animating {
+            self.refresh_animating();
+        }
         Task::batch(tasks)
     }
 }
 
 impl AppModel {
-    /// Persist the full account list (and which one is active) to the keychain.
-    fn persist_sessions(&self) -> Result<(), String> {
-        let data = serde_json::to_string(&self.sessions).map_err(|err| err.to_string())?;
-        keytar::set_password(Self::APP_ID, "mastodon-data", &data).map_err(|err| err.to_string())
+    /// Persist the full account list (and which one is active) to the
+    /// session store. Without a usable keyring, this instead asks for the
+    /// encrypted file's passphrase, and saving happens once it's entered.
+    fn persist_sessions(&mut self) -> Result<(), Error> {
+        if let Some(store) = &self.session_store {
+            match store.save(&self.sessions) {
+                Ok(()) => return Ok(()),
+                // The keyring can answer reads but refuse writes (e.g. no
+                // Secret Service running); fall back instead of losing the
+                // session.
+                Err(Error::Keyring(err)) => {
+                    tracing::warn!("{err}");
+                    self.session_store = None;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if !EncryptedFileStore::is_available() {
+            return Err(Error::NoSessionStore);
+        }
+        let asking = self
+            .dialog_pages
+            .iter()
+            .any(|dialog| matches!(dialog, Dialog::Unlock(_) | Dialog::SetPassphrase(_)));
+        if !asking {
+            self.dialog_pages.push_back(if EncryptedFileStore::exists() {
+                Dialog::Unlock(String::new())
+            } else {
+                Dialog::SetPassphrase(String::new())
+            });
+        }
+        Ok(())
+    }
+
+    /// Switch to the passphrase-protected file store once unlocked, merging
+    /// in any account logged into while it was still locked.
+    fn unlock_sessions(&mut self, passphrase: String) -> Task<Message> {
+        let Some(store) = EncryptedFileStore::new(passphrase) else {
+            return cosmic::task::message(Message::Error(Error::NoSessionStore.to_string()));
+        };
+        let mut sessions = match store.load() {
+            Ok(sessions) => sessions.unwrap_or_default(),
+            Err(err) => {
+                self.dialog_pages.push_front(Dialog::Unlock(String::new()));
+                return cosmic::task::message(Message::Error(format!(
+                    "Couldn't unlock saved accounts: {err}"
+                )));
+            }
+        };
+        let current = std::mem::take(&mut self.sessions);
+        let active = current.active_session().cloned();
+        sessions.apps.extend(current.apps);
+        if !current.translation_api_key.is_empty() {
+            sessions.translation_api_key = current.translation_api_key;
+        }
+        for session in current.sessions.into_iter().chain(active) {
+            sessions.upsert_active(session);
+        }
+        self.sessions = sessions;
+        self.session_store = Some(Box::new(store));
+
+        if !self.mastodon.is_authenticated() && self.sessions.active_session().is_some() {
+            return self.switch_account(self.sessions.active);
+        }
+        match self.persist_sessions() {
+            Ok(()) => Task::none(),
+            Err(err) => cosmic::task::message(Message::Error(format!(
+                "Couldn't save session: {err}"
+            ))),
+        }
+    }
+
+    /// Open the block-domain confirmation dialog and start looking up which
+    /// of the user's followers live on that domain, since blocking it
+    /// removes them as followers.
+    fn confirm_block_domain(&mut self, domain: String) -> Task<Message> {
+        self.dialog_pages
+            .push_back(Dialog::BlockDomain(domain.clone(), None));
+        let Some(me) = self.cache.me.as_ref().map(|me| me.id.clone()) else {
+            return cosmic::task::message(Message::SetDomainFollowers(domain, Vec::new()));
+        };
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            let options = megalodon::megalodon::AccountFollowersInputOptions {
+                limit: Some(80),
+                get_all: Some(true),
+                ..Default::default()
+            };
+            match mastodon.get_account_followers(me, Some(&options)).await {
+                Ok(response) => {
+                    let followers = response
+                        .json
+                        .iter()
+                        .filter(|account| crate::cache::account_domain(account) == Some(&domain))
+                        .map(|account| account.acct.clone())
+                        .collect();
+                    Message::SetDomainFollowers(domain, followers)
+                }
+                Err(err) => failure("Couldn't load followers", err),
+            }
+        })
+    }
+
+    fn update_list_membership(&mut self, list_id: &str, account_id: &str, member: bool) {
+        for dialog in self.dialog_pages.iter_mut() {
+            if let Dialog::AddToList(account, Some(memberships)) = dialog {
+                if account.id == account_id {
+                    if member {
+                        memberships.insert(list_id.to_string());
+                    } else {
+                        memberships.remove(list_id);
+                    }
+                }
+            }
+        }
     }
 
     /// Push the active client to every feature that holds its own copy, so
     /// switching accounts doesn't leave a page talking to the old session.
     fn update_all_clients(&mut self) -> Task<Message> {
         let mastodon = self.mastodon.clone();
+        for timeline in self.pinned.values_mut() {
+            timeline.mastodon = mastodon.clone();
+        }
         Task::batch(vec![
             self.home
                 .update(timeline::Message::SetClient(mastodon.clone())),
@@ -1096,7 +1871,8 @@ impl AppModel {
                 .update(hashtags::Message::SetClient(mastodon.clone())),
             self.lists
                 .update(lists::Message::SetClient(mastodon.clone())),
-            self.search.update(search::Message::SetClient(mastodon)),
+            self.search.update(search::Message::SetClient(mastodon.clone())),
+            self.outbox.update(outbox::Message::SetClient(mastodon)),
         ])
     }
 
@@ -1105,6 +1881,9 @@ impl AppModel {
     /// the previous account's posts visible until a fresh fetch lands.
     fn reset_and_reload_feeds(&mut self) -> Task<Message> {
         let mastodon = self.mastodon.clone();
+        // Another server may well be reachable; the next failed request says
+        // otherwise.
+        self.online = true;
         self.home.reset(mastodon.clone());
         self.explore.reset(mastodon.clone());
         self.local.reset(mastodon.clone());
@@ -1112,7 +1891,11 @@ impl AppModel {
         self.favorites.reset(mastodon.clone());
         self.bookmarks.reset(mastodon.clone());
         self.notifications.reset(mastodon.clone());
+        self.pinned.clear();
+        self.load_cached_relationships();
         let load_tasks = vec![
+            self.sync_pinned(),
+            self.load_deck(),
             self.home.load_cached(),
             self.explore.load_cached(),
             self.local.load_cached(),
@@ -1126,7 +1909,8 @@ impl AppModel {
                 .update(hashtags::Message::SetClient(mastodon.clone())),
             self.lists
                 .update(lists::Message::SetClient(mastodon.clone())),
-            self.search.update(search::Message::SetClient(mastodon)),
+            self.search.update(search::Message::SetClient(mastodon.clone())),
+            self.outbox.update(outbox::Message::SetClient(mastodon)),
             Task::batch(load_tasks),
         ])
     }
@@ -1142,9 +1926,10 @@ impl AppModel {
                 break;
             };
             self.image_inflight.insert(url.clone());
+            let animate = self.cache.play_animations;
             tasks.push(cosmic::task::future(async move {
-                match crate::cache::get(&url).await {
-                    Ok(handle) => Message::CacheHandle(url, handle),
+                match crate::cache::get(&url, animate).await {
+                    Ok(image) => Message::CacheHandle(url, image),
                     Err(err) => {
                         tracing::error!("Failed to fetch image: {}", err);
                         Message::ImageFetchFailed(url)
@@ -1155,19 +1940,293 @@ impl AppModel {
         Task::batch(tasks)
     }
 
-    /// Save every feed's cached content to disk, if anything changed since
-    /// the last flush.
+    /// Images on the active page (or every deck column) and in the open
+    /// status or profile, which are kept in memory when loaded images are
+    /// evicted.
+    fn visible_image_urls(&self) -> HashSet<String> {
+        let cache = &self.cache;
+        let mut urls: HashSet<String> = match &self.context_page {
+            _ if !self.core.window.show_context => HashSet::new(),
+            ContextPage::Status(_) => self
+                .thread_ids()
+                .iter()
+                .filter_map(|id| cache.statuses.get(id))
+                .flat_map(crate::cache::extract_status_images)
+                .collect(),
+            ContextPage::Account(account) => [&account.avatar, &account.header]
+                .into_iter()
+                .filter(|url| !url.is_empty())
+                .cloned()
+                .chain(emoji::image_urls(&account.emojis))
+                .collect(),
+            _ => HashSet::new(),
+        };
+        let page_urls = match self.nav.active_data::<Page>() {
+            Some(Page::Deck) => self.deck.image_urls(cache),
+            Some(Page::Home) => self.home.image_urls(cache),
+            Some(Page::Notifications) => self.notifications.image_urls(cache),
+            Some(Page::Explore) => self.explore.image_urls(cache),
+            Some(Page::Local) => self.local.image_urls(cache),
+            Some(Page::Federated) => self.federated.image_urls(cache),
+            Some(Page::Favorites) => self.favorites.image_urls(cache),
+            Some(Page::Bookmarks) => self.bookmarks.image_urls(cache),
+            Some(Page::Search) => self.search.image_urls(cache),
+            Some(Page::Hashtags) => self.hashtags.image_urls(cache),
+            Some(Page::Lists) => self.lists.image_urls(cache),
+            Some(Page::Pinned(pin)) => self
+                .pinned
+                .get(pin)
+                .map(|timeline| timeline.image_urls(cache))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        urls.extend(page_urls);
+        urls
+    }
+
+    /// Recheck whether anything on screen animates, for the animation ticks.
+    fn refresh_animating(&mut self) {
+        self.animating = self.cache.is_animating(self.visible_image_urls().iter());
+    }
+
+    /// Reload any of the active page's images that were evicted while it
+    /// was off-screen. Ones still in memory are only marked as used.
+    fn fetch_visible_images(&self) -> Task<Message> {
+        let urls: Vec<String> = self.visible_image_urls().into_iter().collect();
+        if urls.is_empty() {
+            return Task::none();
+        }
+        cosmic::task::message(Message::Fetch(urls))
+    }
+
+    /// Ids of every status and notification something still shows: loaded
+    /// feeds, deck columns, search results, the selected hashtag and list
+    /// timelines, the open status and the post being replied to. Everything
+    /// else may be evicted from the cache.
+    fn referenced_ids(&self) -> (HashSet<String>, HashSet<String>) {
+        let mut statuses: HashSet<String> = [
+            &self.home,
+            &self.explore,
+            &self.local,
+            &self.federated,
+            &self.favorites,
+            &self.bookmarks,
+        ]
+        .into_iter()
+        .chain(self.pinned.values())
+        .flat_map(Timeline::status_ids)
+        .chain(self.deck.status_ids())
+        .chain(self.search.status_ids())
+        .chain(self.hashtags.status_ids())
+        .chain(self.lists.status_ids())
+        .cloned()
+        .collect();
+        statuses.extend(self.thread_ids());
+        for dialog in &self.dialog_pages {
+            if let Dialog::Compose(state) = dialog {
+                statuses.extend(state.in_reply_to_id.clone());
+            }
+        }
+
+        let notifications = self
+            .notifications
+            .notification_ids()
+            .chain(self.deck.notification_ids())
+            .cloned()
+            .collect();
+        (statuses, notifications)
+    }
+
+    /// Bring back a status evicted from the cache, from disk if it's still
+    /// saved there or else from the server.
+    fn reload_status(&self, id: String) -> Task<Message> {
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            let base_url = mastodon.base_url.clone();
+            let disk_id = id.clone();
+            let saved = tokio::task::spawn_blocking(move || {
+                crate::persistence::load_status(&base_url, &disk_id)
+            })
+            .await
+            .ok()
+            .flatten();
+            if let Some(status) = saved {
+                return Message::CacheStatus(status);
+            }
+            match mastodon.get_status(id).await {
+                Ok(response) => Message::CacheStatus(response.json),
+                Err(err) => failure("Couldn't load post", err),
+            }
+        })
+    }
+
+    /// Fetch the replies around a status, for its page in the context drawer.
+    fn load_thread(&self, id: String) -> Task<Message> {
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            match mastodon.get_status_context(id.clone(), None).await {
+                Ok(response) => Message::Thread(id, response.json),
+                Err(err) => failure("Couldn't load replies", err),
+            }
+        })
+    }
+
+    /// Translate a status into the UI language, with the server or else the
+    /// translation service from the settings.
+    fn translate(&self, status: Status) -> Task<Message> {
+        let mastodon = self.mastodon.clone();
+        let language = self.cache.language.clone();
+        let server = self.cache.server_translation;
+        let service = translate::Service::configured(
+            &self.config.translation_url,
+            &self.sessions.translation_api_key,
+        );
+        cosmic::task::future(async move {
+            let id = status.id.clone();
+            match translate::translate(mastodon, status, language, server, service).await {
+                Ok(translation) => Message::Translated(id, translation),
+                Err(err) => Message::TranslationFailed(id, err.to_string()),
+            }
+        })
+    }
+
+    /// Ids of the status open in the context drawer and its thread, in
+    /// order.
+    fn thread_ids(&self) -> Vec<String> {
+        let ContextPage::Status(id) = &self.context_page else {
+            return Vec::new();
+        };
+        let (ancestors, descendants) = &self.thread;
+        ancestors
+            .iter()
+            .chain(Some(id))
+            .chain(descendants)
+            .cloned()
+            .collect()
+    }
+
+    /// Trim the image cache on disk to the configured size and age limits,
+    /// then report its size.
+    fn prune_image_cache(&self) -> Task<Message> {
+        let max_bytes = self.config.image_cache_limit.bytes();
+        let max_age = self.config.image_cache_age.duration();
+        cosmic::task::future(async move {
+            let usage = tokio::task::spawn_blocking(move || {
+                crate::persistence::prune_image_cache(max_bytes, max_age)
+            })
+            .await
+            .unwrap_or_default();
+            Message::ImageCacheUsage(usage)
+        })
+    }
+
+    /// Send `action` to the server, or queue it in the outbox if the server
+    /// can't be reached, now or when it's tried. Actions also queue behind
+    /// ones still waiting, so the server sees them in order. An action that
+    /// isn't safe to repeat isn't queued after a timeout, since the server
+    /// may already have taken it.
+    fn dispatch(&mut self, action: Action) -> Task<Message> {
+        if !self.online || !self.outbox.is_empty() {
+            self.outbox.push(action);
+            self.update_outbox_text();
+            return if self.online {
+                self.outbox.replay()
+            } else {
+                Task::none()
+            };
+        }
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            let context = action.context();
+            match action.clone().perform(mastodon).await {
+                Ok(message) => message,
+                Err(Error::TimedOut(err)) if !action.is_idempotent() => {
+                    tracing::warn!("timed out, not queueing action: {err}");
+                    Message::Error(fl!("may-not-have-been-sent"))
+                }
+                Err(Error::Network(err) | Error::TimedOut(err)) => {
+                    tracing::warn!("offline, queueing action: {err}");
+                    Message::Outbox(outbox::Message::Queue(action))
+                }
+                Err(err) => failure(context, err),
+            }
+        })
+    }
+
+    /// While offline, put a banner above `content` saying why nothing new is
+    /// loading and what's waiting to be sent.
+    fn with_offline_banner<'a>(&self, content: Element<'a, Message>) -> Element<'a, Message> {
+        if self.online {
+            return content;
+        }
+        let spacing = cosmic::theme::active().cosmic().spacing;
+        let pending = self.outbox.len();
+        let banner = widget::row![
+            widget::icon::from_name("network-offline-symbolic").size(16).icon(),
+            widget::text(fl!("offline")),
+        ]
+        .push_maybe(
+            (pending > 0).then(|| widget::text(fl!("offline-pending", count = pending))),
+        )
+        .spacing(spacing.space_xs)
+        .align_y(Vertical::Center)
+        .apply(widget::container)
+        .padding(spacing.space_xs)
+        .width(Length::Fill)
+        .class(cosmic::style::Container::Card);
+
+        widget::column![banner, content]
+            .spacing(spacing.space_xs)
+            .into()
+    }
+
+    /// Restore the relationships saved for the active account, so follow and
+    /// mute buttons are right before a profile's fresh lookup lands.
+    fn load_cached_relationships(&mut self) {
+        for relationship in crate::persistence::load_relationships(&self.mastodon.base_url) {
+            self.cache
+                .relationships
+                .insert(relationship.id.clone(), relationship);
+        }
+    }
+
+    /// Save every feed's cached content to disk, if anything changed since
+    /// the last flush.
     fn flush_cache_to_disk(&mut self) {
         if !self.cache.dirty {
             return;
         }
+        let base_url = &self.mastodon.base_url;
+        let cache = &mut self.cache;
+        crate::persistence::save_statuses(
+            base_url,
+            cache.unsaved_statuses.drain().filter_map(|id| cache.statuses.get(&id)),
+        );
+        crate::persistence::save_notifications(
+            base_url,
+            cache
+                .unsaved_notifications
+                .drain()
+                .filter_map(|id| cache.notifications.get(&id)),
+        );
+        crate::persistence::save_relationships(
+            base_url,
+            cache
+                .unsaved_relationships
+                .drain()
+                .filter_map(|id| cache.relationships.get(&id)),
+        );
+
         self.home.save_cached(&self.cache);
         self.explore.save_cached(&self.cache);
         self.local.save_cached(&self.cache);
         self.federated.save_cached(&self.cache);
         self.favorites.save_cached(&self.cache);
         self.bookmarks.save_cached(&self.cache);
-        self.notifications.save_cached(&self.cache);
+        for timeline in self.pinned.values() {
+            timeline.save_cached(&self.cache);
+        }
+        self.deck.save_cached(&self.cache);
         self.cache.dirty = false;
     }
 
@@ -1178,8 +2237,9 @@ impl AppModel {
         };
         self.flush_cache_to_disk();
         self.sessions.active = index;
-        self.mastodon = Client::new(session.base_url, Some(session.token));
+        self.mastodon = Client::new(session.software, session.base_url, Some(session.token));
         self.cache.clear();
+        self.cache.software = self.mastodon.software;
         self.cache.hide_boosts = self.config.hide_boosts;
         self.cache.hide_replies = self.config.hide_replies;
         self.cache.feed_density = self.config.feed_density;
@@ -1202,20 +2262,33 @@ impl AppModel {
     /// another remaining account or falls back to a logged-out client.
     fn remove_account(&mut self, index: usize) -> Task<Message> {
         let was_active = index == self.sessions.active;
-        let removed_base_url = self
-            .sessions
-            .sessions
-            .get(index)
-            .map(|s| s.base_url.clone());
+        let removed = self.sessions.sessions.get(index).cloned();
         if was_active {
             self.flush_cache_to_disk();
         }
         self.sessions.remove(index);
-        if let Some(base_url) = removed_base_url {
-            crate::persistence::clear_account(&base_url);
-        }
 
         let mut tasks = vec![];
+        if let Some(session) = removed {
+            crate::persistence::clear_account(&session.base_url);
+            // Sessions saved before app registrations were kept can't be
+            // revoked; their tokens stay valid until revoked on the web.
+            match self.sessions.apps.get(&session.base_url).cloned() {
+                Some(app) => {
+                    let client =
+                        Client::new(session.software, session.base_url, Some(session.token));
+                    tasks.push(cosmic::task::future(async move {
+                        match client.revoke(&app).await {
+                            Ok(()) => Message::None,
+                            Err(err) => action_failure("Couldn't revoke login", err),
+                        }
+                    }));
+                }
+                None => {
+                    tracing::warn!("No app registration for {}, not revoking", session.base_url)
+                }
+            }
+        }
         if let Err(err) = self.persist_sessions() {
             tasks.push(cosmic::task::message(Message::Error(format!(
                 "Couldn't save session: {err}"
@@ -1225,13 +2298,15 @@ impl AppModel {
         if was_active {
             match self.sessions.active_session().cloned() {
                 Some(session) => {
-                    self.mastodon = Client::new(session.base_url, Some(session.token));
+                    self.mastodon =
+                        Client::new(session.software, session.base_url, Some(session.token));
                 }
                 None => {
-                    self.mastodon = Client::new(self.instance(), None);
+                    self.mastodon = Client::new(Software::default(), self.instance(), None);
                 }
             }
             self.cache.clear();
+            self.cache.software = self.mastodon.software;
             self.cache.hide_boosts = self.config.hide_boosts;
             self.cache.hide_replies = self.config.hide_replies;
             self.cache.feed_density = self.config.feed_density;
@@ -1299,6 +2374,57 @@ impl AppModel {
             )
     }
 
+    fn authorize(&self) -> widget::Dialog<'_, Message> {
+        widget::dialog()
+            .title(fl!("confirm-authorization"))
+            .body(fl!("waiting-for-browser"))
+            .icon(widget::icon::from_name("network-server-symbolic"))
+            .secondary_action(
+                widget::button::standard(fl!("cancel"))
+                    .on_press(Message::Dialog(DialogAction::Close)),
+            )
+    }
+
+    fn unlock(&self, passphrase: String) -> widget::Dialog<'_, Message> {
+        let complete = (!passphrase.is_empty()).then_some(Message::Dialog(DialogAction::Complete));
+        widget::dialog()
+            .title(fl!("unlock-accounts"))
+            .body(fl!("unlock-accounts-description"))
+            .icon(widget::icon::from_name("dialog-password-symbolic"))
+            .control(
+                widget::text_input(fl!("passphrase"), passphrase)
+                    .password()
+                    .on_input(|passphrase| {
+                        Message::Dialog(DialogAction::Update(Dialog::Unlock(passphrase)))
+                    }),
+            )
+            .primary_action(widget::button::suggested(fl!("unlock")).on_press_maybe(complete))
+            .secondary_action(
+                widget::button::standard(fl!("cancel"))
+                    .on_press(Message::Dialog(DialogAction::Close)),
+            )
+    }
+
+    fn set_passphrase(&self, passphrase: String) -> widget::Dialog<'_, Message> {
+        let complete = (!passphrase.is_empty()).then_some(Message::Dialog(DialogAction::Complete));
+        widget::dialog()
+            .title(fl!("set-passphrase"))
+            .body(fl!("set-passphrase-description"))
+            .icon(widget::icon::from_name("dialog-password-symbolic"))
+            .control(
+                widget::text_input(fl!("passphrase"), passphrase)
+                    .password()
+                    .on_input(|passphrase| {
+                        Message::Dialog(DialogAction::Update(Dialog::SetPassphrase(passphrase)))
+                    }),
+            )
+            .primary_action(widget::button::suggested(fl!("confirm")).on_press_maybe(complete))
+            .secondary_action(
+                widget::button::standard(fl!("cancel"))
+                    .on_press(Message::Dialog(DialogAction::Close)),
+            )
+    }
+
     fn code(&self, code: String) -> widget::Dialog<'_, Message> {
         widget::dialog()
             .title(fl!("confirm-authorization"))
@@ -1349,95 +2475,185 @@ impl AppModel {
             )
     }
 
-    /// A full-window image overlay, in the same spirit as cosmic-files'
-    /// gallery view: a translucent backdrop covering the whole window with
-    /// the image centered and content-fit, rather than a small dialog box.
-    fn view_image(&self, preview_url: String, original_url: String) -> Element<'_, Message> {
+    fn block_domain(
+        &self,
+        domain: &str,
+        followers: Option<&Vec<String>>,
+    ) -> widget::Dialog<'_, Message> {
         let spacing = cosmic::theme::active().cosmic().spacing;
+        let followers: Element<'_, Message> = match followers {
+            None => widget::text::caption(fl!("block-domain-checking")).into(),
+            Some(followers) if followers.is_empty() => {
+                widget::text::caption(fl!("block-domain-no-followers")).into()
+            }
+            Some(followers) => widget::column![
+                widget::text::caption(fl!(
+                    "block-domain-followers",
+                    count = followers.len()
+                )),
+                widget::scrollable(
+                    widget::column(
+                        followers
+                            .iter()
+                            .map(|acct| widget::text(format!("@{acct}")).into())
+                            .collect::<Vec<Element<'_, Message>>>(),
+                    )
+                    .spacing(spacing.space_xxs),
+                )
+                .height(Length::Shrink),
+            ]
+            .spacing(spacing.space_xs)
+            .into(),
+        };
 
-        let image: Element<'_, Message> = self
-            .cache
-            .handles
-            .get(&preview_url)
-            .map(|handle| {
-                widget::image(handle)
-                    .content_fit(cosmic::iced::ContentFit::Contain)
-                    .width(Length::Fill)
-                    .height(Length::Fill)
-                    .into()
-            })
-            .unwrap_or_else(|| widget::text("Image not available").into());
-
-        // Fixed, matching top/bottom bars so the image's `Length::Fill` area
-        // in between is symmetric — an empty bottom bar mirrors the header's
-        // height rather than letting the image run flush to the window edge.
-        // The fixed height is on the *wrapping container*, not the row itself,
-        // so the buttons are centered within it rather than being squashed
-        // to fit. Generous headroom above the row's own intrinsic size
-        // (buttons + padding) so the container never has to compress it —
-        // a too-tight fixed height here is what squashed the buttons before.
-        let bar_height = Length::Fixed(96.0);
-
-        let header_row = widget::row![
-            widget::space::horizontal(),
-            widget::button::icon(widget::icon::from_name("send-to-symbolic"))
-                .class(cosmic::style::Button::Standard)
-                .on_press(Message::Open(original_url)),
-            widget::button::icon(widget::icon::from_name("window-close-symbolic"))
-                .class(cosmic::style::Button::Standard)
-                .on_press(Message::Dialog(DialogAction::Close)),
-        ]
-        .align_y(cosmic::iced::Alignment::Center)
-        .spacing(spacing.space_xs)
-        .padding(spacing.space_xs);
+        widget::dialog()
+            .title(fl!("block-domain-question", domain = domain))
+            .body(fl!("block-domain-description"))
+            .icon(widget::icon::from_name("action-unavailable-symbolic"))
+            .control(followers)
+            .primary_action(
+                widget::button::destructive(fl!("block"))
+                    .on_press(Message::Dialog(DialogAction::Complete)),
+            )
+            .secondary_action(
+                widget::button::standard(fl!("cancel"))
+                    .on_press(Message::Dialog(DialogAction::Close)),
+            )
+    }
 
-        let header = widget::container(header_row)
-            .width(Length::Fill)
-            .height(bar_height)
-            .align_y(Vertical::Center);
+    fn delete_list(&self, title: &str) -> widget::Dialog<'_, Message> {
+        widget::dialog()
+            .title(fl!("delete-list-question", title = title))
+            .body(fl!("delete-list-description"))
+            .icon(widget::icon::from_name("user-trash-symbolic"))
+            .primary_action(
+                widget::button::destructive(fl!("delete"))
+                    .on_press(Message::Dialog(DialogAction::Complete)),
+            )
+            .secondary_action(
+                widget::button::standard(fl!("cancel"))
+                    .on_press(Message::Dialog(DialogAction::Close)),
+            )
+    }
 
-        let footer = widget::container(widget::text(""))
-            .width(Length::Fill)
-            .height(bar_height);
+    fn add_to_list<'a>(
+        &'a self,
+        account: &'a Account,
+        memberships: Option<&'a HashSet<String>>,
+    ) -> widget::Dialog<'a, Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+        let control: Element<'a, Message> = match memberships {
+            Some(memberships) if self.lists.is_loaded() => {
+                if self.lists.lists().is_empty() {
+                    widget::text::caption(fl!("add-to-list-empty")).into()
+                } else {
+                    widget::column(
+                        self.lists
+                            .lists()
+                            .iter()
+                            .map(|list| {
+                                let member = memberships.contains(&list.id);
+                                let id = list.id.clone();
+                                widget::checkbox(list.title.clone(), member)
+                                    .on_toggle(move |_| {
+                                        Message::ToggleListMembership(id.clone(), member)
+                                    })
+                                    .into()
+                            })
+                            .collect::<Vec<Element<'a, Message>>>(),
+                    )
+                    .spacing(spacing.space_xs)
+                    .apply(widget::scrollable)
+                    .into()
+                }
+            }
+            _ => widget::container(widget::indeterminate_circular().size(24.0))
+                .center_x(Length::Fill)
+                .into(),
+        };
 
-        widget::column![header, widget::container(image).center(Length::Fill), footer]
-            .apply(widget::container)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .class(cosmic::style::Container::Dialog(false))
-            .into()
+        widget::dialog()
+            .title(fl!("add-to-list-title", acct = account.acct.clone()))
+            .icon(widget::icon::from_name("view-list-symbolic"))
+            .control(control)
+            .primary_action(
+                widget::button::suggested(fl!("done"))
+                    .on_press(Message::Dialog(DialogAction::Close)),
+            )
     }
 
     fn status(&self, id: &String) -> Element<'_, Message> {
-        let status = self.cache.statuses.get(id).map(|status| {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+        let statuses: Vec<&Status> = self
+            .thread_ids()
+            .iter()
+            .filter_map(|id| self.cache.statuses.get(id))
+            .collect();
+
+        let warnings: Vec<&&Status> = statuses
+            .iter()
+            .filter(|status| !status.spoiler_text.is_empty())
+            .collect();
+        let expand_all = (!warnings.is_empty()).then(|| {
+            let expanded = warnings
+                .iter()
+                .all(|status| self.cache.is_content_shown(status));
+            let label = if expanded {
+                "Collapse all"
+            } else {
+                "Expand all"
+            };
+            widget::button::text(label).on_press(Message::ExpandThread(!expanded))
+        });
+
+        let thread = statuses.into_iter().map(|status| {
+            // Other posts in the thread open their own page when clicked.
+            let focused = status.id == *id;
             status::status(
                 status,
-                StatusOptions::new(true, true, true, false),
+                StatusOptions::new(true, true, true, !focused),
                 &self.cache,
             )
             .map(timeline::Message::Status)
             .map(Message::Home)
             .apply(widget::container)
-            .class(cosmic::theme::Container::Dialog(false))
+            .class(if focused {
+                cosmic::theme::Container::Dialog(false)
+            } else {
+                cosmic::theme::Container::Card
+            })
+            .into()
         });
-        widget::column![status].into()
+        widget::column![expand_all]
+            .extend(thread)
+            .spacing(spacing.space_xs)
+            .into()
     }
 
     fn account<'a>(&'a self, account: &'a Account) -> Element<'a, Message> {
-        accounts::account(account, &self.cache).map(Message::Account)
+        let note_draft = self
+            .note_draft
+            .as_ref()
+            .filter(|(id, _)| *id == account.id)
+            .map(|(_, draft)| draft.as_str());
+        accounts::account(account, &self.cache, note_draft).map(Message::Account)
     }
 }
 
-/// Fetch the authenticated account and the instance's status length limit,
-/// used to gate the compose dialog's delete action and character counter.
+/// Fetch the authenticated account, the instance's status length limit,
+/// whether it translates posts, and the user's blocked domains, used to gate
+/// the compose dialog's delete action and character counter, offer
+/// translations, and filter blocked instances from feeds.
 fn fetch_session_info(mastodon: Client) -> Task<Message> {
     let account_client = mastodon.clone();
-    let instance_client = mastodon;
+    let instance_client = mastodon.clone();
+    let translation_client = mastodon.clone();
+    let domain_blocks_client = mastodon;
     Task::batch(vec![
         cosmic::task::future(async move {
             match account_client.verify_account_credentials().await {
                 Ok(response) => Message::SetAccount(response.json),
-                Err(err) => Message::Error(format!("Couldn't load account: {err}")),
+                Err(err) => failure("Couldn't load account", err),
             }
         }),
         cosmic::task::future(async move {
@@ -1445,12 +2661,86 @@ fn fetch_session_info(mastodon: Client) -> Task<Message> {
                 Ok(response) => {
                     Message::SetMaxCharacters(response.json.configuration.statuses.max_characters)
                 }
-                Err(err) => Message::Error(format!("Couldn't load instance info: {err}")),
+                Err(err) => failure("Couldn't load instance info", err),
+            }
+        }),
+        cosmic::task::future(async move {
+            if !translation_client.software.supports_translation() {
+                return Message::SetServerTranslation(false);
+            }
+            match translation_client.translation_enabled().await {
+                Ok(enabled) => Message::SetServerTranslation(enabled),
+                Err(err) => {
+                    tracing::debug!("couldn't tell whether the server translates: {err}");
+                    Message::SetServerTranslation(false)
+                }
+            }
+        }),
+        cosmic::task::future(async move {
+            match domain_blocks_client.get_domain_blocks(None).await {
+                Ok(response) => Message::SetBlockedDomains(response.json),
+                Err(err) => failure("Couldn't load blocked domains", err),
             }
         }),
     ])
 }
 
+/// The message for a failed request: a re-login prompt if the session has
+/// expired, offline mode if the server couldn't be reached, otherwise an
+/// error toast prefixed with `context`.
+pub fn failure(context: &str, err: impl Into<Error>) -> Message {
+    match err.into() {
+        Error::Unauthorized => Message::SessionExpired,
+        Error::Network(_) | Error::TimedOut(_) => Message::Offline,
+        err => Message::Error(format!("{context}: {err}")),
+    }
+}
+
+/// Like [`failure`], for a change the outbox doesn't queue: if the server
+/// couldn't be reached, also say that the change didn't happen.
+pub fn action_failure(context: &str, err: impl Into<Error>) -> Message {
+    match err.into() {
+        err @ (Error::Network(_) | Error::TimedOut(_)) => {
+            Message::OfflineError(format!("{context}: {err}"))
+        }
+        err => failure(context, err),
+    }
+}
+
+/// Block a domain, or unblock it if `unblock`.
+fn update_domain_block(mastodon: Client, domain: String, unblock: bool) -> Task<Message> {
+    cosmic::task::future(async move {
+        let result = if unblock {
+            mastodon.unblock_domain(domain.clone()).await
+        } else {
+            mastodon.block_domain(domain.clone()).await
+        };
+        match result {
+            Ok(_) => Message::DomainBlockChanged(domain, !unblock),
+            Err(err) => action_failure("Couldn't update domain block", err),
+        }
+    })
+}
+
+/// Whether a link looks like a post or profile on a fediverse server, going
+/// by the URL shapes Mastodon, Pleroma, Misskey and friends use. Only these
+/// are worth the round trip to resolve them.
+fn is_fediverse_link(url: &str) -> bool {
+    let Ok(url) = url::Url::parse(url) else {
+        return false;
+    };
+    let Some(mut segments) = url.path_segments() else {
+        return false;
+    };
+    match segments.next() {
+        Some(first) if first.starts_with('@') && first.len() > 1 => true,
+        Some("users" | "notice" | "objects" | "notes" | "post" | "u") => {
+            segments.next().is_some_and(|segment| !segment.is_empty())
+        }
+        _ => false,
+    }
+}
+
 fn instance(instance: impl Into<String>) -> String {
     let instance: String = instance.into();
     let instance = instance
@@ -1474,21 +2764,315 @@ where
     }
 
     fn update_navbar(&mut self) {
+        let active = self.nav.active_data::<Page>().cloned();
         self.nav.clear();
 
-        let variants = (!self.mastodon.is_authenticated())
-            .then(Page::public_variants)
-            .unwrap_or_else(Page::variants);
+        let pins = self.config.pins(&self.mastodon.base_url).to_vec();
+        populate_nav(&mut self.nav, &self.mastodon, &pins, self.config.deck_mode);
 
-        for page in variants {
+        // Stay on the same page if it survived the rebuild (e.g. pinning a
+        // timeline shouldn't bounce the user back to Home).
+        let id = active.and_then(|active| {
             self.nav
-                .insert()
-                .text(page.to_string())
-                .icon(widget::icon::from_name(page.icon()))
-                .data::<Page>(page.clone());
+                .iter()
+                .find(|id| self.nav.data::<Page>(*id) == Some(&active))
+        });
+        match id {
+            Some(id) => self.nav.activate(id),
+            None => self.nav.activate_position(0),
+        }
+        for pin in pins {
+            self.update_pin_text(&pin);
+        }
+        self.update_outbox_text();
+    }
+
+    /// Show how many actions are waiting next to the Outbox page's name.
+    fn update_outbox_text(&mut self) {
+        let Some(id) = self
+            .nav
+            .iter()
+            .find(|id| self.nav.data::<Page>(*id) == Some(&Page::Outbox))
+        else {
+            return;
+        };
+        let text = match self.outbox.len() {
+            0 => Page::Outbox.to_string(),
+            pending => format!("{} ({pending})", Page::Outbox),
+        };
+        self.nav.text_set(id, text);
+    }
+
+    /// Show a pinned page's unread count next to its name in the sidebar.
+    fn update_pin_text(&mut self, pin: &Pin) {
+        let unread = self.pinned.get(pin).map_or(0, Timeline::unread);
+        let page = Page::Pinned(pin.clone());
+        let Some(id) = self
+            .nav
+            .iter()
+            .find(|id| self.nav.data::<Page>(*id) == Some(&page))
+        else {
+            return;
+        };
+        let text = if unread > 0 {
+            format!("{} ({unread})", pin.label())
+        } else {
+            pin.label()
+        };
+        self.nav.text_set(id, text);
+    }
 
-            self.nav.activate_position(0);
+    /// The sidebar entry for `page`, if it's in the sidebar.
+    fn nav_id(&self, page: &Page) -> Option<nav_bar::Id> {
+        self.nav
+            .iter()
+            .find(|id| self.nav.data::<Page>(*id) == Some(page))
+    }
+
+    /// Show `context_page` in the context drawer, loading what it shows.
+    fn open_context_page(&mut self, context_page: ContextPage) -> Task<Message> {
+        let mut tasks = vec![];
+        if let ContextPage::Account(account) = &context_page {
+            if self.mastodon.is_authenticated() {
+                let mastodon = self.mastodon.clone();
+                let id = account.id.clone();
+                tasks.push(cosmic::task::future(async move {
+                    match mastodon.get_relationships(vec![id]).await {
+                        Ok(response) => response
+                            .json
+                            .into_iter()
+                            .next()
+                            .map(Message::CacheRelationship)
+                            .unwrap_or(Message::None),
+                        Err(err) => failure("Couldn't load relationship", err),
+                    }
+                }));
+            }
         }
+        match &context_page {
+            ContextPage::Settings => tasks.push(self.prune_image_cache()),
+            ContextPage::Account(account) => {
+                self.cache.convert_profile_text(account);
+                let urls = [&account.avatar, &account.header]
+                    .into_iter()
+                    .filter(|url| !url.is_empty())
+                    .cloned()
+                    .chain(emoji::image_urls(&account.emojis))
+                    .collect();
+                tasks.push(cosmic::task::message(Message::Fetch(urls)))
+            }
+            ContextPage::Status(id) => {
+                if !self.cache.statuses.contains_key(id) {
+                    tasks.push(self.reload_status(id.clone()));
+                }
+                self.thread = (Vec::new(), Vec::new());
+                tasks.push(self.load_thread(id.clone()));
+            }
+            _ => (),
+        }
+        self.context_page = context_page;
+        self.core.window.show_context = true;
+        Task::batch(tasks)
+    }
+
+    /// Look a link up on the server, which fetches posts and profiles from
+    /// other instances, and open what it points at in the context drawer.
+    /// Anything else, or anything the server can't find, opens in the
+    /// browser.
+    fn open_link(&self, url: String) -> Task<Message> {
+        if !self.mastodon.is_authenticated() || !is_fediverse_link(&url) {
+            return cosmic::task::message(Message::Open(url));
+        }
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            let options = SearchInputOptions {
+                resolve: Some(true),
+                limit: Some(1),
+                ..Default::default()
+            };
+            match mastodon.search(url.clone(), Some(&options)).await {
+                Ok(response) => {
+                    let results = response.json;
+                    if let Some(status) = results.statuses.into_iter().next() {
+                        Message::OpenStatus(status)
+                    } else if let Some(account) = results.accounts.into_iter().next() {
+                        Message::OpenContextPage(ContextPage::Account(account))
+                    } else {
+                        Message::Open(url)
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!("couldn't resolve {url}: {err}");
+                    Message::Open(url)
+                }
+            }
+        })
+    }
+
+    /// Show a hashtag's timeline: as a new column in the deck, on its own
+    /// page if pinned, or else on the Hashtags page (the Search page on
+    /// servers without followed tags).
+    fn open_tag(&mut self, name: String) -> Task<Message> {
+        if self.is_deck_active() {
+            let mut tasks = vec![self.update(Message::Deck(deck::Message::Add(
+                deck::ColumnKind::Tag(name),
+            )))];
+            if let Some(id) = self.nav_id(&Page::Deck) {
+                tasks.push(self.on_nav_select(id));
+            }
+            return Task::batch(tasks);
+        }
+        if let Some(id) = self.nav_id(&Page::Pinned(Pin::Tag(name.clone()))) {
+            return self.on_nav_select(id);
+        }
+        if let Some(id) = self.nav_id(&Page::Hashtags) {
+            return Task::batch([
+                self.on_nav_select(id),
+                self.hashtags.update(hashtags::Message::Select(name)),
+            ]);
+        }
+        match self.nav_id(&Page::Search) {
+            Some(id) => Task::batch([
+                self.on_nav_select(id),
+                self.search.update(search::Message::SelectTag(name)),
+            ]),
+            None => Task::none(),
+        }
+    }
+
+    /// Make sure every pin of the active account has its own [`Timeline`]
+    /// (loading its disk snapshot), and drop timelines that are no longer
+    /// pinned.
+    fn sync_pinned(&mut self) -> Task<Message> {
+        let pins = self.config.pins(&self.mastodon.base_url).to_vec();
+        self.pinned.retain(|pin, _| pins.contains(pin));
+        self.cache.pinned = pins.iter().map(Pin::kind).collect();
+
+        let mut tasks = vec![];
+        for pin in pins {
+            if self.pinned.contains_key(&pin) {
+                continue;
+            }
+            let mut timeline = Timeline::new(self.mastodon.clone(), pin.kind());
+            tasks.push(timeline.load_cached());
+            self.pinned.insert(pin, timeline);
+        }
+        Task::batch(tasks)
+    }
+
+    /// Pin `pin` for the active account, or unpin it if already pinned, and
+    /// persist the change.
+    fn toggle_pin(&mut self, pin: Pin) -> Task<Message> {
+        let mut pinned = self.config.pinned.clone();
+        let pins = pinned.entry(self.mastodon.base_url.clone()).or_default();
+        if let Some(index) = pins.iter().position(|existing| *existing == pin) {
+            pins.remove(index);
+        } else {
+            pins.push(pin);
+        }
+        self.set_pinned(pinned);
+        let task = self.sync_pinned();
+        self.update_navbar();
+        task
+    }
+
+    /// Keep a pinned list's sidebar label in step with a rename.
+    fn rename_list_pin(&mut self, id: &str, title: &str) {
+        let mut pinned = self.config.pinned.clone();
+        let Some(pins) = pinned.get_mut(&self.mastodon.base_url) else {
+            return;
+        };
+        let mut renamed = None;
+        for pin in pins.iter_mut() {
+            if let Pin::List(pin_id, pin_title) = pin {
+                if pin_id == id && pin_title != title {
+                    let old = pin.clone();
+                    *pin_title = title.to_string();
+                    renamed = Some((old, pin.clone()));
+                }
+            }
+        }
+        let Some((old, new)) = renamed else {
+            return;
+        };
+        if let Some(timeline) = self.pinned.remove(&old) {
+            self.pinned.insert(new, timeline);
+        }
+        self.set_pinned(pinned);
+        self.update_navbar();
+    }
+
+    /// Whether the sidebar has a Deck page, and new hashtag timelines open as
+    /// deck columns. Deck columns are mostly account feeds, so it's only used
+    /// while logged in.
+    fn is_deck_active(&self) -> bool {
+        self.config.deck_mode && self.mastodon.is_authenticated()
+    }
+
+    /// Rebuild the deck from the active account's saved layout.
+    fn load_deck(&mut self) -> Task<Message> {
+        let layout = self.config.deck_layout(&self.mastodon.base_url);
+        let mut tasks = vec![self.deck.set_layout(self.mastodon.clone(), &layout)];
+        if self.is_deck_active() && !self.lists.is_loaded() {
+            tasks.push(self.lists.load());
+        }
+        Task::batch(tasks)
+    }
+
+    fn set_pinned(&mut self, pinned: HashMap<String, Vec<Pin>>) {
+        match self.handler {
+            Some(ref handler) => {
+                if let Err(err) = self.config.set_pinned(handler, pinned) {
+                    tracing::error!("{err}");
+                }
+            }
+            None => self.config.pinned = pinned,
+        }
+    }
+}
+
+/// Fill the sidebar with the pages available for the current login state,
+/// followed by the account's pinned timelines.
+/// Fill the sidebar with the pages `mastodon` can show, then `pins`. In deck
+/// mode the deck comes first and is where the app opens.
+fn populate_nav(nav: &mut nav_bar::Model, mastodon: &Client, pins: &[Pin], deck: bool) {
+    let authenticated = mastodon.is_authenticated();
+    let software = mastodon.software;
+    let deck = deck && authenticated;
+    let variants = (!authenticated)
+        .then(Page::public_variants)
+        .unwrap_or_else(Page::variants)
+        .into_iter()
+        .filter(|page| match page {
+            Page::Deck => deck,
+            Page::Hashtags => software.supports_followed_tags(),
+            Page::Lists => software.supports_lists(),
+            _ => true,
+        });
+    let pins = pins.iter().filter(|_| authenticated).cloned().map(Page::Pinned);
+
+    for page in variants.chain(pins) {
+        let id = nav
+            .insert()
+            .text(page.to_string())
+            .icon(widget::icon::from_name(page.icon()))
+            .data::<Page>(page.clone())
+            .id();
+
+        if (page == Page::default() && !deck) || page == Page::Deck {
+            nav.activate(id);
+        }
+
+        if authenticated && page == Page::Explore {
+            nav.divider_above_set(id, true);
+        }
+    }
+
+    if let Some(id) = nav
+        .iter()
+        .find(|id| matches!(nav.data::<Page>(*id), Some(Page::Pinned(_))))
+    {
+        nav.divider_above_set(id, true);
     }
 }
 
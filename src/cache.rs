@@ -1,18 +1,67 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use cosmic::{
     iced::core::image,
     widget::{self, image::Handle},
 };
-use megalodon::entities::{Account, Notification, Relationship, Status};
+use megalodon::entities::{Account, Attachment, Notification, Relationship, Status};
 
-use crate::config::FeedDensity;
+use crate::client::Software;
+use crate::config::{FeedDensity, SensitiveMedia, TimestampStyle};
 use crate::error::Error;
+use crate::features::status::{
+    emoji, html,
+    translate::{self, Translation},
+};
+use crate::features::timeline::TimelineKind;
+
+/// How many statuses and notifications to keep in memory before ones no
+/// feed, search or open page refers to are dropped. They're still on disk,
+/// and are reloaded from there (or the server) when opened again.
+const MAX_STATUSES: usize = 2000;
+const MAX_NOTIFICATIONS: usize = 500;
+
+/// How many decoded images to keep in memory before off-screen ones are
+/// dropped. Dropped images reload from the disk cache when next needed.
+const MAX_HANDLES: usize = 500;
 
 #[derive(Debug, Clone)]
 pub struct Cache {
     pub handles: HashMap<String, Handle>,
+    /// When each handle was last requested, for evicting the least recently
+    /// used first.
+    handle_used: HashMap<String, Instant>,
+    /// Every frame of animated custom emoji, keyed by URL. Only decoded
+    /// while [`Cache::play_animations`] is on.
+    animations: HashMap<String, Animation>,
+    /// Loaded images that animate, whether or not their frames were
+    /// decoded, so turning animations on only reloads these.
+    animated: HashSet<String>,
+    /// Whether animated custom emoji play, from
+    /// [`crate::config::TootConfig::play_animated_emoji`].
+    pub play_animations: bool,
+    /// When animations started, and how long they've been playing as of
+    /// the last [`Cache::advance_animations`].
+    animation_start: Instant,
+    animation_clock: Duration,
     pub statuses: HashMap<String, Status>,
+    /// When each status and notification was last cached or shown, for
+    /// evicting the least recently used first.
+    status_used: HashMap<String, Instant>,
+    notification_used: HashMap<String, Instant>,
+    /// Each cached status's body parsed from HTML, keyed by status id, so
+    /// views don't reparse it on every frame.
+    content: HashMap<String, html::Content>,
+    /// The open profile's bio and field values converted from HTML, keyed
+    /// by the HTML, so its view doesn't convert them on every frame.
+    profile_text: HashMap<String, String>,
+    /// Blurry placeholders for media attachments, decoded from their
+    /// blurhashes when their status is cached and keyed by the hash. Shown
+    /// while a preview loads and over covered media.
+    blurhashes: HashMap<String, Handle>,
     pub notifications: HashMap<String, Notification>,
     /// The authenticated user's relationship (following/muting/blocking) to
     /// each account whose profile has been viewed, keyed by account id.
@@ -21,41 +70,130 @@ pub struct Cache {
     /// which statuses/relationships belong to the current user (e.g. to
     /// show a delete action only on your own posts).
     pub me: Option<Account>,
+    /// Instances the authenticated user has blocked. Posts from accounts on
+    /// these domains are filtered out of every feed at render time, so a new
+    /// block takes effect without refetching anything.
+    pub blocked_domains: HashSet<String>,
     /// Timeline display preferences from [`crate::config::TootConfig`],
     /// snapshotted here since it's already threaded through every feature's
     /// `view(&Cache)` call.
     pub hide_boosts: bool,
     pub hide_replies: bool,
     pub feed_density: FeedDensity,
+    pub sensitive_media: SensitiveMedia,
+    /// Statuses whose covered media the user chose to show.
+    pub shown_media: HashSet<String>,
+    /// Whether posts behind content warnings start out expanded.
+    pub expand_warnings: bool,
+    /// Statuses with content warnings the user opened or closed, i.e. that
+    /// aren't in the state [`Cache::expand_warnings`] puts them in.
+    toggled_warnings: HashSet<String>,
+    /// Whether media without alt text gets a "No ALT" badge.
+    pub flag_missing_alt_text: bool,
+    /// Attachments whose alt text the user revealed, by attachment id.
+    pub shown_alt_text: HashSet<String>,
+    /// Translations fetched so far, by status id, kept so switching back to
+    /// one doesn't fetch it again.
+    translations: HashMap<String, Translation>,
+    /// Statuses shown translated rather than in their own language.
+    pub shown_translations: HashSet<String>,
+    /// Statuses with a translation on its way.
+    pub translating: HashSet<String>,
+    /// The language posts are translated into: the UI's.
+    pub language: String,
+    /// Whether a translation service is set up for servers that can't
+    /// translate themselves.
+    pub translation_service: bool,
+    /// Whether the active account's server translates posts itself.
+    pub server_translation: bool,
+    /// How post headers show when posts were made.
+    pub timestamp_style: TimestampStyle,
+    /// The time relative timestamps count from, refreshed on a timer so
+    /// they keep up without every view reading the clock.
+    pub now: DateTime<Utc>,
+    /// Timelines the active account has pinned to the sidebar, so list and
+    /// hashtag pages can offer pin/unpin.
+    pub pinned: HashSet<TimelineKind>,
+    /// The active account's server software, so views can hide actions the
+    /// server doesn't support.
+    pub software: Software,
     /// Set whenever new content is cached; cleared once flushed to disk.
     /// Lets the periodic save subscription skip writing when nothing changed.
     pub dirty: bool,
+    /// Ids of statuses, notifications and relationships (by account id)
+    /// cached since the last flush, so it only upserts what changed.
+    pub unsaved_statuses: HashSet<String>,
+    pub unsaved_notifications: HashSet<String>,
+    pub unsaved_relationships: HashSet<String>,
 }
 
 impl Cache {
     pub fn new() -> Self {
         Self {
             handles: HashMap::new(),
+            handle_used: HashMap::new(),
+            animations: HashMap::new(),
+            animated: HashSet::new(),
+            play_animations: false,
+            animation_start: Instant::now(),
+            animation_clock: Duration::ZERO,
             statuses: HashMap::new(),
+            status_used: HashMap::new(),
+            notification_used: HashMap::new(),
+            content: HashMap::new(),
+            profile_text: HashMap::new(),
+            blurhashes: HashMap::new(),
             notifications: HashMap::new(),
             relationships: HashMap::new(),
             me: None,
+            blocked_domains: HashSet::new(),
             hide_boosts: false,
             hide_replies: false,
             feed_density: FeedDensity::default(),
+            sensitive_media: SensitiveMedia::default(),
+            shown_media: HashSet::new(),
+            expand_warnings: false,
+            toggled_warnings: HashSet::new(),
+            flag_missing_alt_text: false,
+            shown_alt_text: HashSet::new(),
+            translations: HashMap::new(),
+            shown_translations: HashSet::new(),
+            translating: HashSet::new(),
+            language: crate::i18n::language(),
+            translation_service: false,
+            server_translation: false,
+            timestamp_style: TimestampStyle::default(),
+            now: Utc::now(),
+            pinned: HashSet::new(),
+            software: Software::default(),
             dirty: false,
+            unsaved_statuses: HashSet::new(),
+            unsaved_notifications: HashSet::new(),
+            unsaved_relationships: HashSet::new(),
         }
     }
 
     /// Whether a status should be shown given the current display preferences.
     pub fn is_visible(&self, status: &Status) -> bool {
         !(self.hide_boosts && status.reblog.is_some()
-            || self.hide_replies && status.in_reply_to_id.is_some())
+            || self.hide_replies && status.in_reply_to_id.is_some()
+            || self.is_domain_blocked(&status.account)
+            || status
+                .reblog
+                .as_ref()
+                .is_some_and(|reblog| self.is_domain_blocked(&reblog.account)))
+    }
+
+    /// Whether the account lives on an instance the user has blocked.
+    pub fn is_domain_blocked(&self, account: &Account) -> bool {
+        account_domain(account).is_some_and(|domain| self.blocked_domains.contains(domain))
     }
 
     pub fn insert_relationship(&mut self, relationship: Relationship) {
+        self.unsaved_relationships.insert(relationship.id.clone());
         self.relationships
             .insert(relationship.id.clone(), relationship);
+        self.dirty = true;
     }
 
     /// Whether the given account id belongs to the authenticated user.
@@ -64,14 +202,182 @@ impl Cache {
     }
 
     pub fn insert_status(&mut self, status: Status) {
+        self.parse_content(&status);
+        self.decode_blurhashes(&status);
+        self.unsaved_statuses.insert(status.id.to_string());
+        self.status_used.insert(status.id.to_string(), Instant::now());
         self.statuses.insert(status.id.to_string(), status.clone());
         if let Some(reblog) = status.reblog {
+            self.parse_content(&reblog);
+            self.decode_blurhashes(&reblog);
+            self.unsaved_statuses.insert(reblog.id.to_string());
+            self.status_used.insert(reblog.id.to_string(), Instant::now());
             self.statuses.insert(reblog.id.to_string(), *reblog);
         }
         self.dirty = true;
     }
 
+    /// Parse a status's body, unless the cached copy already has the same
+    /// body (i.e. it wasn't edited).
+    fn parse_content(&mut self, status: &Status) {
+        let unchanged = self.content.contains_key(&status.id)
+            && self
+                .statuses
+                .get(&status.id)
+                .is_some_and(|cached| cached.content == status.content);
+        if !unchanged {
+            self.content.insert(
+                status.id.clone(),
+                html::parse(&status.content, &status.mentions, &status.emojis),
+            );
+        }
+    }
+
+    fn decode_blurhashes(&mut self, status: &Status) {
+        for hash in status
+            .media_attachments
+            .iter()
+            .filter_map(|media| media.blurhash.as_ref())
+        {
+            if !self.blurhashes.contains_key(hash) {
+                if let Some(handle) = decode_blurhash(hash) {
+                    self.blurhashes.insert(hash.clone(), handle);
+                }
+            }
+        }
+    }
+
+    /// An attachment's blurhash placeholder, if it has one.
+    pub fn blurhash(&self, media: &Attachment) -> Option<&Handle> {
+        self.blurhashes.get(media.blurhash.as_ref()?)
+    }
+
+    /// Whether the sensitive media setting puts a status's media behind a
+    /// cover (which the user may still have chosen to lift).
+    pub fn covers_media(&self, status: &Status) -> bool {
+        match self.sensitive_media {
+            SensitiveMedia::Hide => status.sensitive,
+            SensitiveMedia::ShowAll => false,
+            SensitiveMedia::HideAll => true,
+        }
+    }
+
+    /// Whether a status's content is shown: it has no content warning, or
+    /// its warning is expanded.
+    pub fn is_content_shown(&self, status: &Status) -> bool {
+        status.spoiler_text.is_empty()
+            || self.expand_warnings != self.toggled_warnings.contains(&status.id)
+    }
+
+    /// Expand (`true`) or collapse a status's content warning. Media behind
+    /// the warning is shown or covered along with it.
+    pub fn show_content(&mut self, id: String, shown: bool) {
+        if shown {
+            self.shown_media.insert(id.clone());
+        } else {
+            self.shown_media.remove(&id);
+        }
+        if shown == self.expand_warnings {
+            self.toggled_warnings.remove(&id);
+        } else {
+            self.toggled_warnings.insert(id);
+        }
+    }
+
+    /// Switch whether content warnings start out expanded, putting every
+    /// post back in the new default state.
+    pub fn set_expand_warnings(&mut self, expand: bool) {
+        self.expand_warnings = expand;
+        self.toggled_warnings.clear();
+    }
+
+    /// Whether a status is in another language than the UI's and can be
+    /// translated.
+    pub fn can_translate(&self, status: &Status) -> bool {
+        (self.server_translation || self.translation_service)
+            && !status.content.is_empty()
+            && status
+                .language
+                .as_deref()
+                .is_some_and(|language| translate::is_foreign(language, &self.language))
+    }
+
+    /// A status's translation, if it's shown translated.
+    pub fn translation(&self, status: &Status) -> Option<&Translation> {
+        self.shown_translations
+            .contains(&status.id)
+            .then(|| self.translations.get(&status.id))
+            .flatten()
+    }
+
+    /// Whether a status's translation was already fetched.
+    pub fn has_translation(&self, id: &str) -> bool {
+        self.translations.contains_key(id)
+    }
+
+    /// Keep a status's translation and show it.
+    pub fn insert_translation(&mut self, id: String, translation: Translation) {
+        self.translating.remove(&id);
+        self.shown_translations.insert(id.clone());
+        self.translations.insert(id, translation);
+    }
+
+    /// A status's parsed body: the cached parse if there is one, or parsed
+    /// now for statuses that were never cached.
+    pub fn content<'a>(&'a self, status: &'a Status) -> Cow<'a, html::Content> {
+        match self.content.get(&status.id) {
+            Some(content) => Cow::Borrowed(content),
+            None => Cow::Owned(html::parse(
+                &status.content,
+                &status.mentions,
+                &status.emojis,
+            )),
+        }
+    }
+
+    /// Convert a profile's bio and field values to text ahead of showing
+    /// it, replacing the previously opened profile's.
+    pub fn convert_profile_text(&mut self, account: &Account) {
+        self.profile_text.clear();
+        for html in std::iter::once(&account.note)
+            .chain(account.fields.iter().map(|field| &field.value))
+        {
+            self.profile_text
+                .entry(html.clone())
+                .or_insert_with(|| html_to_text(html));
+        }
+    }
+
+    /// Profile bio or field HTML as text: the converted copy if there is
+    /// one, or converted now.
+    pub fn profile_text<'a>(&'a self, html: &str) -> Cow<'a, str> {
+        match self.profile_text.get(html) {
+            Some(text) => Cow::Borrowed(text),
+            None => Cow::Owned(html_to_text(html)),
+        }
+    }
+
+    pub fn remove_status(&mut self, id: &str) {
+        if let Some(status) = self.statuses.remove(id) {
+            for media in &status.media_attachments {
+                if let Some(hash) = &media.blurhash {
+                    self.blurhashes.remove(hash);
+                }
+                self.shown_alt_text.remove(&media.id);
+            }
+        }
+        self.status_used.remove(id);
+        self.content.remove(id);
+        self.shown_media.remove(id);
+        self.toggled_warnings.remove(id);
+        self.translations.remove(id);
+        self.shown_translations.remove(id);
+    }
+
     pub fn insert_notification(&mut self, notification: Notification) {
+        self.unsaved_notifications.insert(notification.id.to_string());
+        self.notification_used
+            .insert(notification.id.to_string(), Instant::now());
         self.notifications
             .insert(notification.id.to_string(), notification.clone());
         if let Some(status) = notification.status {
@@ -81,19 +387,165 @@ impl Cache {
     }
 
     pub fn insert_handle(&mut self, url: String, handle: Handle) {
+        self.handle_used.insert(url.clone(), Instant::now());
         self.handles.insert(url, handle);
     }
 
+    /// Cache an image loaded by [`get`], with its frames if it has them.
+    pub fn insert_image(&mut self, url: String, image: Image) {
+        if image.animated {
+            self.animated.insert(url.clone());
+        }
+        if let Some(animation) = image.animation {
+            self.animations.insert(url.clone(), animation);
+        }
+        self.insert_handle(url, image.handle);
+    }
+
+    /// Mark an already-loaded image as just requested again.
+    pub fn touch_handle(&mut self, url: &str) {
+        if let Some(used) = self.handle_used.get_mut(url) {
+            *used = Instant::now();
+        }
+    }
+
+    /// Drop the least recently used statuses and notifications beyond
+    /// [`MAX_STATUSES`] and [`MAX_NOTIFICATIONS`] that aren't in
+    /// `statuses`/`notifications` (the ones something still shows, which
+    /// count as just used). Boosted originals and notifications' statuses
+    /// stay with whatever refers to them, and nothing unsaved is dropped.
+    pub fn evict_statuses(
+        &mut self,
+        mut statuses: HashSet<String>,
+        notifications: HashSet<String>,
+    ) {
+        touch(&mut self.notification_used, &notifications);
+        let excess = self.notifications.len().saturating_sub(MAX_NOTIFICATIONS);
+        let evicted = least_recently_used(&self.notification_used, excess, |id| {
+            !notifications.contains(id) && !self.unsaved_notifications.contains(id)
+        });
+        for id in evicted {
+            self.notifications.remove(&id);
+            self.notification_used.remove(&id);
+        }
+
+        statuses.extend(
+            self.notifications
+                .values()
+                .filter_map(|notification| notification.status.as_ref())
+                .map(|status| status.id.clone()),
+        );
+        let reblogs: Vec<String> = statuses
+            .iter()
+            .filter_map(|id| self.statuses.get(id)?.reblog.as_ref())
+            .map(|reblog| reblog.id.clone())
+            .collect();
+        statuses.extend(reblogs);
+
+        touch(&mut self.status_used, &statuses);
+        let excess = self.statuses.len().saturating_sub(MAX_STATUSES);
+        let evicted = least_recently_used(&self.status_used, excess, |id| {
+            !statuses.contains(id) && !self.unsaved_statuses.contains(id)
+        });
+        for id in evicted {
+            self.remove_status(&id);
+        }
+    }
+
+    /// Once more than [`MAX_HANDLES`] images are loaded, drop the least
+    /// recently requested ones that aren't in `keep` (the images on screen).
+    pub fn evict_handles(&mut self, keep: &HashSet<String>) {
+        let excess = self.handles.len().saturating_sub(MAX_HANDLES);
+        let evicted = least_recently_used(&self.handle_used, excess, |url| !keep.contains(url));
+        for url in evicted {
+            self.handles.remove(&url);
+            self.handle_used.remove(&url);
+            self.animations.remove(&url);
+            self.animated.remove(&url);
+        }
+    }
+
+    /// Drop the loaded images that animate, so they're reloaded (from disk)
+    /// with their frames when next needed.
+    pub fn clear_animated_handles(&mut self) {
+        for url in self.animated.drain() {
+            self.handles.remove(&url);
+            self.handle_used.remove(&url);
+            self.animations.remove(&url);
+        }
+    }
+
+    /// Whether any of `urls` is an emoji that's playing, i.e. whether to
+    /// keep advancing animations while they're on screen.
+    pub fn is_animating<'a>(&self, mut urls: impl Iterator<Item = &'a String>) -> bool {
+        self.play_animations && urls.any(|url| self.animations.contains_key(url))
+    }
+
+    /// Move animations on to the current time.
+    pub fn advance_animations(&mut self) {
+        self.animation_clock = self.animation_start.elapsed();
+    }
+
+    /// A custom emoji's image: its current frame if it animates, otherwise
+    /// the (first frame of the) loaded image.
+    pub fn emoji_handle(&self, url: &str) -> Option<&Handle> {
+        self.animations
+            .get(url)
+            .filter(|_| self.play_animations)
+            .map(|animation| animation.frame(self.animation_clock))
+            .or_else(|| self.handles.get(url))
+    }
+
     pub fn clear(&mut self) {
         self.statuses.clear();
+        self.status_used.clear();
+        self.notification_used.clear();
+        self.content.clear();
+        self.profile_text.clear();
+        self.blurhashes.clear();
+        self.shown_media.clear();
+        self.toggled_warnings.clear();
+        self.shown_alt_text.clear();
+        self.translations.clear();
+        self.shown_translations.clear();
+        self.server_translation = false;
+        self.translating.clear();
         self.notifications.clear();
         self.handles.clear();
+        self.handle_used.clear();
+        self.animations.clear();
+        self.animated.clear();
         self.relationships.clear();
         self.me = None;
+        self.blocked_domains.clear();
         self.dirty = false;
+        self.unsaved_statuses.clear();
+        self.unsaved_notifications.clear();
+        self.unsaved_relationships.clear();
     }
 }
 
+/// Convert profile bio or field HTML to text for display. Should conversion ever
+/// fail, the HTML is shown as-is rather than losing the text.
+pub fn html_to_text(html: &str) -> String {
+    html2text::config::rich()
+        .string_from_read(html.as_bytes(), 700)
+        .unwrap_or_else(|err| {
+            tracing::warn!("failed to convert HTML to text: {err}");
+            html.to_string()
+        })
+}
+
+/// The instance a remote account lives on, taken from the `@domain` suffix
+/// of its `acct`. Local accounts have no suffix and return `None`.
+pub fn account_domain(account: &Account) -> Option<&str> {
+    account
+        .acct
+        .split_once('@')
+        .map(|(_, domain)| domain)
+        .filter(|domain| !domain.is_empty())
+}
+
 pub fn fallback_avatar<'a>() -> widget::Image<'a> {
     widget::image(image::Handle::from_bytes(
         include_bytes!("../assets/missing.png").to_vec(),
@@ -104,24 +556,211 @@ pub fn fallback_handle() -> widget::image::Handle {
     image::Handle::from_bytes(include_bytes!("../assets/missing.png").to_vec())
 }
 
-pub async fn get(url: impl ToString) -> Result<Handle, Error> {
-    let url = url.to_string();
+/// The frames of an animated image, each with how long it shows.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<(Handle, Duration)>,
+    length: Duration,
+}
 
-    if let Some(bytes) = load_cached_image(url.clone()).await {
-        return Ok(Handle::from_bytes(bytes));
+impl Animation {
+    /// The frame showing `elapsed` into the animation, looping.
+    pub fn frame(&self, elapsed: Duration) -> &Handle {
+        let length = self.length.as_millis().max(1);
+        let mut offset = Duration::from_millis((elapsed.as_millis() % length) as u64);
+        for (handle, delay) in &self.frames {
+            if offset < *delay {
+                return handle;
+            }
+            offset -= *delay;
+        }
+        &self.frames[0].0
     }
+}
+
+/// Mark the entries of `used` for `ids` as used just now.
+fn touch(used: &mut HashMap<String, Instant>, ids: &HashSet<String>) {
+    let now = Instant::now();
+    for id in ids {
+        if let Some(used) = used.get_mut(id) {
+            *used = now;
+        }
+    }
+}
+
+/// Up to `count` of the keys of `used` that `evictable` allows, least
+/// recently used first.
+fn least_recently_used(
+    used: &HashMap<String, Instant>,
+    count: usize,
+    evictable: impl Fn(&String) -> bool,
+) -> Vec<String> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let mut candidates: Vec<(&String, &Instant)> =
+        used.iter().filter(|(id, _)| evictable(id)).collect();
+    candidates.sort_by_key(|(_, used)| **used);
+    candidates
+        .into_iter()
+        .take(count)
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+/// Width and height blurhashes are decoded at. They're smooth gradients, so
+/// a few pixels scaled up look the same as decoding at full size.
+const BLURHASH_SIZE: u32 = 32;
 
-    let response = reqwest::get(&url).await?;
-    match response.error_for_status() {
-        Ok(response) => {
-            let bytes = response.bytes().await?.to_vec();
-            save_cached_image(url, bytes.clone());
-            Ok(Handle::from_bytes(bytes))
+fn decode_blurhash(hash: &str) -> Option<Handle> {
+    match blurhash::decode(hash, BLURHASH_SIZE, BLURHASH_SIZE, 1.0) {
+        Ok(pixels) => Some(Handle::from_rgba(BLURHASH_SIZE, BLURHASH_SIZE, pixels)),
+        Err(err) => {
+            tracing::debug!("invalid blurhash {hash:?}: {err}");
+            None
         }
-        Err(err) => Err(err.into()),
     }
 }
 
+/// Animations larger than this (in either dimension) or longer than this
+/// many frames are shown still: custom emoji are small, and decoding every
+/// frame of a large GIF would take a lot of memory.
+const MAX_ANIMATION_SIZE: u32 = 256;
+const MAX_ANIMATION_FRAMES: usize = 120;
+/// Browsers treat shorter frame delays as "as fast as possible" and slow
+/// them down to this; so do we.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+
+/// Whether [`decode_animation`] would find frames in an image, judged from
+/// its header alone: any small enough GIF, or an animated PNG or WebP.
+fn may_animate(bytes: &[u8]) -> bool {
+    use ::image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+    use ::image::{ImageDecoder, ImageFormat};
+
+    let cursor = std::io::Cursor::new(bytes);
+    let dimensions = match ::image::guess_format(bytes) {
+        Ok(ImageFormat::Gif) => GifDecoder::new(cursor).ok().map(|decoder| decoder.dimensions()),
+        Ok(ImageFormat::Png) => PngDecoder::new(cursor)
+            .ok()
+            .filter(|decoder| decoder.is_apng().unwrap_or(false))
+            .map(|decoder| decoder.dimensions()),
+        Ok(ImageFormat::WebP) => WebPDecoder::new(cursor)
+            .ok()
+            .filter(|decoder| decoder.has_animation())
+            .map(|decoder| decoder.dimensions()),
+        _ => None,
+    };
+    dimensions.is_some_and(|(width, height)| {
+        width <= MAX_ANIMATION_SIZE && height <= MAX_ANIMATION_SIZE
+    })
+}
+
+/// Decode every frame of an animated GIF, PNG or WebP, or `None` if it's a
+/// still image (or too big to animate).
+fn decode_animation(bytes: &[u8]) -> Option<Animation> {
+    use ::image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+    use ::image::{AnimationDecoder, ImageDecoder, ImageFormat};
+
+    let cursor = std::io::Cursor::new(bytes);
+    let (frames, (width, height)) = match ::image::guess_format(bytes).ok()? {
+        ImageFormat::Gif => {
+            let decoder = GifDecoder::new(cursor).ok()?;
+            let dimensions = decoder.dimensions();
+            (decoder.into_frames(), dimensions)
+        }
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(cursor).ok()?;
+            if !decoder.is_apng().ok()? {
+                return None;
+            }
+            let dimensions = decoder.dimensions();
+            (decoder.apng().ok()?.into_frames(), dimensions)
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(cursor).ok()?;
+            if !decoder.has_animation() {
+                return None;
+            }
+            let dimensions = decoder.dimensions();
+            (decoder.into_frames(), dimensions)
+        }
+        _ => return None,
+    };
+    if width > MAX_ANIMATION_SIZE || height > MAX_ANIMATION_SIZE {
+        return None;
+    }
+
+    let frames: Vec<(Handle, Duration)> = frames
+        .take(MAX_ANIMATION_FRAMES)
+        .map_while(Result::ok)
+        .map(|frame| {
+            let delay = Duration::from(frame.delay()).max(MIN_FRAME_DELAY);
+            let buffer = frame.into_buffer();
+            let handle = Handle::from_rgba(buffer.width(), buffer.height(), buffer.into_raw());
+            (handle, delay)
+        })
+        .collect();
+    if frames.len() < 2 {
+        return None;
+    }
+    let length = frames.iter().map(|(_, delay)| *delay).sum();
+    Some(Animation { frames, length })
+}
+
+/// An image loaded by [`get`].
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub handle: Handle,
+    /// Its frames, if it animates and they were asked for.
+    pub animation: Option<Animation>,
+    /// Whether it animates (or may: still GIFs count), even if its frames
+    /// weren't decoded.
+    pub animated: bool,
+}
+
+/// Load an image from the disk cache, or else download it. With `animate`,
+/// also decodes the frames of small animated images.
+pub async fn get(url: impl ToString, animate: bool) -> Result<Image, Error> {
+    let bytes = download(&url.to_string()).await?;
+
+    let (animation, animated) = if animate {
+        let bytes = bytes.clone();
+        let animation = tokio::task::spawn_blocking(move || decode_animation(&bytes))
+            .await
+            .ok()
+            .flatten();
+        let animated = animation.is_some();
+        (animation, animated)
+    } else {
+        (None, may_animate(&bytes))
+    };
+    Ok(Image {
+        handle: Handle::from_bytes(bytes),
+        animation,
+        animated,
+    })
+}
+
+/// A media file's bytes from the disk cache, or else downloaded (and then
+/// saved to the cache).
+pub async fn download(url: &str) -> Result<Vec<u8>, Error> {
+    if let Some(bytes) = load_cached_image(url.to_string()).await {
+        return Ok(bytes);
+    }
+    let bytes = fetch(url).await?;
+    save_cached_image(url.to_string(), bytes.clone());
+    Ok(bytes)
+}
+
+/// Download a media file, bypassing the disk cache.
+pub async fn fetch(url: &str) -> Result<Vec<u8>, Error> {
+    crate::error::retry(|| async {
+        let response = crate::error::check(reqwest::get(url).await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    })
+    .await
+}
+
 /// Disk reads/writes are blocking; run them on tokio's blocking pool so a
 /// cold or growing image cache doesn't stall the async executor (and with
 /// it, every other in-flight fetch) one file at a time.
@@ -162,6 +801,7 @@ pub fn extract_status_images(status: &Status) -> Vec<String> {
                 urls.push(url.clone());
             }
         }
+        urls.extend(status_emoji_images(reblog));
     }
 
     if let Some(card) = &status.card {
@@ -175,10 +815,23 @@ pub fn extract_status_images(status: &Status) -> Vec<String> {
             urls.push(url.clone());
         }
     }
+    urls.extend(status_emoji_images(status));
 
     urls
 }
 
+/// Custom emoji in a status's text, its author's name and its reactions.
+fn status_emoji_images(status: &Status) -> impl Iterator<Item = String> + '_ {
+    let reactions = status
+        .emoji_reactions
+        .iter()
+        .flatten()
+        .filter_map(|reaction| reaction.url.clone());
+    emoji::image_urls(&status.emojis)
+        .chain(emoji::image_urls(&status.account.emojis))
+        .chain(reactions)
+}
+
 pub fn extract_notification_images(notification: &Notification) -> Vec<String> {
     let mut urls: Vec<String> = Vec::new();
     if let Some(account) = &notification.account {
@@ -188,6 +841,7 @@ pub fn extract_notification_images(notification: &Notification) -> Vec<String> {
         if !account.header.is_empty() {
             urls.push(account.header.clone());
         }
+        urls.extend(emoji::image_urls(&account.emojis));
     }
 
     if let Some(status) = &notification.status {
@@ -207,6 +861,7 @@ pub fn extract_notification_images(notification: &Notification) -> Vec<String> {
                 urls.push(url.clone());
             }
         }
+        urls.extend(status_emoji_images(status));
     }
     urls
 }
@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: {{LICENSE}}
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use megalodon::Megalodon;
+use megalodon::{entities::Relationship, Megalodon};
+
+use crate::error::{self, Error};
 
 /// Wraps a megalodon client together with the connection details we need to
 /// track ourselves (megalodon's clients don't expose their base URL or token).
@@ -10,6 +12,7 @@ use megalodon::Megalodon;
 pub struct Client {
     pub base_url: String,
     pub token: Option<String>,
+    pub software: Software,
     inner: Arc<Box<dyn Megalodon + Send + Sync>>,
 }
 
@@ -17,6 +20,7 @@ impl std::fmt::Debug for Client {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Client")
             .field("base_url", &self.base_url)
+            .field("software", &self.software)
             .field("token", &self.token.as_ref().map(|_| "<redacted>"))
             .finish()
     }
@@ -26,21 +30,24 @@ impl std::hash::Hash for Client {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.base_url.hash(state);
         self.token.hash(state);
+        self.software.hash(state);
     }
 }
 
 impl PartialEq for Client {
     fn eq(&self, other: &Self) -> bool {
-        self.base_url == other.base_url && self.token == other.token
+        self.base_url == other.base_url
+            && self.token == other.token
+            && self.software == other.software
     }
 }
 
 impl Eq for Client {}
 
 impl Client {
-    pub fn new(base_url: String, token: Option<String>) -> Self {
+    pub fn new(software: Software, base_url: String, token: Option<String>) -> Self {
         let inner = megalodon::generator(
-            megalodon::SNS::Mastodon,
+            software.sns(),
             base_url.clone(),
             token.clone(),
             Some("toot".to_string()),
@@ -49,6 +56,7 @@ impl Client {
         Self {
             base_url,
             token,
+            software,
             inner: Arc::new(inner),
         }
     }
@@ -56,6 +64,98 @@ impl Client {
     pub fn is_authenticated(&self) -> bool {
         self.token.as_ref().is_some_and(|token| !token.is_empty())
     }
+
+    /// A request to a REST endpoint megalodon doesn't wrap (or wraps without
+    /// the fields we need), authorized with this session's token. `path` is
+    /// relative to the instance, e.g. `/api/v1/lists`.
+    pub fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(self.token.as_deref().unwrap_or_default())
+    }
+
+    /// Fill in when a rate limit resets for a megalodon call that hit it:
+    /// megalodon drops the response headers, so ask the server again with a
+    /// cheap request, which counts against the same limit and carries them.
+    pub async fn with_rate_limit_reset<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        match result {
+            Err(Error::RateLimited(None)) => {
+                let probe = self.request(reqwest::Method::GET, "/api/v1/instance");
+                let reset = match probe.send().await {
+                    Ok(response) => error::rate_limit_reset(response.headers()),
+                    Err(_) => None,
+                };
+                Err(Error::RateLimited(reset))
+            }
+            result => result,
+        }
+    }
+
+    /// Feature (`pin`) or unfeature (`unpin`) an account on the user's own
+    /// profile.
+    pub async fn set_endorsed(&self, id: &str, endorse: bool) -> Result<Relationship, Error> {
+        let action = if endorse { "pin" } else { "unpin" };
+        let response = self
+            .request(reqwest::Method::POST, &format!("/api/v1/accounts/{id}/{action}"))
+            .send()
+            .await?;
+        let relationship = error::check(response).await?.json().await?;
+        Ok(relationship)
+    }
+
+    /// Translate a status into `language` with the instance's translation
+    /// service (Mastodon 4.0+, when the admin has set one up).
+    pub async fn translate_status(
+        &self,
+        id: &str,
+        language: &str,
+    ) -> Result<ServerTranslation, Error> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("/api/v1/statuses/{id}/translate"))
+            .form(&[("lang", language)])
+            .send()
+            .await?;
+        let translation = error::check(response).await?.json().await?;
+        Ok(translation)
+    }
+
+    /// Whether the instance's admin has set up a translation service, as
+    /// Mastodon reports in its v2 instance info.
+    pub async fn translation_enabled(&self) -> Result<bool, Error> {
+        #[derive(serde::Deserialize)]
+        struct Instance {
+            configuration: Configuration,
+        }
+        #[derive(serde::Deserialize)]
+        struct Configuration {
+            translation: Option<Translation>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Translation {
+            enabled: bool,
+        }
+
+        let response = self
+            .request(reqwest::Method::GET, "/api/v2/instance")
+            .send()
+            .await?;
+        let instance: Instance = error::check(response).await?.json().await?;
+        Ok(instance
+            .configuration
+            .translation
+            .is_some_and(|translation| translation.enabled))
+    }
+
+    /// Invalidate this client's access token on the server, so it stops
+    /// working even if a copy of it survives somewhere.
+    pub async fn revoke(&self, app: &AppCredentials) -> Result<(), Error> {
+        let Some(token) = self.token.clone() else {
+            return Ok(());
+        };
+        self.revoke_token(app.client_id.clone(), app.client_secret.clone(), token)
+            .await?;
+        Ok(())
+    }
 }
 
 impl std::ops::Deref for Client {
@@ -66,10 +166,157 @@ impl std::ops::Deref for Client {
     }
 }
 
+/// The server software behind an instance, which decides both the API
+/// megalodon speaks to it and which features the UI offers.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Software {
+    /// Mastodon and its forks (Hometown, Glitch, Fedibird, ...), plus anything
+    /// unrecognized, since most servers implement the Mastodon API.
+    #[default]
+    Mastodon,
+    /// Pleroma and Akkoma.
+    Pleroma,
+    Friendica,
+    /// Misskey and its forks (Firefish, Iceshrimp, Sharkey, ...), which share
+    /// the Misskey API.
+    Misskey,
+    GoToSocial,
+    Pixelfed,
+}
+
+impl Software {
+    fn sns(self) -> megalodon::SNS {
+        match self {
+            Software::Mastodon => megalodon::SNS::Mastodon,
+            Software::Pleroma => megalodon::SNS::Pleroma,
+            Software::Friendica => megalodon::SNS::Friendica,
+            Software::Misskey => megalodon::SNS::Firefish,
+            Software::GoToSocial => megalodon::SNS::Gotosocial,
+            Software::Pixelfed => megalodon::SNS::Pixelfed,
+        }
+    }
+
+    fn from_nodeinfo_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "pleroma" | "akkoma" => Software::Pleroma,
+            "friendica" => Software::Friendica,
+            "misskey" | "firefish" | "calckey" | "iceshrimp" | "sharkey" | "foundkey"
+            | "cherrypick" => Software::Misskey,
+            "gotosocial" => Software::GoToSocial,
+            "pixelfed" => Software::Pixelfed,
+            _ => Software::Mastodon,
+        }
+    }
+
+    /// Detect the software an instance runs from its NodeInfo document.
+    pub async fn detect(base_url: &str) -> Result<Self, Error> {
+        #[derive(serde::Deserialize)]
+        struct Links {
+            links: Vec<Link>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Link {
+            href: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct NodeInfo {
+            software: NodeSoftware,
+        }
+        #[derive(serde::Deserialize)]
+        struct NodeSoftware {
+            name: String,
+        }
+
+        let client = reqwest::Client::new();
+        let links: Links = client
+            .get(format!("{base_url}/.well-known/nodeinfo"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let Some(link) = links.links.last() else {
+            return Ok(Software::Mastodon);
+        };
+        let nodeinfo: NodeInfo = client
+            .get(&link.href)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Software::from_nodeinfo_name(&nodeinfo.software.name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Software::Mastodon => "Mastodon",
+            Software::Pleroma => "Pleroma/Akkoma",
+            Software::Friendica => "Friendica",
+            Software::Misskey => "Misskey",
+            Software::GoToSocial => "GoToSocial",
+            Software::Pixelfed => "Pixelfed",
+        }
+    }
+
+    /// Emoji reactions on posts.
+    pub fn supports_reactions(self) -> bool {
+        matches!(self, Software::Pleroma | Software::Misskey)
+    }
+
+    /// Following hashtags into the home timeline.
+    pub fn supports_followed_tags(self) -> bool {
+        matches!(self, Software::Mastodon | Software::GoToSocial)
+    }
+
+    /// User lists.
+    pub fn supports_lists(self) -> bool {
+        !matches!(self, Software::Pixelfed)
+    }
+
+    /// Featuring accounts on your profile (Mastodon's endorsements).
+    pub fn supports_endorsements(self) -> bool {
+        matches!(self, Software::Mastodon | Software::Pleroma)
+    }
+
+    /// Translating posts with Mastodon's translate endpoint.
+    pub fn supports_translation(self) -> bool {
+        matches!(self, Software::Mastodon)
+    }
+}
+
+/// A status translated by the instance.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ServerTranslation {
+    /// The translated body, as HTML.
+    pub content: String,
+    #[serde(default)]
+    pub spoiler_text: String,
+    pub detected_source_language: Option<String>,
+    /// The translation service the instance used, e.g. "DeepL.com".
+    pub provider: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Session {
     pub base_url: String,
     pub token: String,
+    /// Sessions saved before server detection existed are all Mastodon.
+    #[serde(default)]
+    pub software: Software,
+}
+
+/// Toot's OAuth app registration on an instance, needed to revoke tokens and
+/// reused when logging in there again instead of registering a new app.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AppCredentials {
+    pub client_id: String,
+    pub client_secret: String,
+    /// The redirect URI the app was registered with; authorization requests
+    /// and token exchanges have to repeat it exactly.
+    pub redirect_uri: String,
 }
 
 /// All accounts saved in the keychain, and which one is active. Persisted as
@@ -78,6 +325,14 @@ pub struct Session {
 pub struct Sessions {
     pub active: usize,
     pub sessions: Vec<Session>,
+    /// App registrations by instance base URL, shared by every session on
+    /// that instance and kept after logging out of it.
+    #[serde(default)]
+    pub apps: HashMap<String, AppCredentials>,
+    /// The API key of the translation service in the settings, kept here
+    /// rather than in the plain-text config.
+    #[serde(default)]
+    pub translation_api_key: String,
 }
 
 impl Sessions {
@@ -92,6 +347,8 @@ impl Sessions {
             .map(|session| Sessions {
                 active: 0,
                 sessions: vec![session],
+                apps: HashMap::new(),
+                translation_api_key: String::new(),
             })
     }
 
@@ -1,11 +1,16 @@
 // SPDX-License-Identifier: {{LICENSE}}
 
+use std::collections::HashMap;
+use std::time::Duration;
+
 use cosmic::{
     cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, Config, CosmicConfigEntry},
     Application,
 };
 
 use crate::app::AppModel;
+use crate::features::deck::ColumnKind;
+use crate::features::timeline::TimelineKind;
 
 #[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
@@ -19,6 +24,64 @@ pub struct TootConfig {
     pub feed_density: FeedDensity,
     /// Preferred theme.
     pub theme_mode: ThemeMode,
+    /// List and hashtag timelines pinned as their own sidebar pages, keyed
+    /// by the account's base URL (since lists are per-account).
+    pub pinned: HashMap<String, Vec<Pin>>,
+    /// Show several feeds side by side as columns instead of one page at a time.
+    pub deck_mode: bool,
+    /// Each account's deck columns, in order, keyed by base URL.
+    pub deck: HashMap<String, Vec<ColumnKind>>,
+    /// Play animated custom emoji rather than showing their first frame.
+    pub play_animated_emoji: bool,
+    /// Which media attachments are covered until clicked.
+    pub sensitive_media: SensitiveMedia,
+    /// Show posts behind content warnings without having to click them open.
+    pub expand_content_warnings: bool,
+    /// Mark media posted without alt text, so it's easy to spot.
+    pub flag_missing_alt_text: bool,
+    /// Whether posts show how long ago they were made, or the date and time.
+    pub timestamp_style: TimestampStyle,
+    /// A LibreTranslate-compatible service to translate posts with when the
+    /// server can't. Empty for none.
+    /// Its API key, if it needs one, is kept with the saved accounts.
+    pub translation_url: String,
+    /// How much disk space downloaded images may take up.
+    pub image_cache_limit: ImageCacheLimit,
+    /// How long a downloaded image is kept after it was last shown.
+    pub image_cache_age: ImageCacheAge,
+}
+
+/// A list or hashtag timeline pinned as its own sidebar page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Pin {
+    /// A user list: (id, title). The title is kept so the sidebar can be
+    /// built before lists are fetched.
+    List(String, String),
+    /// A hashtag, by name.
+    Tag(String),
+}
+
+impl Pin {
+    pub fn kind(&self) -> TimelineKind {
+        match self {
+            Pin::List(id, _) => TimelineKind::List(id.clone()),
+            Pin::Tag(name) => TimelineKind::Tag(name.clone()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Pin::List(_, title) => title.clone(),
+            Pin::Tag(name) => format!("#{name}"),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Pin::List(..) => "view-list-symbolic",
+            Pin::Tag(_) => "lang-include-symbolic",
+        }
+    }
 }
 
 /// The user's preferred theme.
@@ -78,7 +141,148 @@ impl FeedDensity {
     }
 }
 
+/// Which media attachments are hidden behind a blurred cover until the user
+/// chooses to show them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SensitiveMedia {
+    /// Cover media marked as sensitive.
+    #[default]
+    Hide,
+    /// Never cover media, even when marked as sensitive.
+    ShowAll,
+    /// Cover all media, sensitive or not.
+    HideAll,
+}
+
+impl SensitiveMedia {
+    pub const ALL: [SensitiveMedia; 3] = [
+        SensitiveMedia::Hide,
+        SensitiveMedia::ShowAll,
+        SensitiveMedia::HideAll,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SensitiveMedia::Hide => "Hide media marked as sensitive",
+            SensitiveMedia::ShowAll => "Always show media",
+            SensitiveMedia::HideAll => "Always hide media",
+        }
+    }
+}
+
+/// How post headers show when a post was made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TimestampStyle {
+    /// How long ago, e.g. "5m", "2h" or "3d".
+    #[default]
+    Relative,
+    /// The local date and time.
+    Absolute,
+}
+
+impl TimestampStyle {
+    pub const ALL: [TimestampStyle; 2] = [TimestampStyle::Relative, TimestampStyle::Absolute];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimestampStyle::Relative => "Time since posted",
+            TimestampStyle::Absolute => "Date and time",
+        }
+    }
+}
+
+/// The most disk space the image cache may use before the least recently
+/// shown images are deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ImageCacheLimit {
+    Small,
+    #[default]
+    Medium,
+    Large,
+    Huge,
+}
+
+impl ImageCacheLimit {
+    pub const ALL: [ImageCacheLimit; 4] = [
+        ImageCacheLimit::Small,
+        ImageCacheLimit::Medium,
+        ImageCacheLimit::Large,
+        ImageCacheLimit::Huge,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImageCacheLimit::Small => "100 MB",
+            ImageCacheLimit::Medium => "250 MB",
+            ImageCacheLimit::Large => "500 MB",
+            ImageCacheLimit::Huge => "1 GB",
+        }
+    }
+
+    pub fn bytes(self) -> u64 {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            ImageCacheLimit::Small => 100 * MB,
+            ImageCacheLimit::Medium => 250 * MB,
+            ImageCacheLimit::Large => 500 * MB,
+            ImageCacheLimit::Huge => 1024 * MB,
+        }
+    }
+}
+
+/// How long an image may go unshown before it's deleted from the cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ImageCacheAge {
+    Day,
+    Week,
+    #[default]
+    Month,
+    Quarter,
+}
+
+impl ImageCacheAge {
+    pub const ALL: [ImageCacheAge; 4] = [
+        ImageCacheAge::Day,
+        ImageCacheAge::Week,
+        ImageCacheAge::Month,
+        ImageCacheAge::Quarter,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImageCacheAge::Day => "1 day",
+            ImageCacheAge::Week => "1 week",
+            ImageCacheAge::Month => "30 days",
+            ImageCacheAge::Quarter => "90 days",
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        const DAY: u64 = 24 * 60 * 60;
+        Duration::from_secs(match self {
+            ImageCacheAge::Day => DAY,
+            ImageCacheAge::Week => 7 * DAY,
+            ImageCacheAge::Month => 30 * DAY,
+            ImageCacheAge::Quarter => 90 * DAY,
+        })
+    }
+}
+
 impl TootConfig {
+    /// The pins saved for the account at `base_url`, in sidebar order.
+    pub fn pins(&self, base_url: &str) -> &[Pin] {
+        self.pinned.get(base_url).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The deck columns saved for the account at `base_url`, defaulting to
+    /// Home and Notifications side by side.
+    pub fn deck_layout(&self, base_url: &str) -> Vec<ColumnKind> {
+        self.deck
+            .get(base_url)
+            .cloned()
+            .unwrap_or_else(|| vec![ColumnKind::Home, ColumnKind::Notifications])
+    }
+
     pub fn config_handler() -> Option<Config> {
         Config::new(AppModel::APP_ID, TootConfig::VERSION).ok()
     }
@@ -1,11 +1,184 @@
+use std::{future::Future, time::Duration};
+
+use chrono::{DateTime, Utc};
+use megalodon::error::Kind;
 use thiserror::Error;
 
+/// How many times an idempotent read is retried after a transient failure.
+const MAX_RETRIES: u32 = 3;
+/// Base delay of the exponential backoff between retries.
+const BACKOFF: Duration = Duration::from_secs(1);
+/// Longest a rate-limited read waits for its limit to reset before giving up
+/// and letting the caller fail.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(300);
+
 #[derive(Debug, Error)]
 pub enum Error {
+    /// The server couldn't be reached: DNS, refused connection.
+    #[error("Couldn't reach the server: {0}")]
+    Network(String),
+    /// The request went out but no answer came back in time, so the server
+    /// may or may not have acted on it.
+    #[error("The server didn't answer in time: {0}")]
+    TimedOut(String),
+    /// 401: the access token was revoked or has expired.
+    #[error("Your login has expired, please log in again")]
+    Unauthorized,
+    /// 429, with when the limit resets if the server said.
+    #[error("Too many requests, try again later")]
+    RateLimited(Option<DateTime<Utc>>),
+    /// 404.
+    #[error("Not found")]
+    NotFound,
+    /// 422, with the server's explanation of what was rejected.
+    #[error("{0}")]
+    Validation(String),
+    /// 5xx.
+    #[error("The server had a problem ({0})")]
+    Server(u16),
+    /// Any other unsuccessful status.
+    #[error("Request failed ({0}): {1}")]
+    Http(u16, String),
     #[error("Mastodon API error: {0}")]
-    Mastodon(#[from] megalodon::error::Error),
+    Mastodon(megalodon::error::Error),
     #[error("Iced error: {0}")]
     Iced(#[from] cosmic::iced::Error),
     #[error("Reqwest error: {0}")]
-    Reqwest(#[from] reqwest::Error),
+    Reqwest(reqwest::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Video or audio that couldn't be decoded or played.
+    #[cfg(feature = "playback")]
+    #[error("Playback error: {0}")]
+    Playback(String),
+    #[error("Login failed: {0}")]
+    Login(String),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Keyring error: {0}")]
+    Keyring(String),
+    #[error("Couldn't decrypt saved accounts; check the passphrase")]
+    Decrypt,
+    #[error("Couldn't encrypt saved accounts")]
+    Encrypt,
+    #[error("No system keyring is available and there's no config directory for an encrypted file")]
+    NoSessionStore,
+}
+
+impl Error {
+    /// The typed error for an unsuccessful HTTP status.
+    fn from_status(status: u16, message: String) -> Self {
+        match status {
+            401 => Error::Unauthorized,
+            404 => Error::NotFound,
+            422 => Error::Validation(message),
+            429 => Error::RateLimited(None),
+            500..=599 => Error::Server(status),
+            _ => Error::Http(status, message),
+        }
+    }
+
+    /// Whether retrying the same request later might succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::Network(_) | Error::TimedOut(_) | Error::RateLimited(_) | Error::Server(_)
+        )
+    }
+
+    /// How long to wait before retry number `attempt` (starting at 0):
+    /// until the rate limit resets if the server said when, otherwise an
+    /// exponential backoff. `None` if a rate limit resets too far out to
+    /// wait for.
+    fn retry_delay(&self, attempt: u32) -> Option<Duration> {
+        if let Error::RateLimited(Some(reset)) = self {
+            let wait = (*reset - Utc::now()).to_std().unwrap_or_default();
+            return (wait <= MAX_RATE_LIMIT_WAIT).then_some(wait);
+        }
+        Some(BACKOFF * 2u32.pow(attempt))
+    }
+}
+
+impl From<megalodon::error::Error> for Error {
+    fn from(err: megalodon::error::Error) -> Self {
+        if let megalodon::error::Error::OwnError(own) = &err {
+            if let (Kind::HTTPStatusError, Some(status)) = (&own.kind, own.status) {
+                return Error::from_status(status, own.message.clone());
+            }
+        }
+        match err {
+            megalodon::error::Error::RequestError(err) => err.into(),
+            err => Error::Mastodon(err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_connect() {
+            return Error::Network(err.to_string());
+        }
+        if err.is_timeout() {
+            return Error::TimedOut(err.to_string());
+        }
+        match err.status() {
+            Some(status) => Error::from_status(status.as_u16(), err.to_string()),
+            None => Error::Reqwest(err),
+        }
+    }
+}
+
+/// Like [`reqwest::Response::error_for_status`], but keeps what the typed
+/// errors need from the response: the rate limit's reset time and the
+/// server's validation message.
+pub async fn check(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status.as_u16() == 429 {
+        return Err(Error::RateLimited(rate_limit_reset(response.headers())));
+    }
+
+    #[derive(serde::Deserialize)]
+    struct ApiError {
+        error: String,
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<ApiError>(&body)
+        .map(|api_error| api_error.error)
+        .unwrap_or(body);
+    Err(Error::from_status(status.as_u16(), message))
+}
+
+/// When the server's rate limit resets, from a response's headers.
+pub fn rate_limit_reset(headers: &reqwest::header::HeaderMap) -> Option<DateTime<Utc>> {
+    headers
+        .get("X-RateLimit-Reset")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|reset| reset.with_timezone(&Utc))
+}
+
+/// Run an idempotent read, retrying network failures, rate limits and
+/// server errors with backoff. Anything else fails immediately.
+pub async fn retry<T, F, Fut>(mut request: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Err(err) if err.is_transient() && attempt < MAX_RETRIES => {
+                let Some(delay) = err.retry_delay(attempt) else {
+                    return Err(err);
+                };
+                tracing::debug!("retrying in {delay:?} after: {err}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
 }
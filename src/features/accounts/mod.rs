@@ -1,4 +1,7 @@
-//! Account profile view: bio/stats plus follow/mute/block relationship actions.
+//! Account profile view: bio/stats plus follow/mute/block relationship
+//! actions, including blocking a remote account's whole instance, featuring
+//! the account on your own profile, adding it to lists, and a private note
+//! about it.
 
 use capitalize::Capitalize;
 use cosmic::{
@@ -6,10 +9,12 @@ use cosmic::{
     iced::{self, alignment::Horizontal, ContentFit, Length},
     widget, Apply, Element,
 };
-use megalodon::entities::{Account, Relationship};
+use megalodon::entities::Account;
 
 use crate::app;
-use crate::cache::Cache;
+use crate::cache::{self, Cache};
+use crate::features::status::emoji;
+use crate::fl;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -17,12 +22,27 @@ pub enum Message {
     Follow(String, bool),
     Mute(String, bool),
     Block(String, bool),
+    /// Block or unblock an entire instance: (domain, currently blocked).
+    BlockDomain(String, bool),
+    /// Feature or unfeature the account on your profile: (id, currently endorsed).
+    Endorse(String, bool),
+    /// Open the "Add to list…" picker for an account.
+    AddToList(Account),
+    /// The private note being edited: (account id, draft text).
+    NoteInput(String, String),
+    /// Save the drafted private note for an account id.
+    SaveNote(String),
 }
 
-pub fn account<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Message> {
+/// `note_draft` is the in-progress edit of this account's private note, if
+/// the user has started typing one; otherwise the saved note is shown.
+pub fn account<'a>(
+    account: &'a Account,
+    cache: &'a Cache,
+    note_draft: Option<&'a str>,
+) -> Element<'a, Message> {
     let spacing = cosmic::theme::active().cosmic().spacing;
     let handles = &cache.handles;
-    let relationship = cache.relationships.get(&account.id);
     let is_me = cache.is_me(&account.id);
 
     let header = handles.get(&account.header).map(|handle| {
@@ -39,14 +59,12 @@ pub fn account<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Messag
         .center(Length::Fill)
     });
     let stack = iced::widget::stack!(header, avatar);
-    let display_name = widget::text(&account.display_name).size(18);
+    let display_name = emoji::text(&account.display_name, &account.emojis, cache, 18);
     let username = widget::button::link(format!("@{}", account.username))
         .on_press(Message::Open(account.url.clone()));
-    let bio = (!account.note.is_empty()).then_some(widget::text(
-        html2text::config::rich()
-            .string_from_read(account.note.as_bytes(), 700)
-            .unwrap(),
-    ));
+    let bio = (!account.note.is_empty()).then(|| {
+        emoji::text(&cache.profile_text(&account.note), &account.emojis, cache, 14)
+    });
     let joined = widget::text::caption(format!(
         "Joined on {}",
         account.created_at.format("%d %b %Y")
@@ -55,9 +73,7 @@ pub fn account<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Messag
         .fields
         .iter()
         .map(|field| {
-            let value = html2text::config::rich()
-                .string_from_read(field.value.as_bytes(), 700)
-                .unwrap();
+            let value = cache.profile_text(&field.value).into_owned();
             widget::column![
                 widget::text(field.name.capitalize()),
                 widget::text(value.clone()).class(cosmic::style::Text::Accent),
@@ -99,7 +115,31 @@ pub fn account<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Messag
     )
     .class(cosmic::style::Container::Card);
 
-    let relationship_actions = (!is_me).then(|| relationship_actions(account, relationship));
+    let relationship_actions = (!is_me).then(|| relationship_actions(account, cache));
+    let note = (!is_me)
+        .then(|| cache.relationships.get(&account.id))
+        .flatten()
+        .map(|relationship| {
+            let saved = relationship.note.as_deref().unwrap_or_default();
+            let value = note_draft.unwrap_or(saved);
+            let id = account.id.clone();
+            widget::column![
+                widget::text::caption("Personal note (only visible to you)"),
+                widget::row![
+                    widget::text_input("Add a note", value)
+                        .on_input(move |text| Message::NoteInput(id.clone(), text))
+                        .on_submit(|_| Message::SaveNote(account.id.clone())),
+                    widget::button::standard("Save").on_press_maybe(
+                        note_draft
+                            .is_some_and(|draft| draft != saved)
+                            .then(|| Message::SaveNote(account.id.clone()))
+                    ),
+                ]
+                .spacing(spacing.space_xs)
+                .align_y(cosmic::iced::Alignment::Center),
+            ]
+            .spacing(spacing.space_xxs)
+        });
 
     let settings = (!fields.is_empty()).then_some(widget::settings::section().extend(fields));
     let content = widget::column![
@@ -107,6 +147,7 @@ pub fn account<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Messag
         display_name,
         username,
         relationship_actions,
+        note,
         bio,
         joined,
         info,
@@ -121,37 +162,78 @@ pub fn account<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Messag
         .into()
 }
 
-fn relationship_actions<'a>(
-    account: &'a Account,
-    relationship: Option<&'a Relationship>,
-) -> Element<'a, Message> {
+fn relationship_actions<'a>(account: &'a Account, cache: &'a Cache) -> Element<'a, Message> {
     let spacing = cosmic::theme::active().cosmic().spacing;
+    let relationship = cache.relationships.get(&account.id);
 
     let following = relationship.is_some_and(|r| r.following);
     let muting = relationship.is_some_and(|r| r.muting);
     let blocking = relationship.is_some_and(|r| r.blocking);
+    let endorsed = relationship.is_some_and(|r| r.endorsed);
 
-    widget::row![
+    let mut actions: Vec<Element<'a, Message>> = vec![
         widget::button::text(if following { "Unfollow" } else { "Follow" })
             .class(if following {
                 cosmic::theme::Button::Standard
             } else {
                 cosmic::theme::Button::Suggested
             })
-            .on_press(Message::Follow(account.id.clone(), following)),
+            .on_press(Message::Follow(account.id.clone(), following))
+            .into(),
         widget::button::text(if muting { "Unmute" } else { "Mute" })
-            .on_press(Message::Mute(account.id.clone(), muting)),
+            .on_press(Message::Mute(account.id.clone(), muting))
+            .into(),
         widget::button::text(if blocking { "Unblock" } else { "Block" })
             .class(cosmic::theme::Button::Destructive)
-            .on_press(Message::Block(account.id.clone(), blocking)),
-    ]
-    .spacing(spacing.space_xs)
-    .into()
+            .on_press(Message::Block(account.id.clone(), blocking))
+            .into(),
+    ];
+    if let Some(domain) = cache::account_domain(account) {
+        let domain_blocked = cache.blocked_domains.contains(domain);
+        actions.push(
+            widget::button::text(if domain_blocked {
+                fl!("unblock-domain", domain = domain)
+            } else {
+                fl!("block-domain", domain = domain)
+            })
+            .class(cosmic::theme::Button::Destructive)
+            .on_press(Message::BlockDomain(domain.to_string(), domain_blocked))
+            .into(),
+        );
+    }
+    // Mastodon only lets you list accounts you follow, so until then the
+    // button is disabled and its tooltip says why.
+    if cache.software.supports_lists() {
+        let add_to_list = widget::button::text(fl!("add-to-list"))
+            .on_press_maybe(following.then(|| Message::AddToList(account.clone())));
+        actions.push(if following {
+            add_to_list.into()
+        } else {
+            widget::tooltip(
+                add_to_list,
+                widget::text(fl!("add-to-list-requires-follow")),
+                widget::tooltip::Position::Bottom,
+            )
+            .into()
+        });
+    }
+    // Likewise, only accounts you follow can be featured.
+    if (following || endorsed) && cache.software.supports_endorsements() {
+        let id = account.id.clone();
+        actions.push(
+            widget::toggler(endorsed)
+                .label(fl!("feature-on-profile"))
+                .on_toggle(move |_| Message::Endorse(id.clone(), endorsed))
+                .into(),
+        );
+    }
+
+    widget::flex_row(actions).spacing(spacing.space_xs).into()
 }
 
-/// Handles [`Message::Open`] directly; [`Message::Follow`]/[`Message::Mute`]/
-/// [`Message::Block`] carry no view-only behavior of their own — they're
-/// intercepted in [`app::AppModel::update`] to perform the actual API call.
+/// Handles [`Message::Open`] directly; the relationship and note messages
+/// carry no view-only behavior of their own — they're intercepted in
+/// [`app::AppModel::update`] to perform the actual API call.
 pub fn update(message: Message) -> Task<app::Message> {
     if let Message::Open(url) = message {
         if let Err(err) = open::that_detached(&url) {
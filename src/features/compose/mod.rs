@@ -49,7 +49,7 @@ const VISIBILITIES: [StatusVisibility; 4] = [
     StatusVisibility::Direct,
 ];
 
-fn visibility_label(visibility: &StatusVisibility) -> &'static str {
+pub fn visibility_label(visibility: &StatusVisibility) -> &'static str {
     match visibility {
         StatusVisibility::Public => "Public",
         StatusVisibility::Unlisted => "Unlisted",
@@ -0,0 +1,402 @@
+//! Deck layout: several feeds side by side as independently scrolling
+//! columns, for wide windows. Each column is its own [`Timeline`] or
+//! [`Notifications`] instance, separate from the single-page views.
+
+use cosmic::{
+    app::Task,
+    iced::widget::scrollable::{Direction, Scrollbar},
+    iced::{Alignment, Length, Subscription},
+    widget, Apply, Element,
+};
+
+use crate::{
+    app,
+    cache::Cache,
+    client::Client,
+    features::{
+        lists,
+        notifications::{self, Notifications},
+        timeline::{self, Timeline, TimelineKind},
+    },
+};
+
+const COLUMN_WIDTH: f32 = 420.0;
+
+/// What a deck column shows. Persisted (per account) as the deck layout.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ColumnKind {
+    Home,
+    Notifications,
+    Explore,
+    Local,
+    Federated,
+    Favorites,
+    Bookmarks,
+    /// A user list: (id, title).
+    List(String, String),
+    /// A hashtag, by name.
+    Tag(String),
+    /// A saved search query.
+    Search(String),
+}
+
+impl ColumnKind {
+    /// The columns that don't need a parameter, offered in the "Add column" picker.
+    const FIXED: [ColumnKind; 7] = [
+        ColumnKind::Home,
+        ColumnKind::Notifications,
+        ColumnKind::Explore,
+        ColumnKind::Local,
+        ColumnKind::Federated,
+        ColumnKind::Favorites,
+        ColumnKind::Bookmarks,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            ColumnKind::Home => "Home".to_string(),
+            ColumnKind::Notifications => "Notifications".to_string(),
+            ColumnKind::Explore => "Explore".to_string(),
+            ColumnKind::Local => "Local".to_string(),
+            ColumnKind::Federated => "Federated".to_string(),
+            ColumnKind::Favorites => "Favorites".to_string(),
+            ColumnKind::Bookmarks => "Bookmarks".to_string(),
+            ColumnKind::List(_, title) => title.clone(),
+            ColumnKind::Tag(name) => format!("#{name}"),
+            ColumnKind::Search(query) => format!("Search: {query}"),
+        }
+    }
+
+    /// The timeline backing this column, or `None` for notifications.
+    fn timeline_kind(&self) -> Option<TimelineKind> {
+        match self {
+            ColumnKind::Home => Some(TimelineKind::Home),
+            ColumnKind::Notifications => None,
+            ColumnKind::Explore => Some(TimelineKind::Public),
+            ColumnKind::Local => Some(TimelineKind::Local),
+            ColumnKind::Federated => Some(TimelineKind::Federated),
+            ColumnKind::Favorites => Some(TimelineKind::Favorites),
+            ColumnKind::Bookmarks => Some(TimelineKind::Bookmarks),
+            ColumnKind::List(id, _) => Some(TimelineKind::List(id.clone())),
+            ColumnKind::Tag(name) => Some(TimelineKind::Tag(name.clone())),
+            ColumnKind::Search(query) => Some(TimelineKind::Search(query.clone())),
+        }
+    }
+}
+
+enum Column {
+    Timeline(Timeline),
+    Notifications(Notifications),
+}
+
+pub struct Deck {
+    mastodon: Client,
+    columns: Vec<(ColumnKind, Column)>,
+    /// Text typed into the toolbar's hashtag/search input.
+    input: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Column(ColumnKind, ColumnMessage),
+    Add(ColumnKind),
+    Remove(ColumnKind),
+    MoveLeft(ColumnKind),
+    MoveRight(ColumnKind),
+    InputChanged(String),
+    /// Add a hashtag column for the toolbar input.
+    AddTag,
+    /// Add a saved-search column for the toolbar input.
+    AddSearch,
+}
+
+#[derive(Debug, Clone)]
+pub enum ColumnMessage {
+    Timeline(timeline::Message),
+    Notifications(notifications::Message),
+}
+
+impl Deck {
+    pub fn new(mastodon: Client) -> Self {
+        Self {
+            mastodon,
+            columns: Vec::new(),
+            input: String::new(),
+        }
+    }
+
+    /// The current column order, for persisting.
+    pub fn layout(&self) -> Vec<ColumnKind> {
+        self.columns.iter().map(|(kind, _)| kind.clone()).collect()
+    }
+
+    /// Whether this message changes which columns exist or their order, so
+    /// the caller knows to persist [`Deck::layout`] afterward.
+    pub fn changes_layout(message: &Message) -> bool {
+        matches!(
+            message,
+            Message::Add(_)
+                | Message::Remove(_)
+                | Message::MoveLeft(_)
+                | Message::MoveRight(_)
+                | Message::AddTag
+                | Message::AddSearch
+        )
+    }
+
+    /// Replace the deck's columns with `layout`, e.g. a saved layout at
+    /// startup or after switching accounts. Columns are rebuilt from their
+    /// disk snapshots.
+    pub fn set_layout(&mut self, mastodon: Client, layout: &[ColumnKind]) -> Task<app::Message> {
+        self.mastodon = mastodon;
+        self.columns.clear();
+        Task::batch(layout.iter().cloned().map(|kind| self.push(kind)))
+    }
+
+    fn push(&mut self, kind: ColumnKind) -> Task<app::Message> {
+        if self.columns.iter().any(|(existing, _)| *existing == kind) {
+            return Task::none();
+        }
+        let (column, task) = match kind.timeline_kind() {
+            Some(timeline_kind) => {
+                let mut timeline = Timeline::new(self.mastodon.clone(), timeline_kind);
+                let task = timeline.load_cached();
+                (Column::Timeline(timeline), task)
+            }
+            None => {
+                let mut notifications = Notifications::new(self.mastodon.clone());
+                let task = notifications.load_cached();
+                (Column::Notifications(notifications), task)
+            }
+        };
+        self.columns.push((kind, column));
+        task
+    }
+
+    /// Persist every timeline column's membership to disk. Notifications
+    /// are saved as they're cached, whichever column shows them.
+    pub fn save_cached(&self, cache: &Cache) {
+        for (_, column) in &self.columns {
+            if let Column::Timeline(timeline) = column {
+                timeline.save_cached(cache);
+            }
+        }
+    }
+
+    /// Ids of the statuses shown across all timeline columns.
+    pub fn status_ids(&self) -> impl Iterator<Item = &String> {
+        self.columns
+            .iter()
+            .filter_map(|(_, column)| match column {
+                Column::Timeline(timeline) => Some(timeline),
+                Column::Notifications(_) => None,
+            })
+            .flat_map(Timeline::status_ids)
+    }
+
+    /// Ids of the notifications shown across all notification columns.
+    pub fn notification_ids(&self) -> impl Iterator<Item = &String> {
+        self.columns
+            .iter()
+            .filter_map(|(_, column)| match column {
+                Column::Notifications(notifications) => Some(notifications),
+                Column::Timeline(_) => None,
+            })
+            .flat_map(Notifications::notification_ids)
+    }
+
+    /// Every image shown across all columns.
+    pub fn image_urls(&self, cache: &Cache) -> Vec<String> {
+        self.columns
+            .iter()
+            .flat_map(|(_, column)| match column {
+                Column::Timeline(timeline) => timeline.image_urls(cache),
+                Column::Notifications(notifications) => notifications.image_urls(cache),
+            })
+            .collect()
+    }
+
+    pub fn view<'a>(&'a self, cache: &'a Cache, lists: &'a [lists::List]) -> Element<'a, Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+
+        let mut options: Vec<ColumnKind> = ColumnKind::FIXED.to_vec();
+        options.extend(
+            lists
+                .iter()
+                .map(|list| ColumnKind::List(list.id.clone(), list.title.clone())),
+        );
+        options.retain(|kind| !self.columns.iter().any(|(existing, _)| existing == kind));
+        let labels: Vec<String> = options.iter().map(ColumnKind::label).collect();
+
+        let has_input = !self.input.trim().is_empty();
+        let toolbar = widget::row![
+            widget::dropdown(labels, None, move |index| Message::Add(options[index].clone())),
+            widget::text_input("Hashtag or search query", &self.input)
+                .on_input(Message::InputChanged)
+                .on_submit(|_| Message::AddSearch)
+                .width(Length::Fixed(240.0)),
+            widget::button::standard("Add hashtag")
+                .on_press_maybe(has_input.then_some(Message::AddTag)),
+            widget::button::standard("Add search")
+                .on_press_maybe(has_input.then_some(Message::AddSearch)),
+        ]
+        .spacing(spacing.space_xs)
+        .align_y(Alignment::Center)
+        .padding(spacing.space_xs);
+
+        if self.columns.is_empty() {
+            return widget::column![
+                toolbar,
+                widget::container(widget::text("Add a column to get started"))
+                    .center(Length::Fill),
+            ]
+            .into();
+        }
+
+        let last = self.columns.len() - 1;
+        let columns: Vec<Element<'a, Message>> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, (kind, column))| {
+                let header = widget::row![
+                    widget::text::heading(kind.label()),
+                    widget::space::horizontal(),
+                    widget::button::icon(widget::icon::from_name("go-previous-symbolic"))
+                        .on_press_maybe((index > 0).then(|| Message::MoveLeft(kind.clone()))),
+                    widget::button::icon(widget::icon::from_name("go-next-symbolic"))
+                        .on_press_maybe((index < last).then(|| Message::MoveRight(kind.clone()))),
+                    widget::button::icon(widget::icon::from_name("window-close-symbolic"))
+                        .on_press(Message::Remove(kind.clone())),
+                ]
+                .spacing(spacing.space_xxs)
+                .align_y(Alignment::Center)
+                .padding(spacing.space_xxs);
+
+                let kind = kind.clone();
+                let content: Element<'a, Message> = match column {
+                    Column::Timeline(timeline) => timeline.view(cache).map(move |message| {
+                        Message::Column(kind.clone(), ColumnMessage::Timeline(message))
+                    }),
+                    Column::Notifications(notifications) => {
+                        notifications.view(cache).map(move |message| {
+                            Message::Column(kind.clone(), ColumnMessage::Notifications(message))
+                        })
+                    }
+                };
+
+                // Feeds cap their width for the single-page view but fill
+                // the column here, so the columns tile edge to edge.
+                widget::column![header, content]
+                    .width(Length::Fixed(COLUMN_WIDTH))
+                    .height(Length::Fill)
+                    .into()
+            })
+            .collect();
+
+        widget::column![
+            toolbar,
+            widget::scrollable(
+                widget::row(columns)
+                    .spacing(spacing.space_s)
+                    .height(Length::Fill)
+            )
+            .direction(Direction::Horizontal(Scrollbar::default()))
+            .height(Length::Fill),
+        ]
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .apply(widget::container)
+        .into()
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<app::Message> {
+        match message {
+            Message::Column(kind, message) => {
+                let Some((_, column)) = self.columns.iter_mut().find(|(k, _)| *k == kind) else {
+                    return Task::none();
+                };
+                match (column, message) {
+                    (Column::Timeline(timeline), ColumnMessage::Timeline(message)) => {
+                        return timeline.update(message);
+                    }
+                    (Column::Notifications(notifications), ColumnMessage::Notifications(message)) => {
+                        return notifications.update(message);
+                    }
+                    _ => (),
+                }
+            }
+            Message::Add(kind) => return self.push(kind),
+            Message::Remove(kind) => self.columns.retain(|(k, _)| *k != kind),
+            Message::MoveLeft(kind) => {
+                if let Some(index) = self.columns.iter().position(|(k, _)| *k == kind) {
+                    if index > 0 {
+                        self.columns.swap(index, index - 1);
+                    }
+                }
+            }
+            Message::MoveRight(kind) => {
+                if let Some(index) = self.columns.iter().position(|(k, _)| *k == kind) {
+                    if index + 1 < self.columns.len() {
+                        self.columns.swap(index, index + 1);
+                    }
+                }
+            }
+            Message::InputChanged(input) => self.input = input,
+            Message::AddTag => {
+                let name = self.input.trim().trim_start_matches('#').to_string();
+                if !name.is_empty() {
+                    self.input.clear();
+                    return self.push(ColumnKind::Tag(name));
+                }
+            }
+            Message::AddSearch => {
+                let query = self.input.trim().to_string();
+                if !query.is_empty() {
+                    self.input.clear();
+                    return self.push(ColumnKind::Search(query));
+                }
+            }
+        }
+        Task::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![];
+        for (kind, column) in &self.columns {
+            match column {
+                Column::Timeline(timeline) => {
+                    subscriptions.push(
+                        timeline
+                            .subscription()
+                            .with(kind.clone())
+                            .map(|(kind, message)| {
+                                Message::Column(kind, ColumnMessage::Timeline(message))
+                            }),
+                    );
+                    if self.mastodon.is_authenticated() {
+                        subscriptions.push(
+                            crate::streaming::stream_timeline(
+                                self.mastodon.clone(),
+                                timeline.kind().clone(),
+                            )
+                            .with(kind.clone())
+                            .map(|(kind, message)| {
+                                Message::Column(kind, ColumnMessage::Timeline(message))
+                            }),
+                        );
+                    }
+                }
+                Column::Notifications(notifications) => subscriptions.push(
+                    notifications
+                        .subscription()
+                        .with(kind.clone())
+                        .map(|(kind, message)| {
+                            Message::Column(kind, ColumnMessage::Notifications(message))
+                        }),
+                ),
+            }
+        }
+        Subscription::batch(subscriptions)
+    }
+}
@@ -14,6 +14,7 @@ use crate::{
     app,
     cache::Cache,
     client::Client,
+    config::Pin,
     features::timeline::{self, Timeline, TimelineKind},
 };
 
@@ -43,6 +44,8 @@ pub enum Message {
     /// Toggle following a tag: (name, currently following).
     ToggleFollow(String, bool),
     FollowResult(String, bool),
+    /// Pin or unpin a tag's timeline as its own sidebar page.
+    TogglePin(String),
     Timeline(timeline::Message),
 }
 
@@ -62,14 +65,39 @@ impl Hashtags {
         self.mastodon.is_authenticated()
     }
 
+    /// Ids of the statuses in the selected hashtag's timeline.
+    pub fn status_ids(&self) -> impl Iterator<Item = &String> {
+        self.selected.iter().flat_map(Timeline::status_ids)
+    }
+
+    /// Every image the selected hashtag's timeline shows.
+    pub fn image_urls(&self, cache: &Cache) -> Vec<String> {
+        self.selected
+            .iter()
+            .flat_map(|timeline| timeline.image_urls(cache))
+            .collect()
+    }
+
     pub fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, Message> {
         let spacing = cosmic::theme::active().cosmic().spacing;
 
         // Once a tag is selected, collapse the list and show only its feed
         // (with a way back), rather than showing both at once.
         if let Some(timeline) = &self.selected {
+            let pinned = cache.pinned.contains(timeline.kind());
+            let pin = match timeline.kind() {
+                TimelineKind::Tag(name) => Some(
+                    widget::button::standard(if pinned { "Unpin" } else { "Pin to sidebar" })
+                        .on_press(Message::TogglePin(name.clone())),
+                ),
+                _ => None,
+            };
             return widget::column![
-                widget::button::standard("← Back to hashtags").on_press(Message::Deselect),
+                widget::row![
+                    widget::button::standard("← Back to hashtags").on_press(Message::Deselect),
+                    widget::space::horizontal(),
+                ]
+                .push_maybe(pin),
                 timeline.view(cache).map(Message::Timeline),
             ]
             .spacing(spacing.space_xs)
@@ -157,7 +185,7 @@ impl Hashtags {
                             name,
                             response.json.following.unwrap_or(!following),
                         )),
-                        Err(err) => app::Message::Error(format!("Couldn't update hashtag follow: {err}")),
+                        Err(err) => app::action_failure("Couldn't update hashtag follow", err),
                     }
                 });
             }
@@ -168,6 +196,9 @@ impl Hashtags {
                     self.unfollowed.insert(name);
                 }
             }
+            Message::TogglePin(name) => {
+                return cosmic::task::message(app::Message::TogglePin(Pin::Tag(name)));
+            }
             Message::Timeline(message) => {
                 if let Some(timeline) = &mut self.selected {
                     return timeline.update(message);
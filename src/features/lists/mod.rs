@@ -1,24 +1,88 @@
-//! User lists: browse the account's lists and view a selected list's timeline.
+//! User lists: browse the account's lists and view a selected list's
+//! timeline, plus creating, renaming, deleting, and curating their members.
 
 use cosmic::{
     app::Task,
     iced::{Alignment, Length, Subscription},
     widget, Apply, Element,
 };
-use megalodon::entities::List;
+use megalodon::{
+    entities::Account,
+    megalodon::{GetAccountsInListInputOptions, SearchAccountInputOptions},
+};
 
 use crate::{
     app,
     cache::Cache,
     client::Client,
+    config::Pin,
+    error::{self, Error},
     features::timeline::{self, Timeline, TimelineKind},
+    fl,
 };
 
+/// A user list. megalodon's `List` entity lacks the replies policy and
+/// exclusive flag, so lists are fetched and updated through the REST API
+/// directly and deserialized into this instead.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct List {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub replies_policy: RepliesPolicy,
+    /// Whether posts from members are hidden from the home timeline.
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+/// Which replies a list's timeline includes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepliesPolicy {
+    /// Replies to any followed user.
+    Followed,
+    /// Replies to members of the list.
+    #[default]
+    List,
+    /// No replies.
+    None,
+}
+
+impl RepliesPolicy {
+    pub const ALL: [RepliesPolicy; 3] = [
+        RepliesPolicy::Followed,
+        RepliesPolicy::List,
+        RepliesPolicy::None,
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            RepliesPolicy::Followed => fl!("replies-followed"),
+            RepliesPolicy::List => fl!("replies-list"),
+            RepliesPolicy::None => fl!("replies-none"),
+        }
+    }
+}
+
+/// The list currently open for editing, with its unsaved changes.
+struct Editor {
+    id: String,
+    title: String,
+    replies_policy: RepliesPolicy,
+    exclusive: bool,
+    members: Vec<Account>,
+    members_loaded: bool,
+    query: String,
+    results: Vec<Account>,
+}
+
 pub struct Lists {
     mastodon: Client,
     lists: Vec<List>,
     loaded: bool,
     selected: Option<Timeline>,
+    new_title: String,
+    editor: Option<Editor>,
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +90,37 @@ pub enum Message {
     SetClient(Client),
     SetLists(Vec<List>),
     Select(String),
+    /// Deselect the current list, returning to the lists overview.
+    Deselect,
+    NewTitleInput(String),
+    Create,
+    Created(List),
+    /// Open a list's editor by id.
+    Edit(String),
+    CloseEditor,
+    TitleInput(String),
+    SetRepliesPolicy(RepliesPolicy),
+    SetExclusive(bool),
+    Save,
+    Saved(List),
+    /// Ask to delete a list: (id, title). Confirmed through an app dialog.
+    Delete(String, String),
+    /// A list was deleted on the server.
+    Deleted(String),
+    /// A list's members were fetched: (list id, members).
+    SetMembers(String, Vec<Account>),
+    MemberQueryInput(String),
+    SearchMembers,
+    SetSearchResults(Vec<Account>),
+    AddMember(Account),
+    /// Remove a member from the list being edited, by account id.
+    RemoveMember(String),
+    /// A member was added to a list on the server: (list id, account).
+    MemberAdded(String, Account),
+    /// A member was removed from a list on the server: (list id, account id).
+    MemberRemoved(String, String),
+    /// Pin or unpin a list's timeline as its own sidebar page, by list id.
+    TogglePin(String),
     Timeline(timeline::Message),
 }
 
@@ -36,6 +131,8 @@ impl Lists {
             lists: Vec::new(),
             loaded: false,
             selected: None,
+            new_title: String::new(),
+            editor: None,
         }
     }
 
@@ -43,44 +140,236 @@ impl Lists {
         self.mastodon.is_authenticated()
     }
 
+    /// Ids of the statuses in the selected list's timeline.
+    pub fn status_ids(&self) -> impl Iterator<Item = &String> {
+        self.selected.iter().flat_map(Timeline::status_ids)
+    }
+
+    /// Every image the selected list's timeline shows, and the avatars in
+    /// the open editor.
+    pub fn image_urls(&self, cache: &Cache) -> Vec<String> {
+        let avatars = self
+            .editor
+            .iter()
+            .flat_map(|editor| editor.members.iter().chain(&editor.results))
+            .map(|account| account.avatar.clone());
+        self.selected
+            .iter()
+            .flat_map(|timeline| timeline.image_urls(cache))
+            .chain(avatars)
+            .collect()
+    }
+
+    /// The user's lists, for pickers outside this page (e.g. a profile's
+    /// "Add to list…" dialog).
+    pub fn lists(&self) -> &[List] {
+        &self.lists
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    /// Fetch the user's lists once, outside of this page's subscription —
+    /// for when another page needs them before the Lists page is visited.
+    pub fn load(&self) -> Task<app::Message> {
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            match get_lists(&mastodon).await {
+                Ok(lists) => app::Message::Lists(Message::SetLists(lists)),
+                Err(err) => app::failure("Couldn't load lists", err),
+            }
+        })
+    }
+
     pub fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, Message> {
         let spacing = cosmic::theme::active().cosmic().spacing;
-        let lists: Vec<Element<_>> = self
-            .lists
+
+        let content: Element<'a, Message> = if let Some(editor) = &self.editor {
+            self.editor_view(editor, cache)
+        } else if let Some(timeline) = &self.selected {
+            // Once a list is selected, collapse the overview and show only
+            // its feed (with a way back), like the hashtags page.
+            let pin = match timeline.kind() {
+                TimelineKind::List(id) => {
+                    let pinned = cache.pinned.contains(timeline.kind());
+                    Some(
+                        widget::button::standard(if pinned {
+                            fl!("unpin")
+                        } else {
+                            fl!("pin-to-sidebar")
+                        })
+                        .on_press(Message::TogglePin(id.clone())),
+                    )
+                }
+                _ => None,
+            };
+            widget::column![
+                widget::row![
+                    widget::button::standard(fl!("back-to-lists")).on_press(Message::Deselect),
+                    widget::space::horizontal(),
+                ]
+                .push_maybe(pin),
+                timeline.view(cache).map(Message::Timeline),
+            ]
+            .spacing(spacing.space_xs)
+            .height(Length::Fill)
+            .into()
+        } else {
+            let create = widget::row![
+                widget::text_input(fl!("new-list-title"), &self.new_title)
+                    .on_input(Message::NewTitleInput)
+                    .on_submit(|_| Message::Create),
+                widget::button::suggested(fl!("create")).on_press_maybe(
+                    (!self.new_title.trim().is_empty()).then_some(Message::Create)
+                ),
+            ]
+            .spacing(spacing.space_xs)
+            .align_y(Alignment::Center);
+
+            let section =
+                self.lists
+                    .iter()
+                    .fold(widget::settings::section().title(fl!("lists")), |section, list| {
+                        section.add(widget::settings::item_row(vec![
+                            widget::button::link(list.title.clone())
+                                .on_press(Message::Select(list.id.clone()))
+                                .into(),
+                            widget::space::horizontal().into(),
+                            widget::button::standard(fl!("edit"))
+                                .on_press(Message::Edit(list.id.clone()))
+                                .into(),
+                        ]))
+                    });
+
+            widget::column![
+                create,
+                widget::scrollable(section)
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+            ]
+            .spacing(spacing.space_xs)
+            .into()
+        };
+
+        content
+            .apply(widget::container)
+            .max_width(700)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Alignment::Start)
+            .into()
+    }
+
+    fn editor_view<'a>(&'a self, editor: &'a Editor, cache: &'a Cache) -> Element<'a, Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+
+        let policy_labels: Vec<String> = RepliesPolicy::ALL
             .iter()
-            .map(|list| {
-                widget::button::suggested(list.title.clone())
-                    .on_press(Message::Select(list.id.clone()))
-                    .into()
-            })
+            .map(|policy| policy.label())
             .collect();
+        let policy_selected = RepliesPolicy::ALL
+            .iter()
+            .position(|policy| *policy == editor.replies_policy);
+        let title = self
+            .lists
+            .iter()
+            .find(|list| list.id == editor.id)
+            .map(|list| list.title.clone())
+            .unwrap_or_default();
 
-        let content = self
-            .selected
-            .as_ref()
-            .map(|timeline| timeline.view(cache).map(Message::Timeline));
+        let settings = widget::settings::section()
+            .title(fl!("list-settings"))
+            .add(widget::settings::item(
+                fl!("list-title"),
+                widget::text_input(fl!("list-title"), &editor.title)
+                    .on_input(Message::TitleInput),
+            ))
+            .add(widget::settings::item(
+                fl!("show-replies"),
+                widget::dropdown(policy_labels, policy_selected, |index| {
+                    Message::SetRepliesPolicy(RepliesPolicy::ALL[index])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("exclusive-list"),
+                widget::toggler(editor.exclusive).on_toggle(Message::SetExclusive),
+            ));
+
+        let actions = widget::row![
+            widget::button::standard(fl!("back-to-lists")).on_press(Message::CloseEditor),
+            widget::space::horizontal(),
+            widget::button::destructive(fl!("delete"))
+                .on_press(Message::Delete(editor.id.clone(), title)),
+            widget::button::suggested(fl!("save")).on_press_maybe(
+                (!editor.title.trim().is_empty()).then_some(Message::Save)
+            ),
+        ]
+        .spacing(spacing.space_xs);
+
+        let members = if !editor.members_loaded {
+            widget::settings::section()
+                .title(fl!("list-members"))
+                .add(widget::indeterminate_circular().size(24.0))
+        } else {
+            editor.members.iter().fold(
+                widget::settings::section().title(fl!("list-members")),
+                |section, account| {
+                    section.add(account_row(
+                        account,
+                        cache,
+                        widget::button::standard(fl!("remove"))
+                            .on_press(Message::RemoveMember(account.id.clone())),
+                    ))
+                },
+            )
+        };
+
+        let search = widget::text_input(fl!("search-followed-accounts"), &editor.query)
+            .on_input(Message::MemberQueryInput)
+            .on_submit(|_| Message::SearchMembers);
+        let results = editor
+            .results
+            .iter()
+            .filter(|account| !editor.members.iter().any(|member| member.id == account.id))
+            .fold(
+                widget::settings::section().title(fl!("add-members")),
+                |section, account| {
+                    section.add(account_row(
+                        account,
+                        cache,
+                        widget::button::suggested(fl!("add"))
+                            .on_press(Message::AddMember(account.clone())),
+                    ))
+                },
+            );
 
         widget::column![
-            widget::scrollable(widget::row(lists).spacing(spacing.space_xs))
-                .direction(cosmic::iced::widget::scrollable::Direction::Horizontal(
-                    Default::default()
-                )),
-            content,
+            actions,
+            widget::scrollable(
+                widget::column![settings, members, search, results].spacing(spacing.space_s)
+            )
+            .width(Length::Fill)
+            .height(Length::Fill),
         ]
         .spacing(spacing.space_xs)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .apply(widget::container)
-        .max_width(700)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .align_x(Alignment::Start)
         .into()
     }
 
     pub fn update(&mut self, message: Message) -> Task<app::Message> {
         match message {
             Message::SetClient(mastodon) => {
+                // Another account's lists, and anything open from them,
+                // don't belong to this one.
+                if mastodon.base_url != self.mastodon.base_url
+                    || mastodon.token != self.mastodon.token
+                {
+                    self.lists.clear();
+                    self.loaded = false;
+                    self.selected = None;
+                    self.editor = None;
+                    self.new_title.clear();
+                }
                 self.mastodon = mastodon.clone();
                 if let Some(timeline) = &mut self.selected {
                     return timeline.update(timeline::Message::SetClient(mastodon));
@@ -96,6 +385,183 @@ impl Lists {
                 self.selected = Some(timeline);
                 return task;
             }
+            Message::Deselect => self.selected = None,
+            Message::NewTitleInput(title) => self.new_title = title,
+            Message::Create => {
+                let title = self.new_title.trim().to_string();
+                if title.is_empty() {
+                    return Task::none();
+                }
+                self.new_title.clear();
+                let mastodon = self.mastodon.clone();
+                return cosmic::task::future(async move {
+                    let body = serde_json::json!({ "title": title });
+                    match send_list(&mastodon, reqwest::Method::POST, "/api/v1/lists", body).await
+                    {
+                        Ok(list) => app::Message::Lists(Message::Created(list)),
+                        Err(err) => app::action_failure("Couldn't create list", err),
+                    }
+                });
+            }
+            Message::Created(list) => self.lists.push(list),
+            Message::Edit(id) => {
+                let Some(list) = self.lists.iter().find(|list| list.id == id) else {
+                    return Task::none();
+                };
+                self.editor = Some(Editor {
+                    id: list.id.clone(),
+                    title: list.title.clone(),
+                    replies_policy: list.replies_policy,
+                    exclusive: list.exclusive,
+                    members: Vec::new(),
+                    members_loaded: false,
+                    query: String::new(),
+                    results: Vec::new(),
+                });
+                let mastodon = self.mastodon.clone();
+                return cosmic::task::future(async move {
+                    // `limit: 0` asks Mastodon for every member, unpaginated.
+                    let options = GetAccountsInListInputOptions {
+                        limit: Some(0),
+                        ..Default::default()
+                    };
+                    match mastodon.get_accounts_in_list(id.clone(), Some(&options)).await {
+                        Ok(response) => app::Message::Lists(Message::SetMembers(id, response.json)),
+                        Err(err) => app::failure("Couldn't load list members", err),
+                    }
+                });
+            }
+            Message::CloseEditor => self.editor = None,
+            Message::TitleInput(title) => {
+                if let Some(editor) = &mut self.editor {
+                    editor.title = title;
+                }
+            }
+            Message::SetRepliesPolicy(policy) => {
+                if let Some(editor) = &mut self.editor {
+                    editor.replies_policy = policy;
+                }
+            }
+            Message::SetExclusive(exclusive) => {
+                if let Some(editor) = &mut self.editor {
+                    editor.exclusive = exclusive;
+                }
+            }
+            Message::Save => {
+                let Some(editor) = &self.editor else {
+                    return Task::none();
+                };
+                let mastodon = self.mastodon.clone();
+                let path = format!("/api/v1/lists/{}", editor.id);
+                let body = serde_json::json!({
+                    "title": editor.title.trim(),
+                    "replies_policy": editor.replies_policy,
+                    "exclusive": editor.exclusive,
+                });
+                return cosmic::task::future(async move {
+                    match send_list(&mastodon, reqwest::Method::PUT, &path, body).await {
+                        Ok(list) => app::Message::Lists(Message::Saved(list)),
+                        Err(err) => app::action_failure("Couldn't save list", err),
+                    }
+                });
+            }
+            Message::Saved(list) => {
+                if let Some(existing) = self.lists.iter_mut().find(|l| l.id == list.id) {
+                    *existing = list;
+                }
+                self.editor = None;
+            }
+            Message::Delete(id, title) => {
+                return cosmic::task::message(app::Message::Dialog(app::DialogAction::Open(
+                    app::Dialog::DeleteList(id, title),
+                )));
+            }
+            Message::Deleted(id) => {
+                self.lists.retain(|list| list.id != id);
+                if self.editor.as_ref().is_some_and(|editor| editor.id == id) {
+                    self.editor = None;
+                }
+                if self
+                    .selected
+                    .as_ref()
+                    .is_some_and(|timeline| *timeline.kind() == TimelineKind::List(id.clone()))
+                {
+                    self.selected = None;
+                }
+            }
+            Message::SetMembers(id, members) => {
+                if let Some(editor) = self.editor.as_mut().filter(|editor| editor.id == id) {
+                    let avatars = members.iter().map(|a| a.avatar.clone()).collect();
+                    editor.members = members;
+                    editor.members_loaded = true;
+                    return cosmic::task::message(app::Message::Fetch(avatars));
+                }
+            }
+            Message::MemberQueryInput(query) => {
+                if let Some(editor) = &mut self.editor {
+                    editor.query = query;
+                }
+            }
+            Message::SearchMembers => {
+                let Some(editor) = &self.editor else {
+                    return Task::none();
+                };
+                let query = editor.query.trim().to_string();
+                if query.is_empty() {
+                    return Task::none();
+                }
+                let mastodon = self.mastodon.clone();
+                return cosmic::task::future(async move {
+                    // Mastodon only allows adding accounts you follow to a list.
+                    let options = SearchAccountInputOptions {
+                        following: Some(true),
+                        ..Default::default()
+                    };
+                    match mastodon.search_account(query, Some(&options)).await {
+                        Ok(response) => {
+                            app::Message::Lists(Message::SetSearchResults(response.json))
+                        }
+                        Err(err) => app::failure("Search failed", err),
+                    }
+                });
+            }
+            Message::SetSearchResults(results) => {
+                if let Some(editor) = &mut self.editor {
+                    let avatars = results.iter().map(|a| a.avatar.clone()).collect();
+                    editor.results = results;
+                    return cosmic::task::message(app::Message::Fetch(avatars));
+                }
+            }
+            Message::AddMember(account) => {
+                if let Some(editor) = &self.editor {
+                    return add_member(self.mastodon.clone(), editor.id.clone(), account);
+                }
+            }
+            Message::RemoveMember(account_id) => {
+                if let Some(editor) = &self.editor {
+                    return remove_member(self.mastodon.clone(), editor.id.clone(), account_id);
+                }
+            }
+            Message::MemberAdded(list_id, account) => {
+                if let Some(editor) = self.editor.as_mut().filter(|e| e.id == list_id) {
+                    if !editor.members.iter().any(|member| member.id == account.id) {
+                        editor.members.push(account);
+                    }
+                }
+            }
+            Message::MemberRemoved(list_id, account_id) => {
+                if let Some(editor) = self.editor.as_mut().filter(|e| e.id == list_id) {
+                    editor.members.retain(|member| member.id != account_id);
+                }
+            }
+            Message::TogglePin(id) => {
+                if let Some(list) = self.lists.iter().find(|list| list.id == id) {
+                    return cosmic::task::message(app::Message::TogglePin(Pin::List(
+                        list.id.clone(),
+                        list.title.clone(),
+                    )));
+                }
+            }
             Message::Timeline(message) => {
                 if let Some(timeline) = &mut self.selected {
                     return timeline.update(message);
@@ -117,6 +583,79 @@ impl Lists {
     }
 }
 
+fn account_row<'a>(
+    account: &'a Account,
+    cache: &'a Cache,
+    action: widget::Button<'a, Message>,
+) -> Element<'a, Message> {
+    let spacing = cosmic::theme::active().cosmic().spacing;
+    widget::settings::item_row(vec![
+        widget::row![
+            cache
+                .handles
+                .get(&account.avatar)
+                .map(|handle| widget::image(handle).width(32).height(32))
+                .unwrap_or(crate::cache::fallback_avatar().width(32).height(32)),
+            widget::column![
+                widget::text(account.display_name.clone()),
+                widget::text::caption(format!("@{}", account.acct)),
+            ],
+        ]
+        .spacing(spacing.space_xs)
+        .align_y(Alignment::Center)
+        .into(),
+        widget::space::horizontal().into(),
+        action.into(),
+    ])
+    .into()
+}
+
+/// Add an account to a list, reporting back as [`Message::MemberAdded`].
+pub fn add_member(mastodon: Client, list_id: String, account: Account) -> Task<app::Message> {
+    cosmic::task::future(async move {
+        match mastodon
+            .add_accounts_to_list(list_id.clone(), vec![account.id.clone()])
+            .await
+        {
+            Ok(_) => app::Message::Lists(Message::MemberAdded(list_id, account)),
+            Err(err) => app::action_failure("Couldn't add to list", err),
+        }
+    })
+}
+
+/// Remove an account from a list, reporting back as [`Message::MemberRemoved`].
+pub fn remove_member(mastodon: Client, list_id: String, account_id: String) -> Task<app::Message> {
+    cosmic::task::future(async move {
+        match mastodon
+            .delete_accounts_from_list(list_id.clone(), vec![account_id.clone()])
+            .await
+        {
+            Ok(_) => app::Message::Lists(Message::MemberRemoved(list_id, account_id)),
+            Err(err) => app::action_failure("Couldn't remove from list", err),
+        }
+    })
+}
+
+async fn get_lists(mastodon: &Client) -> Result<Vec<List>, Error> {
+    let response = mastodon
+        .request(reqwest::Method::GET, "/api/v1/lists")
+        .send()
+        .await?;
+    let lists = error::check(response).await?.json().await?;
+    Ok(lists)
+}
+
+async fn send_list(
+    mastodon: &Client,
+    method: reqwest::Method,
+    path: &str,
+    body: serde_json::Value,
+) -> Result<List, Error> {
+    let response = mastodon.request(method, path).json(&body).send().await?;
+    let list = error::check(response).await?.json().await?;
+    Ok(list)
+}
+
 fn fetch_lists(mastodon: Client) -> Subscription<Message> {
     Subscription::run_with(mastodon, |mastodon| {
         let mastodon = mastodon.clone();
@@ -124,9 +663,9 @@ fn fetch_lists(mastodon: Client) -> Subscription<Message> {
             1,
             move |mut output: futures_channel::mpsc::Sender<Message>| async move {
                 use futures_util::SinkExt;
-                match mastodon.get_lists().await {
-                    Ok(response) => {
-                        if let Err(err) = output.send(Message::SetLists(response.json)).await {
+                match error::retry(|| get_lists(&mastodon)).await {
+                    Ok(lists) => {
+                        if let Err(err) = output.send(Message::SetLists(lists)).await {
                             tracing::warn!("failed to send lists: {}", err);
                         }
                     }
@@ -0,0 +1,379 @@
+//! Full-window viewer for a post's media attachments: original images with
+//! zoom and pan, in-app playback of video, gifv and audio (with the
+//! `playback` feature; otherwise they open in the browser), each
+//! attachment's alt text, and saving to disk. Opens on the attachment that
+//! was clicked, with previous/next to step through the rest.
+
+#[cfg(feature = "playback")]
+pub mod player;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use cosmic::{
+    app::Task,
+    iced::{alignment::Vertical, widget::image::viewer, Alignment, ContentFit, Length},
+    widget::{self, image::Handle},
+    Apply, Element,
+};
+use megalodon::entities::{attachment::AttachmentType, Attachment};
+
+use crate::app;
+use crate::cache::Cache;
+use crate::error::Error;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Previous,
+    Next,
+    /// Play or pause the current video or audio.
+    TogglePlaying,
+    /// A decoded video frame, and how far into the video it is.
+    #[cfg(feature = "playback")]
+    Frame(Handle, Duration),
+    /// How far audio-only playback has got.
+    #[cfg(feature = "playback")]
+    Position(Duration),
+    /// Playback reached the end.
+    #[cfg(feature = "playback")]
+    Ended,
+    #[cfg(feature = "playback")]
+    PlaybackFailed(String),
+    /// Download the current attachment to the downloads folder.
+    Save,
+    OpenInBrowser,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct Viewer {
+    attachments: Vec<Attachment>,
+    index: usize,
+    /// Whether the current video or audio is playing.
+    playing: bool,
+    /// Where playback last (re)started, and how far it has got since.
+    #[cfg(feature = "playback")]
+    start: Duration,
+    position: Duration,
+    /// The latest decoded video frame, shown in place of the preview once
+    /// playback starts.
+    frame: Option<Handle>,
+}
+
+impl Viewer {
+    pub fn new(attachments: Vec<Attachment>, index: usize) -> Self {
+        let mut viewer = Self {
+            attachments,
+            index,
+            playing: false,
+            #[cfg(feature = "playback")]
+            start: Duration::ZERO,
+            position: Duration::ZERO,
+            frame: None,
+        };
+        viewer.select(index);
+        viewer
+    }
+
+    fn current(&self) -> Option<&Attachment> {
+        self.attachments.get(self.index)
+    }
+
+    /// Switch to another attachment. Gifvs play straight away, like GIFs
+    /// would; video and audio wait to be played.
+    fn select(&mut self, index: usize) {
+        self.index = index;
+        #[cfg(feature = "playback")]
+        {
+            self.start = Duration::ZERO;
+        }
+        self.position = Duration::ZERO;
+        self.frame = None;
+        self.playing = cfg!(feature = "playback")
+            && self
+                .current()
+                .is_some_and(|media| matches!(media.r#type, AttachmentType::Gifv));
+    }
+
+    /// Fetch the current attachment's original image, if it's an image.
+    pub fn load(&self) -> Task<app::Message> {
+        match self.current() {
+            Some(media) if matches!(media.r#type, AttachmentType::Image) => {
+                cosmic::task::message(app::Message::Fetch(vec![media.url.clone()]))
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<app::Message> {
+        match message {
+            Message::Previous if self.index > 0 => {
+                self.select(self.index - 1);
+                return self.load();
+            }
+            Message::Next if self.index + 1 < self.attachments.len() => {
+                self.select(self.index + 1);
+                return self.load();
+            }
+            Message::Previous | Message::Next => {}
+            #[cfg(feature = "playback")]
+            Message::TogglePlaying => {
+                self.playing = !self.playing;
+                // Resume where it was paused; the player seeks there.
+                self.start = self.position;
+            }
+            #[cfg(not(feature = "playback"))]
+            Message::TogglePlaying => {
+                if let Some(media) = self.current() {
+                    return cosmic::task::message(app::Message::Open(media.url.clone()));
+                }
+            }
+            #[cfg(feature = "playback")]
+            Message::Frame(frame, position) => {
+                self.frame = Some(frame);
+                self.position = position;
+            }
+            #[cfg(feature = "playback")]
+            Message::Position(position) => self.position = position,
+            #[cfg(feature = "playback")]
+            Message::Ended => {
+                self.playing = false;
+                self.start = Duration::ZERO;
+                self.position = Duration::ZERO;
+            }
+            #[cfg(feature = "playback")]
+            Message::PlaybackFailed(err) => {
+                self.playing = false;
+                return cosmic::task::message(app::failure(
+                    "Couldn't play media",
+                    Error::Playback(err),
+                ));
+            }
+            Message::Save => {
+                if let Some(media) = self.current() {
+                    let url = media.url.clone();
+                    return cosmic::task::future(async move {
+                        match save(url).await {
+                            Ok(path) => {
+                                app::Message::Notice(format!("Saved to {}", path.display()))
+                            }
+                            Err(err) => app::action_failure("Couldn't save media", err),
+                        }
+                    });
+                }
+            }
+            Message::OpenInBrowser => {
+                if let Some(media) = self.current() {
+                    return cosmic::task::message(app::Message::Open(media.url.clone()));
+                }
+            }
+            Message::Close => {
+                return cosmic::task::message(app::Message::Dialog(app::DialogAction::Close))
+            }
+        }
+        Task::none()
+    }
+
+    /// Plays the current video or audio while it's playing.
+    #[cfg(feature = "playback")]
+    pub fn subscription(&self) -> cosmic::iced::Subscription<Message> {
+        match self.current() {
+            Some(media) if self.playing && is_playable(media) => player::play(
+                media.url.clone(),
+                self.start,
+                matches!(media.r#type, AttachmentType::Gifv),
+            ),
+            _ => cosmic::iced::Subscription::none(),
+        }
+    }
+
+    /// Without in-app playback there's nothing to run.
+    #[cfg(not(feature = "playback"))]
+    pub fn subscription(&self) -> cosmic::iced::Subscription<Message> {
+        cosmic::iced::Subscription::none()
+    }
+
+    pub fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+        let Some(media) = self.current() else {
+            return widget::text("Nothing to show").into();
+        };
+
+        // Matching top and bottom bars keep the media centered between them.
+        let bar_height = Length::Fixed(96.0);
+
+        let counter = (self.attachments.len() > 1)
+            .then(|| widget::text(format!("{} / {}", self.index + 1, self.attachments.len())));
+        let header_row = widget::row![
+            counter,
+            widget::space::horizontal(),
+            widget::button::icon(widget::icon::from_name("document-save-symbolic"))
+                .class(cosmic::style::Button::Standard)
+                .on_press(Message::Save),
+            widget::button::icon(widget::icon::from_name("send-to-symbolic"))
+                .class(cosmic::style::Button::Standard)
+                .on_press(Message::OpenInBrowser),
+            widget::button::icon(widget::icon::from_name("window-close-symbolic"))
+                .class(cosmic::style::Button::Standard)
+                .on_press(Message::Close),
+        ]
+        .align_y(Alignment::Center)
+        .spacing(spacing.space_xs)
+        .padding(spacing.space_xs);
+
+        let header = widget::container(header_row)
+            .width(Length::Fill)
+            .height(bar_height)
+            .align_y(Vertical::Center);
+
+        let alt_text = match &media.description {
+            Some(description) if !description.trim().is_empty() => {
+                widget::text(description.clone())
+            }
+            _ => widget::text::caption("No alt text"),
+        };
+        let footer = widget::scrollable(alt_text.width(Length::Fill))
+            .apply(widget::container)
+            .padding(spacing.space_xs)
+            .width(Length::Fill)
+            .height(bar_height);
+
+        let previous = widget::button::icon(widget::icon::from_name("go-previous-symbolic"))
+            .class(cosmic::style::Button::Standard)
+            .on_press_maybe((self.index > 0).then_some(Message::Previous));
+        let next = widget::button::icon(widget::icon::from_name("go-next-symbolic"))
+            .class(cosmic::style::Button::Standard)
+            .on_press_maybe((self.index + 1 < self.attachments.len()).then_some(Message::Next));
+        let body = if self.attachments.len() > 1 {
+            widget::row![previous, self.media(media, cache), next]
+                .align_y(Alignment::Center)
+                .spacing(spacing.space_xs)
+                .padding([0, spacing.space_xs])
+                .into()
+        } else {
+            self.media(media, cache)
+        };
+
+        widget::column![header, widget::container(body).center(Length::Fill), footer]
+            .apply(widget::container)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .class(cosmic::style::Container::Dialog(false))
+            .into()
+    }
+
+    fn media<'a>(&'a self, media: &'a Attachment, cache: &'a Cache) -> Element<'a, Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+        // Until the original (or the first frame) arrives, the preview
+        // already in the cache stands in for it.
+        let preview = media
+            .preview_url
+            .as_ref()
+            .and_then(|url| cache.handles.get(url))
+            .or_else(|| cache.blurhash(media));
+
+        match media.r#type {
+            AttachmentType::Image => match cache.handles.get(&media.url).or(preview) {
+                Some(handle) => viewer(handle.clone())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .min_scale(1.0)
+                    .max_scale(10.0)
+                    .into(),
+                None => widget::text("Loading…").into(),
+            },
+            AttachmentType::Gifv | AttachmentType::Video | AttachmentType::Audio => {
+                let picture: Element<_> = match self.frame.as_ref().or(preview) {
+                    Some(handle) => widget::image(handle.clone())
+                        .content_fit(ContentFit::Contain)
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into(),
+                    None => {
+                        widget::icon::from_name(if matches!(media.r#type, AttachmentType::Audio) {
+                            "audio-x-generic-symbolic"
+                        } else {
+                            "video-x-generic-symbolic"
+                        })
+                        .size(128)
+                        .icon()
+                        .apply(widget::container)
+                        .center(Length::Fill)
+                        .into()
+                    }
+                };
+                let play = widget::button::icon(widget::icon::from_name(if self.playing {
+                    "media-playback-pause-symbolic"
+                } else {
+                    "media-playback-start-symbolic"
+                }))
+                .class(cosmic::style::Button::Standard)
+                .on_press(Message::TogglePlaying);
+                let controls = widget::row![play, widget::text(format_position(self.position))]
+                    .align_y(Alignment::Center)
+                    .spacing(spacing.space_xs);
+                widget::column![picture, controls]
+                    .align_x(Alignment::Center)
+                    .spacing(spacing.space_xs)
+                    .into()
+            }
+            _ => widget::column![
+                widget::text("This attachment can't be shown here"),
+                widget::button::standard("Open in browser").on_press(Message::OpenInBrowser),
+            ]
+            .align_x(Alignment::Center)
+            .spacing(spacing.space_xs)
+            .into(),
+        }
+    }
+}
+
+/// Whether an attachment is opened in the browser rather than the viewer,
+/// i.e. it's video or audio and in-app playback isn't built in.
+pub fn plays_externally(media: &Attachment) -> bool {
+    !cfg!(feature = "playback") && is_playable(media)
+}
+
+fn is_playable(media: &Attachment) -> bool {
+    matches!(
+        media.r#type,
+        AttachmentType::Gifv | AttachmentType::Video | AttachmentType::Audio
+    )
+}
+
+/// A playback position as minutes and seconds.
+fn format_position(position: Duration) -> String {
+    let seconds = position.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Download an attachment into the downloads folder, named after the end of
+/// its URL and numbered if that name is taken.
+async fn save(url: String) -> Result<PathBuf, Error> {
+    let bytes = crate::cache::download(&url).await?;
+    tokio::task::spawn_blocking(move || {
+        let dir = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| std::io::Error::other("no downloads folder"))?;
+        let name = url
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.split(['?', '#']).next())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("attachment");
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) => (stem, format!(".{extension}")),
+            None => (name, String::new()),
+        };
+        let mut path = dir.join(name);
+        let mut copy = 1;
+        while path.exists() {
+            path = dir.join(format!("{stem} ({copy}){extension}"));
+            copy += 1;
+        }
+        std::fs::write(&path, bytes)?;
+        Ok(path)
+    })
+    .await
+    .map_err(|err| Error::Io(std::io::Error::other(err)))?
+}
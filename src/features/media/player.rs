@@ -0,0 +1,382 @@
+//! In-app playback of video, gifv and audio attachments, decoded in
+//! software with FFmpeg on a blocking thread. Video frames come back as
+//! image handles, paced to real time; audio goes straight to the default
+//! output device.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use cosmic::iced::{stream, Subscription};
+use cosmic::widget::image::Handle;
+use ffmpeg_next as ffmpeg;
+use futures_channel::mpsc::Sender;
+use futures_util::SinkExt;
+use tokio::io::AsyncWriteExt;
+
+use super::Message;
+use crate::error::Error;
+
+/// How far ahead of real time audio is decoded, so the output device never
+/// runs dry.
+const AUDIO_LEAD: Duration = Duration::from_millis(500);
+/// How often waits check whether playback was stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How often audio-only playback reports its position.
+const POSITION_INTERVAL: Duration = Duration::from_millis(250);
+/// Frames bigger than this are scaled down to fit; the viewer never shows
+/// them larger, and converting fewer pixels keeps up with real time.
+const MAX_FRAME_WIDTH: u32 = 1920;
+const MAX_FRAME_HEIGHT: u32 = 1080;
+
+/// Play `url` from `start`, starting over at the end if `looping`. Stops
+/// when the subscription is dropped.
+pub fn play(url: String, start: Duration, looping: bool) -> Subscription<Message> {
+    Subscription::run_with((url, start, looping), |(url, start, looping)| {
+        let (url, start, looping) = (url.clone(), *start, *looping);
+        stream::channel(1, move |mut output: Sender<Message>| async move {
+            let result = match local_file(url).await {
+                Ok(path) => {
+                    let runtime = tokio::runtime::Handle::current();
+                    let sender = output.clone();
+                    tokio::task::spawn_blocking(move || {
+                        decode(&path, start, looping, sender, runtime)
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(Error::Playback(err.to_string())))
+                }
+                Err(err) => Err(err),
+            };
+            let message = match result {
+                Ok(()) => Message::Ended,
+                Err(err) => Message::PlaybackFailed(err.to_string()),
+            };
+            if let Err(err) = output.send(message).await {
+                tracing::debug!("playback stopped before it ended: {err}");
+            }
+
+            std::future::pending().await
+        })
+    })
+}
+
+/// The attachment on disk, downloading it into the image cache first if
+/// it isn't there yet. FFmpeg needs a file it can seek in.
+async fn local_file(url: String) -> Result<PathBuf, Error> {
+    if let Some(path) = crate::persistence::cached_media_path(&url) {
+        return Ok(path);
+    }
+    let path = crate::persistence::media_download_path(&url)
+        .ok_or_else(|| Error::Playback("there's no cache folder to download to".to_string()))?;
+    crate::error::retry(|| download(&url, &path)).await?;
+    Ok(path)
+}
+
+/// Stream `url` into `path`, via a partial file so an interrupted download
+/// is never mistaken for a cached one.
+async fn download(url: &str, path: &Path) -> Result<(), Error> {
+    let partial = path.with_extension("part");
+    let mut response = crate::error::check(reqwest::get(url).await?).await?;
+    let mut file = tokio::fs::File::create(&partial).await?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    drop(file);
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+fn playback(err: impl ToString) -> Error {
+    Error::Playback(err.to_string())
+}
+
+/// Where decoding has got to, to pace it against the wall clock.
+struct Clock {
+    /// When playback (re)started, and the media position it started from.
+    started: Instant,
+    start: Duration,
+}
+
+impl Clock {
+    fn new(start: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            start,
+        }
+    }
+
+    /// Wait until `position` (less `lead`) is due. `false` if playback was
+    /// stopped in the meantime.
+    fn wait(&self, position: Duration, lead: Duration, output: &Sender<Message>) -> bool {
+        let due = self.started + position.saturating_sub(self.start).saturating_sub(lead);
+        loop {
+            if output.is_closed() {
+                return false;
+            }
+            let now = Instant::now();
+            if now >= due {
+                return true;
+            }
+            std::thread::sleep((due - now).min(POLL_INTERVAL));
+        }
+    }
+}
+
+fn decode(
+    path: &Path,
+    start: Duration,
+    looping: bool,
+    mut output: Sender<Message>,
+    runtime: tokio::runtime::Handle,
+) -> Result<(), Error> {
+    ffmpeg::init().map_err(playback)?;
+    let mut input = ffmpeg::format::input(path).map_err(playback)?;
+
+    let mut video = match input.streams().best(ffmpeg::media::Type::Video) {
+        Some(stream) => Some(VideoStream::new(&stream)?),
+        None => None,
+    };
+    let mut audio = match input.streams().best(ffmpeg::media::Type::Audio) {
+        Some(stream) => AudioStream::new(&stream)
+            .inspect_err(|err| tracing::warn!("playing without sound: {err}"))
+            .ok(),
+        None => None,
+    };
+    if video.is_none() && audio.is_none() {
+        return Err(Error::Playback("nothing to play".to_string()));
+    }
+
+    let mut start = start;
+    loop {
+        if !start.is_zero() {
+            let timestamp = start.as_micros() as i64;
+            input.seek(timestamp, ..timestamp).map_err(playback)?;
+        }
+        let clock = Clock::new(start);
+        let mut reported = Instant::now();
+        let audio_only = video.is_none();
+
+        for (stream, packet) in input.packets() {
+            if let Some(video) = video.as_mut().filter(|video| video.index == stream.index()) {
+                video.decoder.send_packet(&packet).map_err(playback)?;
+                if !video.drain(&clock, &mut output, &runtime)? {
+                    return Ok(());
+                }
+            } else if let Some(audio) = audio.as_mut().filter(|audio| audio.index == stream.index())
+            {
+                audio.decoder.send_packet(&packet).map_err(playback)?;
+                let Some(position) = audio.drain(&clock, &output)? else {
+                    return Ok(());
+                };
+                if audio_only && reported.elapsed() >= POSITION_INTERVAL {
+                    reported = Instant::now();
+                    if runtime
+                        .block_on(output.send(Message::Position(position)))
+                        .is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        if let Some(video) = video.as_mut() {
+            video.decoder.send_eof().map_err(playback)?;
+            if !video.drain(&clock, &mut output, &runtime)? {
+                return Ok(());
+            }
+            video.decoder.flush();
+        }
+        if let Some(audio) = audio.as_mut() {
+            audio.decoder.send_eof().map_err(playback)?;
+            if audio.drain(&clock, &output)?.is_none() {
+                return Ok(());
+            }
+            audio.decoder.flush();
+        }
+
+        if !looping {
+            break;
+        }
+        start = Duration::ZERO;
+        input.seek(0, ..0).map_err(playback)?;
+    }
+
+    // Let the last of the sound play out.
+    if let Some(audio) = &audio {
+        while !audio.sink.empty() {
+            if output.is_closed() {
+                return Ok(());
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+    Ok(())
+}
+
+/// Seconds of a frame's timestamp, in its stream's time base.
+fn frame_position(timestamp: Option<i64>, time_base: f64) -> Option<Duration> {
+    Some(Duration::from_secs_f64(
+        (timestamp? as f64 * time_base).max(0.0),
+    ))
+}
+
+struct VideoStream {
+    index: usize,
+    time_base: f64,
+    decoder: ffmpeg::decoder::Video,
+    scaler: ffmpeg::software::scaling::Context,
+}
+
+impl VideoStream {
+    fn new(stream: &ffmpeg::Stream) -> Result<Self, Error> {
+        let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+            .map_err(playback)?;
+        let decoder = context.decoder().video().map_err(playback)?;
+        let (width, height) = fit(decoder.width(), decoder.height());
+        let scaler = ffmpeg::software::scaling::Context::get(
+            decoder.format(),
+            decoder.width(),
+            decoder.height(),
+            ffmpeg::format::Pixel::RGBA,
+            width,
+            height,
+            ffmpeg::software::scaling::Flags::BILINEAR,
+        )
+        .map_err(playback)?;
+        Ok(Self {
+            index: stream.index(),
+            time_base: f64::from(stream.time_base()),
+            decoder,
+            scaler,
+        })
+    }
+
+    /// Send every frame decoded so far, each when it's due. `false` if
+    /// playback was stopped.
+    fn drain(
+        &mut self,
+        clock: &Clock,
+        output: &mut Sender<Message>,
+        runtime: &tokio::runtime::Handle,
+    ) -> Result<bool, Error> {
+        let mut decoded = ffmpeg::frame::Video::empty();
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let Some(position) = frame_position(decoded.timestamp(), self.time_base) else {
+                continue;
+            };
+            // Frames between the keyframe seeked to and the start position.
+            if position < clock.start {
+                continue;
+            }
+            let mut rgba = ffmpeg::frame::Video::empty();
+            self.scaler.run(&decoded, &mut rgba).map_err(playback)?;
+            let frame = Handle::from_rgba(rgba.width(), rgba.height(), pixels(&rgba));
+            if !clock.wait(position, Duration::ZERO, output)
+                || runtime
+                    .block_on(output.send(Message::Frame(frame, position)))
+                    .is_err()
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// A frame's size scaled down to fit [`MAX_FRAME_WIDTH`] by
+/// [`MAX_FRAME_HEIGHT`], keeping its aspect ratio.
+fn fit(width: u32, height: u32) -> (u32, u32) {
+    let scale = (MAX_FRAME_WIDTH as f64 / width as f64)
+        .min(MAX_FRAME_HEIGHT as f64 / height as f64)
+        .min(1.0);
+    (
+        ((width as f64 * scale) as u32).max(1),
+        ((height as f64 * scale) as u32).max(1),
+    )
+}
+
+/// An RGBA frame's pixels without the padding FFmpeg adds to each row.
+fn pixels(frame: &ffmpeg::frame::Video) -> Vec<u8> {
+    let row = frame.width() as usize * 4;
+    frame
+        .data(0)
+        .chunks(frame.stride(0))
+        .take(frame.height() as usize)
+        .flat_map(|line| &line[..row])
+        .copied()
+        .collect()
+}
+
+struct AudioStream {
+    index: usize,
+    time_base: f64,
+    decoder: ffmpeg::decoder::Audio,
+    resampler: ffmpeg::software::resampling::Context,
+    sink: rodio::Sink,
+    /// Keeps the output device open for as long as the sink plays.
+    _output: rodio::OutputStream,
+}
+
+impl AudioStream {
+    fn new(stream: &ffmpeg::Stream) -> Result<Self, Error> {
+        let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+            .map_err(playback)?;
+        let decoder = context.decoder().audio().map_err(playback)?;
+        let resampler = decoder
+            .resampler(
+                ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+                ffmpeg::ChannelLayout::STEREO,
+                decoder.rate(),
+            )
+            .map_err(playback)?;
+        let (output, handle) = rodio::OutputStream::try_default().map_err(playback)?;
+        let sink = rodio::Sink::try_new(&handle).map_err(playback)?;
+        Ok(Self {
+            index: stream.index(),
+            time_base: f64::from(stream.time_base()),
+            decoder,
+            resampler,
+            sink,
+            _output: output,
+        })
+    }
+
+    /// Queue every frame decoded so far, keeping [`AUDIO_LEAD`] ahead of
+    /// real time. The position reached, or `None` if playback was stopped.
+    fn drain(
+        &mut self,
+        clock: &Clock,
+        output: &Sender<Message>,
+    ) -> Result<Option<Duration>, Error> {
+        let mut position = clock.start;
+        let mut decoded = ffmpeg::frame::Audio::empty();
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let Some(timestamp) = frame_position(decoded.timestamp(), self.time_base) else {
+                continue;
+            };
+            if timestamp < clock.start {
+                continue;
+            }
+            position = timestamp;
+            let mut resampled = ffmpeg::frame::Audio::empty();
+            self.resampler
+                .run(&decoded, &mut resampled)
+                .map_err(playback)?;
+            let samples: Vec<f32> = resampled
+                .plane::<(f32, f32)>(0)
+                .iter()
+                .flat_map(|&(left, right)| [left, right])
+                .collect();
+            self.sink.append(rodio::buffer::SamplesBuffer::new(
+                2,
+                self.decoder.rate(),
+                samples,
+            ));
+            if !clock.wait(position, AUDIO_LEAD, output) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(position))
+    }
+}
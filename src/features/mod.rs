@@ -4,9 +4,12 @@
 
 pub mod accounts;
 pub mod compose;
+pub mod deck;
 pub mod hashtags;
 pub mod lists;
+pub mod media;
 pub mod notifications;
+pub mod outbox;
 pub mod search;
 pub mod settings;
 pub mod status;
@@ -2,7 +2,10 @@ use cosmic::iced::{stream, Subscription};
 use futures_util::SinkExt;
 use megalodon::megalodon::GetNotificationsInputOptions;
 
-use crate::client::Client;
+use crate::{
+    client::Client,
+    error::{self, Error},
+};
 
 use super::Message;
 
@@ -17,9 +20,17 @@ pub fn timeline(mastodon: Client, max_id: Option<String>) -> Subscription<Messag
                 ..Default::default()
             };
 
-            match mastodon.get_notifications(Some(&options)).await {
-                Ok(response) => {
-                    for notification in response.json {
+            let result = error::retry(|| async {
+                let result = match mastodon.get_notifications(Some(&options)).await {
+                    Ok(response) => Ok(response.json),
+                    Err(err) => Err(Error::from(err)),
+                };
+                mastodon.with_rate_limit_reset(result).await
+            })
+            .await;
+            match result {
+                Ok(notifications) => {
+                    for notification in notifications {
                         if let Err(err) = output
                             .send(Message::AppendNotification(notification.clone()))
                             .await
@@ -28,6 +39,18 @@ pub fn timeline(mastodon: Client, max_id: Option<String>) -> Subscription<Messag
                         }
                     }
                 }
+                Err(Error::Unauthorized) => {
+                    if let Err(err) = output.send(Message::Unauthorized).await {
+                        tracing::warn!("failed to send unauthorized: {}", err);
+                    }
+                }
+                Err(Error::Network(err) | Error::TimedOut(err)) => {
+                    tracing::warn!("offline: {}", err);
+                    if let Err(err) = output.send(Message::Offline).await {
+                        tracing::warn!("failed to send offline: {}", err);
+                    }
+                    return std::future::pending().await;
+                }
                 Err(err) => {
                     tracing::warn!("failed to get notifications: {}", err);
                 }
@@ -37,6 +37,10 @@ pub enum Message {
     ClearAll,
     /// A fetch's result stream has ended (successfully, even if empty).
     LoadComplete,
+    /// A fetch was rejected because the session's token is no longer valid.
+    Unauthorized,
+    /// A fetch couldn't reach the server. Sent instead of `LoadComplete`.
+    Offline,
 }
 
 const FILTERS: [Option<NotificationType>; 5] = [
@@ -91,11 +95,10 @@ impl Notifications {
         self.notifications.is_empty() && !self.has_loaded
     }
 
-    /// Load the last-saved notification snapshot from disk (if any) so the
-    /// view has something to render immediately, before the network fetch lands.
+    /// Load the last-saved notifications from disk (if any) so the view
+    /// has something to render immediately, before the network fetch lands.
     pub fn load_cached(&mut self) -> Task<app::Message> {
-        let cached: Vec<Notification> =
-            crate::persistence::load_snapshot(&self.mastodon.base_url, "notifications");
+        let cached = crate::persistence::load_notifications(&self.mastodon.base_url);
         let mut tasks = vec![];
         for notification in cached {
             if !self.notifications.contains(&notification.id) {
@@ -111,14 +114,18 @@ impl Notifications {
         Task::batch(tasks)
     }
 
-    /// Persist the currently-cached notifications to disk.
-    pub fn save_cached(&self, cache: &Cache) {
-        let notifications: Vec<Notification> = self
-            .notifications
+    /// Ids of the notifications this feed shows, which must stay in the cache.
+    pub fn notification_ids(&self) -> impl Iterator<Item = &String> {
+        self.notifications.iter()
+    }
+
+    /// Every image this feed's loaded notifications show.
+    pub fn image_urls(&self, cache: &Cache) -> Vec<String> {
+        self.notifications
             .iter()
-            .filter_map(|id| cache.notifications.get(id).cloned())
-            .collect();
-        crate::persistence::save_notification_snapshot(&self.mastodon.base_url, &notifications);
+            .filter_map(|id| cache.notifications.get(id))
+            .flat_map(cache::extract_notification_images)
+            .collect()
     }
 
     pub fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, Message> {
@@ -151,6 +158,12 @@ impl Notifications {
                     .as_ref()
                     .is_none_or(|filter| notification.r#type == *filter)
             })
+            .filter(|notification| {
+                notification
+                    .account
+                    .as_ref()
+                    .is_none_or(|account| !cache.is_domain_blocked(account))
+            })
             .map(|notification| view::notification(notification, cache).map(Message::Notification))
             .collect();
 
@@ -161,6 +174,7 @@ impl Notifications {
             ]
             .apply(widget::container)
             .max_width(700)
+            .width(Length::Fill)
             .height(Length::Fill)
             .into();
         }
@@ -186,6 +200,7 @@ impl Notifications {
         ]
         .apply(widget::container)
         .max_width(700)
+        .width(Length::Fill)
         .height(Length::Fill)
         .into()
     }
@@ -224,6 +239,16 @@ impl Notifications {
                 self.loading = false;
                 self.has_loaded = true;
             }
+            Message::Unauthorized => {
+                tasks.push(cosmic::task::message(app::Message::SessionExpired));
+            }
+            Message::Offline => {
+                // Fetch again once back online; until then the cached
+                // content stays up without a loading indicator.
+                self.loading = false;
+                self.has_loaded = false;
+                tasks.push(cosmic::task::message(app::Message::Offline));
+            }
             Message::SetFilter(filter) => self.filter = filter,
             Message::ClearAll => {
                 self.notifications.clear();
@@ -231,9 +256,7 @@ impl Notifications {
                 tasks.push(cosmic::task::future(async move {
                     match mastodon.dismiss_notifications().await {
                         Ok(_) => app::Message::None,
-                        Err(err) => {
-                            app::Message::Error(format!("Couldn't clear notifications: {err}"))
-                        }
+                        Err(err) => app::action_failure("Couldn't clear notifications", err),
                     }
                 }));
             }
@@ -245,9 +268,7 @@ impl Notifications {
                     tasks.push(cosmic::task::future(async move {
                         match mastodon.accept_follow_request(account_id).await {
                             Ok(response) => app::Message::CacheRelationship(response.json),
-                            Err(err) => {
-                                app::Message::Error(format!("Couldn't accept follow: {err}"))
-                            }
+                            Err(err) => app::action_failure("Couldn't accept follow", err),
                         }
                     }));
                 }
@@ -257,9 +278,7 @@ impl Notifications {
                     tasks.push(cosmic::task::future(async move {
                         match mastodon.reject_follow_request(account_id).await {
                             Ok(response) => app::Message::CacheRelationship(response.json),
-                            Err(err) => {
-                                app::Message::Error(format!("Couldn't reject follow: {err}"))
-                            }
+                            Err(err) => app::action_failure("Couldn't reject follow", err),
                         }
                     }));
                 }
@@ -2,7 +2,7 @@ use cosmic::{widget, Element};
 use megalodon::entities::{notification::NotificationType, Notification};
 
 use crate::cache::{self, Cache};
-use crate::features::status::{self, StatusOptions};
+use crate::features::status::{self, emoji, StatusOptions};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -60,9 +60,15 @@ pub fn notification<'a>(notification: &'a Notification, cache: &'a Cache) -> Ele
         .unwrap_or_else(|| cache::fallback_avatar().width(20));
 
     let action = action.unwrap_or_else(|| "Unknown notification type".to_string());
+    let emojis = notification
+        .account
+        .as_ref()
+        .map(|account| account.emojis.as_slice())
+        .unwrap_or_default();
 
     let action = widget::button::custom(
-        widget::row![avatar_url, widget::text(action)].spacing(spacing.space_xs),
+        widget::row![avatar_url, emoji::text(&action, emojis, cache, 14)]
+            .spacing(spacing.space_xs),
     )
     .on_press_maybe(notification.account.as_ref().map(|account| {
         Message::Status(status::Message::OpenAccount(account.clone()))
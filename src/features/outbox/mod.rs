@@ -0,0 +1,428 @@
+//! Outbox: actions taken while the server can't be reached, persisted per
+//! account and replayed in order once it can, with a page to inspect or
+//! discard what's still pending.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use cosmic::{
+    app::Task,
+    iced::{stream, Alignment, Length, Subscription},
+    widget, Apply, Element,
+};
+use futures_util::SinkExt;
+use megalodon::{entities::status::StatusVisibility, megalodon::PostStatusInputOptions};
+use serde::{Deserialize, Serialize};
+
+use crate::{app, client::Client, error::Error, fl, persistence};
+
+/// How often the server is probed while offline.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Something the user did that changes state on the server. Toggles carry
+/// the state at the time of the action, like the messages they come from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Action {
+    /// (status id, currently favorited)
+    Favorite(String, bool),
+    /// (status id, currently boosted)
+    Boost(String, bool),
+    /// (status id, currently bookmarked)
+    Bookmark(String, bool),
+    /// (status id, emoji, currently reacted)
+    React(String, String, bool),
+    /// (account id, currently following)
+    Follow(String, bool),
+    /// (account id, currently muting)
+    Mute(String, bool),
+    /// (account id, currently blocking)
+    Block(String, bool),
+    /// (account id, currently endorsed)
+    Endorse(String, bool),
+    /// (account id, note)
+    Note(String, String),
+    Post {
+        text: String,
+        in_reply_to_id: Option<String>,
+        spoiler_text: Option<String>,
+        visibility: StatusVisibility,
+    },
+}
+
+impl Action {
+    /// Send the action, returning the message that applies its result.
+    pub async fn perform(self, mastodon: Client) -> Result<app::Message, Error> {
+        let message = match self {
+            Action::Favorite(id, true) => {
+                app::Message::CacheStatus(mastodon.unfavourite_status(id).await?.json)
+            }
+            Action::Favorite(id, false) => {
+                app::Message::CacheStatus(mastodon.favourite_status(id).await?.json)
+            }
+            Action::Boost(id, true) => {
+                app::Message::CacheStatus(mastodon.unreblog_status(id).await?.json)
+            }
+            Action::Boost(id, false) => {
+                app::Message::CacheStatus(mastodon.reblog_status(id).await?.json)
+            }
+            Action::Bookmark(id, true) => {
+                app::Message::CacheStatus(mastodon.unbookmark_status(id).await?.json)
+            }
+            Action::Bookmark(id, false) => {
+                app::Message::CacheStatus(mastodon.bookmark_status(id).await?.json)
+            }
+            Action::React(id, emoji, true) => {
+                app::Message::CacheStatus(mastodon.delete_emoji_reaction(id, emoji).await?.json)
+            }
+            Action::React(id, emoji, false) => {
+                app::Message::CacheStatus(mastodon.create_emoji_reaction(id, emoji).await?.json)
+            }
+            Action::Follow(id, true) => {
+                app::Message::CacheRelationship(mastodon.unfollow_account(id).await?.json)
+            }
+            Action::Follow(id, false) => {
+                app::Message::CacheRelationship(mastodon.follow_account(id, None).await?.json)
+            }
+            Action::Mute(id, true) => {
+                app::Message::CacheRelationship(mastodon.unmute_account(id).await?.json)
+            }
+            Action::Mute(id, false) => {
+                app::Message::CacheRelationship(mastodon.mute_account(id, true).await?.json)
+            }
+            Action::Block(id, true) => {
+                app::Message::CacheRelationship(mastodon.unblock_account(id).await?.json)
+            }
+            Action::Block(id, false) => {
+                app::Message::CacheRelationship(mastodon.block_account(id).await?.json)
+            }
+            Action::Endorse(id, endorsed) => {
+                app::Message::CacheRelationship(mastodon.set_endorsed(&id, !endorsed).await?)
+            }
+            Action::Note(id, note) => app::Message::CacheRelationship(
+                mastodon.set_account_note(id, Some(note)).await?.json,
+            ),
+            Action::Post {
+                text,
+                in_reply_to_id,
+                spoiler_text,
+                visibility,
+            } => {
+                let options = PostStatusInputOptions {
+                    in_reply_to_id,
+                    spoiler_text,
+                    visibility: Some(visibility),
+                    ..Default::default()
+                };
+                match mastodon.post_status(text, Some(&options)).await?.json {
+                    megalodon::megalodon::PostStatusOutput::Status(status) => {
+                        app::Message::CacheStatus(status)
+                    }
+                    megalodon::megalodon::PostStatusOutput::ScheduledStatus(_) => {
+                        app::Message::None
+                    }
+                }
+            }
+        };
+        Ok(message)
+    }
+
+    /// Whether sending the action twice leaves the same result as sending it
+    /// once, so it can be replayed after a timeout. Toggles carry the state
+    /// they move away from, so repeating one is harmless; a post would be
+    /// published twice.
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(self, Action::Post { .. })
+    }
+
+    /// Prefix for the error toast if the server rejects the action.
+    pub fn context(&self) -> &'static str {
+        match self {
+            Action::Favorite(..) => "Couldn't update favorite",
+            Action::Boost(..) => "Couldn't update boost",
+            Action::Bookmark(..) => "Couldn't update bookmark",
+            Action::React(..) => "Couldn't update reaction",
+            Action::Follow(..) => "Couldn't update follow",
+            Action::Mute(..) => "Couldn't update mute",
+            Action::Block(..) => "Couldn't update block",
+            Action::Endorse(..) => "Couldn't update featured accounts",
+            Action::Note(..) => "Couldn't save note",
+            Action::Post { .. } => "Couldn't post",
+        }
+    }
+
+    /// What the outbox page lists the action as.
+    fn describe(&self) -> String {
+        let toggle =
+            |undo: bool, done: &str, undone: &str| if undo { undone } else { done }.to_string();
+        match self {
+            Action::Favorite(_, undo) => toggle(*undo, "Favorite a post", "Unfavorite a post"),
+            Action::Boost(_, undo) => toggle(*undo, "Boost a post", "Undo a boost"),
+            Action::Bookmark(_, undo) => toggle(*undo, "Bookmark a post", "Remove a bookmark"),
+            Action::React(_, emoji, true) => format!("Remove {emoji} reaction"),
+            Action::React(_, emoji, false) => format!("React with {emoji}"),
+            Action::Follow(_, undo) => toggle(*undo, "Follow an account", "Unfollow an account"),
+            Action::Mute(_, undo) => toggle(*undo, "Mute an account", "Unmute an account"),
+            Action::Block(_, undo) => toggle(*undo, "Block an account", "Unblock an account"),
+            Action::Endorse(_, undo) => {
+                toggle(*undo, "Feature an account", "Stop featuring an account")
+            }
+            Action::Note(..) => "Save a note on an account".to_string(),
+            Action::Post {
+                text,
+                in_reply_to_id,
+                ..
+            } => {
+                let preview: String = text.chars().take(60).collect();
+                let ellipsis = if preview.len() < text.len() {
+                    "…"
+                } else {
+                    ""
+                };
+                let kind = if in_reply_to_id.is_some() {
+                    "Reply"
+                } else {
+                    "Post"
+                };
+                format!("{kind}: {preview}{ellipsis}")
+            }
+        }
+    }
+}
+
+/// An action waiting in the outbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Queued {
+    pub id: u64,
+    pub action: Action,
+    pub queued_at: DateTime<Utc>,
+}
+
+pub struct Outbox {
+    mastodon: Client,
+    queue: VecDeque<Queued>,
+    /// An action is being replayed; the next one is only sent once it's done,
+    /// so the server sees them in the order they were taken.
+    sending: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SetClient(Client),
+    /// An action couldn't reach the server: queue it and go offline.
+    Queue(Action),
+    Discard(u64),
+    DiscardAll,
+    /// Try sending the queue now, rather than waiting for the reconnect probe.
+    SendNow,
+    /// The queued action went through, or was rejected for good; drop it,
+    /// apply the boxed result and send the next one.
+    Sent(u64, Box<app::Message>),
+    /// Replaying stopped at the front action, which stays queued; the boxed
+    /// message says why (offline again, or the session expired).
+    Stalled(Box<app::Message>),
+}
+
+impl Outbox {
+    pub fn new(mastodon: Client) -> Self {
+        let queue = Self::load(&mastodon);
+        Self {
+            mastodon,
+            queue,
+            sending: false,
+        }
+    }
+
+    fn load(mastodon: &Client) -> VecDeque<Queued> {
+        if mastodon.is_authenticated() {
+            persistence::load_outbox(&mastodon.base_url).into()
+        } else {
+            VecDeque::new()
+        }
+    }
+
+    fn save(&self) {
+        if self.mastodon.is_authenticated() {
+            let queue: Vec<&Queued> = self.queue.iter().collect();
+            persistence::save_outbox(&self.mastodon.base_url, &queue);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Add `action` to the back of the queue.
+    pub fn push(&mut self, action: Action) {
+        let id = self
+            .queue
+            .iter()
+            .map(|queued| queued.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.queue.push_back(Queued {
+            id,
+            action,
+            queued_at: Utc::now(),
+        });
+        self.save();
+    }
+
+    /// Send the front of the queue, if nothing is being sent already.
+    pub fn replay(&mut self) -> Task<app::Message> {
+        if self.sending {
+            return Task::none();
+        }
+        let Some(queued) = self.queue.front().cloned() else {
+            return Task::none();
+        };
+        self.sending = true;
+        let mastodon = self.mastodon.clone();
+        cosmic::task::future(async move {
+            let context = queued.action.context();
+            let idempotent = queued.action.is_idempotent();
+            let message = match queued.action.perform(mastodon).await {
+                Ok(message) => Message::Sent(queued.id, Box::new(message)),
+                Err(Error::TimedOut(err)) if !idempotent => {
+                    tracing::warn!("timed out, dropping queued action: {err}");
+                    let message = app::Message::Error(fl!("may-not-have-been-sent"));
+                    Message::Sent(queued.id, Box::new(message))
+                }
+                Err(Error::Network(_) | Error::TimedOut(_)) => {
+                    Message::Stalled(Box::new(app::Message::Offline))
+                }
+                Err(Error::Unauthorized) => {
+                    Message::Stalled(Box::new(app::Message::SessionExpired))
+                }
+                Err(err) => Message::Sent(queued.id, Box::new(app::failure(context, err))),
+            };
+            app::Message::Outbox(message)
+        })
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+
+        if self.queue.is_empty() {
+            return widget::text("Nothing waiting to be sent")
+                .apply(widget::container)
+                .center(Length::Fill)
+                .into();
+        }
+
+        let section = self.queue.iter().fold(
+            widget::settings::section().title("Waiting to be sent"),
+            |section, queued| {
+                let queued_at = queued.queued_at.with_timezone(&Local);
+                section.add(widget::settings::item_row(vec![
+                    widget::column![
+                        widget::text(queued.action.describe()),
+                        widget::text::caption(format!(
+                            "Queued {}",
+                            queued_at.format("%b %-d, %H:%M")
+                        )),
+                    ]
+                    .into(),
+                    widget::space::horizontal().into(),
+                    widget::button::destructive("Discard")
+                        .on_press(Message::Discard(queued.id))
+                        .into(),
+                ]))
+            },
+        );
+
+        widget::column![
+            widget::row![
+                widget::text::title4(format!("{} pending", self.queue.len())),
+                widget::space::horizontal(),
+                widget::button::standard("Discard all").on_press(Message::DiscardAll),
+                widget::button::suggested("Send now")
+                    .on_press_maybe((!self.sending).then_some(Message::SendNow)),
+            ]
+            .spacing(spacing.space_xs)
+            .align_y(Alignment::Center),
+            widget::scrollable(section)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        ]
+        .spacing(spacing.space_xs)
+        .apply(widget::container)
+        .max_width(700)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Alignment::Start)
+        .into()
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<app::Message> {
+        match message {
+            Message::SetClient(mastodon) => {
+                if mastodon.base_url != self.mastodon.base_url
+                    || mastodon.is_authenticated() != self.mastodon.is_authenticated()
+                {
+                    self.queue = Self::load(&mastodon);
+                    self.sending = false;
+                }
+                self.mastodon = mastodon;
+            }
+            Message::Queue(action) => {
+                self.push(action);
+                return cosmic::task::message(app::Message::Offline);
+            }
+            Message::Discard(id) => {
+                // The front action may be in flight; it'll still be sent, but
+                // its `Sent` finds nothing left to remove.
+                self.queue.retain(|queued| queued.id != id);
+                self.save();
+            }
+            Message::DiscardAll => {
+                self.queue.clear();
+                self.save();
+            }
+            Message::SendNow => return self.replay(),
+            Message::Sent(id, message) => {
+                self.sending = false;
+                self.queue.retain(|queued| queued.id != id);
+                self.save();
+                return Task::batch(vec![cosmic::task::message(*message), self.replay()]);
+            }
+            Message::Stalled(message) => {
+                self.sending = false;
+                return cosmic::task::message(*message);
+            }
+        }
+        Task::none()
+    }
+}
+
+/// While offline, probe the server every [`RECONNECT_INTERVAL`] and report
+/// [`app::Message::Online`] once it answers at all (even with an error
+/// status: it's reachable).
+pub fn reconnect(mastodon: Client) -> Subscription<app::Message> {
+    Subscription::run_with(mastodon, |mastodon| {
+        let mastodon = mastodon.clone();
+        stream::channel(
+            1,
+            move |mut output: futures_channel::mpsc::Sender<app::Message>| async move {
+                loop {
+                    tokio::time::sleep(RECONNECT_INTERVAL).await;
+                    match mastodon.get_instance().await.map_err(Error::from) {
+                        Err(Error::Network(err) | Error::TimedOut(err)) => {
+                            tracing::debug!("still offline: {err}");
+                        }
+                        _ => break,
+                    }
+                }
+                if let Err(err) = output.send(app::Message::Online).await {
+                    tracing::warn!("failed to send online: {}", err);
+                }
+
+                std::future::pending().await
+            },
+        )
+    })
+}
@@ -1,5 +1,7 @@
 //! Search: accounts, hashtags, and statuses matching a query, plus a
-//! trending-hashtags view while idle.
+//! trending-hashtags view while idle. The "Local" tab searches posts already
+//! cached on disk, which also works offline and finds posts the server
+//! doesn't index.
 
 use std::collections::HashMap;
 
@@ -10,7 +12,7 @@ use cosmic::{
     Apply, Element,
 };
 use megalodon::{
-    entities::{Account, Results, Tag},
+    entities::{Account, Results, Status, Tag},
     megalodon::SearchInputOptions,
 };
 
@@ -30,12 +32,15 @@ enum ResultKind {
     Accounts,
     Hashtags,
     Statuses,
+    Local,
 }
 
 pub struct Search {
     mastodon: Client,
     query: String,
     results: Option<Results>,
+    /// Matches from the local full-text index, newest query only.
+    local_results: Option<Vec<Status>>,
     searching: bool,
     result_tabs: segmented_button::SingleSelectModel,
     trending: Vec<Tag>,
@@ -54,7 +59,9 @@ pub enum Message {
     /// Clear the query and any results, returning to the idle/trending view.
     Clear,
     SetResults(Results),
-    SearchFailed(String),
+    SetLocalResults(Vec<Status>),
+    /// The server search failed; the boxed message reports why.
+    SearchFailed(Box<app::Message>),
     SelectResultKind(segmented_button::Entity),
     OpenAccount(Account),
     ToggleFollowAccount(String, bool),
@@ -69,16 +76,46 @@ pub enum Message {
 }
 
 impl Search {
+    /// Ids of the statuses in the current server and local results, and in
+    /// the selected tag's timeline.
+    pub fn status_ids(&self) -> impl Iterator<Item = &String> {
+        let remote = self.results.iter().flat_map(|results| &results.statuses);
+        let local = self.local_results.iter().flatten();
+        remote
+            .chain(local)
+            .map(|status| &status.id)
+            .chain(self.tag_timeline.iter().flat_map(Timeline::status_ids))
+    }
+
+    /// Every image the current results and the selected tag's timeline show.
+    pub fn image_urls(&self, cache: &Cache) -> Vec<String> {
+        let remote = self.results.iter().flat_map(|results| &results.statuses);
+        let local = self.local_results.iter().flatten();
+        let avatars = self
+            .results
+            .iter()
+            .flat_map(|results| &results.accounts)
+            .map(|account| account.avatar.clone());
+        remote
+            .chain(local)
+            .flat_map(cache::extract_status_images)
+            .chain(avatars)
+            .chain(self.tag_timeline.iter().flat_map(|timeline| timeline.image_urls(cache)))
+            .collect()
+    }
+
     pub fn new(mastodon: Client) -> Self {
         let mut result_tabs = segmented_button::SingleSelectModel::default();
         result_tabs.insert().text("Users").data(ResultKind::Accounts).activate();
         result_tabs.insert().text("Hashtags").data(ResultKind::Hashtags);
         result_tabs.insert().text("Posts").data(ResultKind::Statuses);
+        result_tabs.insert().text("Local").data(ResultKind::Local);
 
         Self {
             mastodon,
             query: String::new(),
             results: None,
+            local_results: None,
             searching: false,
             result_tabs,
             trending: Vec::new(),
@@ -88,6 +125,16 @@ impl Search {
         }
     }
 
+    fn activate_tab(&mut self, kind: ResultKind) {
+        let entity = self
+            .result_tabs
+            .iter()
+            .find(|entity| self.result_tabs.data::<ResultKind>(*entity) == Some(&kind));
+        if let Some(entity) = entity {
+            self.result_tabs.activate(entity);
+        }
+    }
+
     fn is_following_tag(&self, tag: &Tag) -> bool {
         self.tag_follow_overrides
             .get(&tag.name)
@@ -116,7 +163,8 @@ impl Search {
             .into();
         }
 
-        let has_query_or_results = !self.query.is_empty() || self.results.is_some();
+        let has_results = self.results.is_some() || self.local_results.is_some();
+        let has_query_or_results = !self.query.is_empty() || has_results;
         let input = widget::row![
             widget::text_input("Search accounts, hashtags, statuses", &self.query)
                 .on_input(Message::QueryChanged)
@@ -136,7 +184,7 @@ impl Search {
                 .into()
         });
 
-        let idle: Option<Element<_>> = (self.results.is_none() && !self.searching).then(|| {
+        let idle: Option<Element<_>> = (!has_results && !self.searching).then(|| {
             self.trending
                 .iter()
                 .fold(
@@ -146,14 +194,35 @@ impl Search {
                 .into()
         });
 
-        let tabs: Option<Element<_>> = self.results.is_some().then(|| {
+        let tabs: Option<Element<_>> = has_results.then(|| {
             widget::segmented_control::horizontal(&self.result_tabs)
                 .on_activate(Message::SelectResultKind)
                 .into()
         });
 
-        let results_content: Option<Element<_>> = self.results.as_ref().map(|results| {
-            match self.result_tabs.active_data::<ResultKind>() {
+        let statuses = |statuses: &'a [Status]| -> Element<'a, Message> {
+            widget::column(
+                statuses
+                    .iter()
+                    .map(|status| {
+                        status::status(status, StatusOptions::all(), cache).map(Message::Status)
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .spacing(spacing.space_xs)
+            .into()
+        };
+
+        let kind = self.result_tabs.active_data::<ResultKind>();
+        let results_content: Option<Element<_>> = match kind {
+            Some(ResultKind::Local) => self.local_results.as_ref().map(|results| {
+                if results.is_empty() {
+                    widget::text("No cached posts match").into()
+                } else {
+                    statuses(results)
+                }
+            }),
+            kind => self.results.as_ref().map(|results| match kind {
                 Some(ResultKind::Accounts) => results
                     .accounts
                     .iter()
@@ -168,17 +237,9 @@ impl Search {
                         section.add(tag_row(tag, self.is_following_tag(tag)))
                     })
                     .into(),
-                Some(ResultKind::Statuses) | None => widget::column(
-                    results
-                        .statuses
-                        .iter()
-                        .map(|status| status::status(status, StatusOptions::all(), cache).map(Message::Status))
-                        .collect::<Vec<_>>(),
-                )
-                .spacing(spacing.space_xs)
-                .into(),
-            }
-        });
+                _ => statuses(&results.statuses),
+            }),
+        };
 
         widget::column![
             input,
@@ -210,20 +271,35 @@ impl Search {
                 let query = self.query.trim().to_string();
                 if !query.is_empty() {
                     self.searching = true;
+                    let base_url = self.mastodon.base_url.clone();
+                    let local_query = query.clone();
+                    let local = cosmic::task::future(async move {
+                        let results = tokio::task::spawn_blocking(move || {
+                            crate::persistence::search_statuses(&base_url, &local_query)
+                        })
+                        .await
+                        .unwrap_or_default();
+                        app::Message::Search(Message::SetLocalResults(results))
+                    });
                     let mastodon = self.mastodon.clone();
-                    return cosmic::task::future(async move {
+                    let remote = cosmic::task::future(async move {
                         match mastodon.search(query, Some(&SearchInputOptions::default())).await {
                             Ok(response) => {
                                 app::Message::Search(Message::SetResults(response.json))
                             }
-                            Err(err) => app::Message::Search(Message::SearchFailed(err.to_string())),
+                            Err(err) => {
+                                let failure = app::failure("Search failed", err);
+                                app::Message::Search(Message::SearchFailed(Box::new(failure)))
+                            }
                         }
                     });
+                    return Task::batch(vec![local, remote]);
                 }
             }
             Message::Clear => {
                 self.query.clear();
                 self.results = None;
+                self.local_results = None;
                 self.tag_timeline = None;
             }
             Message::SetResults(results) => {
@@ -249,17 +325,24 @@ impl Search {
                     tasks.push(cosmic::task::future(async move {
                         match mastodon.get_relationships(account_ids).await {
                             Ok(response) => app::Message::CacheRelationships(response.json),
-                            Err(err) => {
-                                app::Message::Error(format!("Couldn't load relationships: {err}"))
-                            }
+                            Err(err) => app::failure("Couldn't load relationships", err),
                         }
                     }));
                 }
                 return Task::batch(tasks);
             }
-            Message::SearchFailed(err) => {
+            Message::SetLocalResults(results) => {
+                let image_urls = results.iter().flat_map(cache::extract_status_images).collect();
+                self.local_results = Some(results);
+                return cosmic::task::message(app::Message::Fetch(image_urls));
+            }
+            Message::SearchFailed(failure) => {
                 self.searching = false;
-                return cosmic::task::message(app::Message::Error(format!("Search failed: {err}")));
+                // Offline or not, what's cached can still be shown.
+                if self.local_results.is_some() {
+                    self.activate_tab(ResultKind::Local);
+                }
+                return cosmic::task::message(*failure);
             }
             Message::SelectResultKind(entity) => self.result_tabs.activate(entity),
             Message::OpenAccount(account) => {
@@ -277,7 +360,7 @@ impl Search {
                     };
                     match result {
                         Ok(response) => app::Message::CacheRelationship(response.json),
-                        Err(err) => app::Message::Error(format!("Couldn't update follow: {err}")),
+                        Err(err) => app::action_failure("Couldn't update follow", err),
                     }
                 });
             }
@@ -301,9 +384,7 @@ impl Search {
                             name,
                             response.json.following.unwrap_or(!following),
                         )),
-                        Err(err) => {
-                            app::Message::Error(format!("Couldn't update hashtag follow: {err}"))
-                        }
+                        Err(err) => app::action_failure("Couldn't update hashtag follow", err),
                     }
                 });
             }
@@ -1,25 +1,55 @@
-//! Settings context page: timeline display preferences and account switching.
+//! Settings context page: timeline display preferences, the translation
+//! service, image cache limits, blocked instances, and account switching.
+
+use std::collections::HashSet;
 
 use cosmic::{widget, Element};
 
 use crate::client::Session;
-use crate::config::{FeedDensity, ThemeMode, TootConfig};
+use crate::config::{
+    FeedDensity, ImageCacheAge, ImageCacheLimit, SensitiveMedia, ThemeMode, TimestampStyle,
+    TootConfig,
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ToggleHideBoosts(bool),
     ToggleHideReplies(bool),
+    ToggleDeckMode(bool),
+    TogglePlayAnimatedEmoji(bool),
     SetFeedDensity(FeedDensity),
+    SetSensitiveMedia(SensitiveMedia),
+    SetTimestampStyle(TimestampStyle),
+    ToggleExpandContentWarnings(bool),
+    ToggleFlagMissingAltText(bool),
+    TranslationUrlInput(String),
+    TranslationApiKeyInput(String),
+    /// Save the translation service URL or API key typed so far.
+    SaveTranslationUrl,
+    SaveTranslationApiKey,
     SetThemeMode(ThemeMode),
     SwitchAccount(usize),
     RemoveAccount(usize),
     AddAccount,
+    DomainBlockInput(String),
+    /// Block the domain typed into the blocked-domains input.
+    AddDomainBlock,
+    RemoveDomainBlock(String),
+    SetImageCacheLimit(ImageCacheLimit),
+    SetImageCacheAge(ImageCacheAge),
+    /// Delete every downloaded image from disk.
+    ClearImageCache,
 }
 
 pub fn view<'a>(
     config: &'a TootConfig,
     sessions: &'a [Session],
     active: usize,
+    blocked_domains: &'a HashSet<String>,
+    domain_block_input: &'a str,
+    translation_url: &'a str,
+    translation_api_key: &'a str,
+    image_cache_usage: Option<u64>,
 ) -> Element<'a, Message> {
     let spacing = cosmic::theme::active().cosmic().spacing;
 
@@ -31,6 +61,22 @@ pub fn view<'a>(
         .iter()
         .position(|density| *density == config.feed_density);
 
+    let sensitive_labels: Vec<&str> = SensitiveMedia::ALL
+        .iter()
+        .map(|sensitive| sensitive.label())
+        .collect();
+    let sensitive_selected = SensitiveMedia::ALL
+        .iter()
+        .position(|sensitive| *sensitive == config.sensitive_media);
+
+    let timestamp_labels: Vec<&str> = TimestampStyle::ALL
+        .iter()
+        .map(|style| style.label())
+        .collect();
+    let timestamp_selected = TimestampStyle::ALL
+        .iter()
+        .position(|style| *style == config.timestamp_style);
+
     let theme_labels: Vec<&str> = ThemeMode::ALL.iter().map(|mode| mode.label()).collect();
     let theme_selected = ThemeMode::ALL
         .iter()
@@ -46,6 +92,15 @@ pub fn view<'a>(
                 }),]
                 .spacing(spacing.space_xxs)
                 .align_x(cosmic::iced::Alignment::End),
+            ))
+            .add(widget::settings::item(
+                "Deck layout (columns side by side)",
+                widget::toggler(config.deck_mode).on_toggle(Message::ToggleDeckMode),
+            ))
+            .add(widget::settings::item(
+                "Play animated emoji",
+                widget::toggler(config.play_animated_emoji)
+                    .on_toggle(Message::TogglePlayAnimatedEmoji),
             ));
 
     let timeline_settings = widget::settings::section()
@@ -56,6 +111,28 @@ pub fn view<'a>(
                 Message::SetFeedDensity(FeedDensity::ALL[index])
             }),
         ))
+        .add(widget::settings::item(
+            "Post times",
+            widget::dropdown(timestamp_labels, timestamp_selected, |index| {
+                Message::SetTimestampStyle(TimestampStyle::ALL[index])
+            }),
+        ))
+        .add(widget::settings::item(
+            "Sensitive media",
+            widget::dropdown(sensitive_labels, sensitive_selected, |index| {
+                Message::SetSensitiveMedia(SensitiveMedia::ALL[index])
+            }),
+        ))
+        .add(widget::settings::item(
+            "Always expand content warnings",
+            widget::toggler(config.expand_content_warnings)
+                .on_toggle(Message::ToggleExpandContentWarnings),
+        ))
+        .add(widget::settings::item(
+            "Flag media without alt text",
+            widget::toggler(config.flag_missing_alt_text)
+                .on_toggle(Message::ToggleFlagMissingAltText),
+        ))
         .add(widget::settings::item(
             "Hide boosts",
             widget::toggler(config.hide_boosts).on_toggle(Message::ToggleHideBoosts),
@@ -65,6 +142,103 @@ pub fn view<'a>(
             widget::toggler(config.hide_replies).on_toggle(Message::ToggleHideReplies),
         ));
 
+    let translation_settings = widget::settings::section()
+        .title("Translation")
+        .add(widget::text::caption(
+            "Posts are translated by your server when it can. Otherwise, they're sent to this \
+             LibreTranslate-compatible service.",
+        ))
+        .add(widget::settings::item(
+            "Service URL",
+            widget::text_input("http://localhost:5000", translation_url)
+                .on_input(Message::TranslationUrlInput)
+                .on_submit(|_| Message::SaveTranslationUrl)
+                .on_unfocus(Message::SaveTranslationUrl),
+        ))
+        .add(widget::settings::item(
+            "API key",
+            widget::text_input("Optional", translation_api_key)
+                .password()
+                .on_input(Message::TranslationApiKeyInput)
+                .on_submit(|_| Message::SaveTranslationApiKey)
+                .on_unfocus(Message::SaveTranslationApiKey),
+        ));
+
+    let limit_labels: Vec<&str> = ImageCacheLimit::ALL
+        .iter()
+        .map(|limit| limit.label())
+        .collect();
+    let limit_selected = ImageCacheLimit::ALL
+        .iter()
+        .position(|limit| *limit == config.image_cache_limit);
+    let age_labels: Vec<&str> = ImageCacheAge::ALL.iter().map(|age| age.label()).collect();
+    let age_selected = ImageCacheAge::ALL
+        .iter()
+        .position(|age| *age == config.image_cache_age);
+    let usage = match image_cache_usage {
+        Some(bytes) => format!("{:.1} MB used", bytes as f64 / (1024.0 * 1024.0)),
+        None => "Calculating…".to_string(),
+    };
+
+    let storage_settings = widget::settings::section()
+        .title("Storage")
+        .add(widget::settings::item(
+            "Image cache size",
+            widget::dropdown(limit_labels, limit_selected, |index| {
+                Message::SetImageCacheLimit(ImageCacheLimit::ALL[index])
+            }),
+        ))
+        .add(widget::settings::item(
+            "Keep unviewed images for",
+            widget::dropdown(age_labels, age_selected, |index| {
+                Message::SetImageCacheAge(ImageCacheAge::ALL[index])
+            }),
+        ))
+        .add(widget::settings::item_row(vec![
+            Element::from(widget::column![
+                widget::text("Downloaded images"),
+                widget::text::caption(usage),
+            ]),
+            Element::from(widget::space::horizontal()),
+            Element::from(
+                widget::button::destructive("Clear cache").on_press_maybe(
+                    image_cache_usage
+                        .is_some_and(|bytes| bytes > 0)
+                        .then_some(Message::ClearImageCache),
+                ),
+            ),
+        ]));
+
+    let mut domains: Vec<&String> = blocked_domains.iter().collect();
+    domains.sort();
+    let domain_blocks_section = domains
+        .into_iter()
+        .fold(
+            widget::settings::section().title("Blocked domains"),
+            |section, domain| {
+                section.add(widget::settings::item_row(vec![
+                    Element::from(widget::text(domain.clone())),
+                    Element::from(widget::space::horizontal()),
+                    Element::from(
+                        widget::button::standard("Unblock")
+                            .on_press(Message::RemoveDomainBlock(domain.clone())),
+                    ),
+                ]))
+            },
+        )
+        .add(
+            widget::row![
+                widget::text_input("example.social", domain_block_input)
+                    .on_input(Message::DomainBlockInput)
+                    .on_submit(|_| Message::AddDomainBlock),
+                widget::button::destructive("Block").on_press_maybe(
+                    (!domain_block_input.trim().is_empty()).then_some(Message::AddDomainBlock)
+                ),
+            ]
+            .spacing(spacing.space_xs)
+            .align_y(cosmic::iced::Alignment::Center),
+        );
+
     let accounts_section = sessions
         .iter()
         .enumerate()
@@ -72,7 +246,10 @@ pub fn view<'a>(
             widget::settings::section().title("Accounts"),
             |section, (index, session)| {
                 let mut row: Vec<Element<'a, Message>> = vec![
-                    Element::from(widget::text(session.base_url.clone())),
+                    Element::from(widget::column![
+                        widget::text(session.base_url.clone()),
+                        widget::text::caption(session.software.name()),
+                    ]),
                     Element::from(widget::space::horizontal()),
                 ];
                 if index != active {
@@ -88,7 +265,14 @@ pub fn view<'a>(
         )
         .add(widget::button::suggested("Add account").on_press(Message::AddAccount));
 
-    widget::column![appearance_settings, timeline_settings, accounts_section]
+    widget::column![
+        appearance_settings,
+        timeline_settings,
+        translation_settings,
+        storage_settings,
+        domain_blocks_section,
+        accounts_section
+    ]
         .spacing(spacing.space_m)
         .into()
 }
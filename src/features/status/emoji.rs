@@ -0,0 +1,88 @@
+//! Custom emoji: the `:shortcode:`s in post text, display names and bios,
+//! shown as the server's images. Until an image loads (or if it fails to),
+//! the shortcode itself is shown instead.
+
+use cosmic::{widget, Element};
+use megalodon::entities::Emoji;
+
+use crate::cache::Cache;
+
+/// A run of text, or a custom emoji found in it.
+pub enum Piece<'a> {
+    Text(&'a str),
+    Emoji(&'a Emoji),
+}
+
+/// Split `text` at the shortcodes of `emojis`.
+pub fn split<'a>(text: &'a str, emojis: &'a [Emoji]) -> Vec<Piece<'a>> {
+    if emojis.is_empty() {
+        return vec![Piece::Text(text)];
+    }
+    let mut pieces = vec![];
+    let mut rest = text;
+    let mut searched = 0;
+    while let Some(start) = rest[searched..].find(':').map(|index| searched + index) {
+        let Some(len) = rest[start + 1..].find(':') else {
+            break;
+        };
+        let shortcode = &rest[start + 1..start + 1 + len];
+        let emoji = (!shortcode.contains(char::is_whitespace))
+            .then(|| emojis.iter().find(|emoji| emoji.shortcode == shortcode))
+            .flatten();
+        match emoji {
+            Some(emoji) => {
+                if start > 0 {
+                    pieces.push(Piece::Text(&rest[..start]));
+                }
+                pieces.push(Piece::Emoji(emoji));
+                rest = &rest[start + len + 2..];
+                searched = 0;
+            }
+            // The closing colon may open the next shortcode instead.
+            None => searched = start + 1 + len,
+        }
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    pieces
+}
+
+/// The images to fetch for `emojis`.
+pub fn image_urls(emojis: &[Emoji]) -> impl Iterator<Item = String> + '_ {
+    emojis.iter().map(|emoji| emoji.url.clone())
+}
+
+/// A custom emoji's image sized to sit in a line of `size`-point text, or
+/// its shortcode if the image isn't loaded.
+pub fn image<'a, M: 'a>(url: &str, shortcode: &str, cache: &'a Cache, size: u16) -> Element<'a, M> {
+    match cache.emoji_handle(url) {
+        Some(handle) => widget::image(handle).height(f32::from(size) * 1.3).into(),
+        None => widget::text(format!(":{shortcode}:")).size(size).into(),
+    }
+}
+
+/// `text` with its custom emoji shown inline, wrapping like text. Plain
+/// text when it has none.
+pub fn text<'a, M: 'a>(
+    text: &str,
+    emojis: &[Emoji],
+    cache: &'a Cache,
+    size: u16,
+) -> Element<'a, M> {
+    let pieces = split(text, emojis);
+    if let [Piece::Text(text)] = pieces.as_slice() {
+        return widget::text(text.to_string()).size(size).into();
+    }
+    let mut children: Vec<Element<'a, M>> = vec![];
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => children.extend(
+                text.split_inclusive(char::is_whitespace)
+                    .map(|word| widget::text(word.to_string()).size(size).into()),
+            ),
+            Piece::Emoji(emoji) => children.push(image(&emoji.url, &emoji.shortcode, cache, size)),
+        }
+    }
+    widget::flex_row(children).into()
+}
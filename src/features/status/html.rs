@@ -0,0 +1,418 @@
+//! Parsing of status HTML into styled, linkable text.
+//!
+//! Servers sanitize post bodies down to a small set of tags (paragraphs,
+//! line breaks, links, emphasis, code, quotes and lists), so this is a
+//! forgiving tokenizer for that subset rather than a full HTML parser.
+//! Anything else is dropped and only its text kept.
+
+use megalodon::entities::{Emoji, Mention};
+
+use super::emoji;
+
+/// Where a link in a post goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    /// An ordinary web link.
+    Url(String),
+    /// A mentioned account, by id, opened as an in-app profile.
+    Mention(String),
+    /// A hashtag, by name, opened as an in-app tag timeline.
+    Tag(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strikethrough: bool,
+    pub underline: bool,
+}
+
+/// A run of text sharing one style and link.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+    pub link: Option<Link>,
+    /// The image URL, if this span is a custom emoji. `text` then holds its
+    /// `:shortcode:`, shown until the image loads.
+    pub emoji: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Paragraph,
+    /// Preformatted text, shown as-is in a monospace font.
+    Code,
+    /// A list item, with its marker (a bullet, or its number if ordered).
+    ListItem(Option<usize>),
+}
+
+/// A paragraph-level piece of a post.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    /// How many block quotes this is nested in.
+    pub quote_depth: usize,
+    /// How many lists this is nested in.
+    pub list_depth: usize,
+    pub spans: Vec<Span>,
+}
+
+/// A parsed post body.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Content {
+    pub blocks: Vec<Block>,
+}
+
+/// Parse a post body. `mentions` are the status's mentioned accounts, used
+/// to turn mention links into in-app profile links, and `emojis` its custom
+/// emoji.
+pub fn parse(html: &str, mentions: &[Mention], emojis: &[Emoji]) -> Content {
+    let mut parser = Parser {
+        mentions,
+        emojis,
+        ..Parser::default()
+    };
+    let mut rest = html;
+    while !rest.is_empty() {
+        match rest.find('<') {
+            Some(0) => {
+                let (tag, after) = match rest.find('>') {
+                    Some(end) => (&rest[1..end], &rest[end + 1..]),
+                    None => (&rest[1..], ""),
+                };
+                parser.tag(tag);
+                rest = after;
+            }
+            Some(start) => {
+                parser.text(&decode_entities(&rest[..start]));
+                rest = &rest[start..];
+            }
+            None => {
+                parser.text(&decode_entities(rest));
+                rest = "";
+            }
+        }
+    }
+    parser.end_block();
+    Content {
+        blocks: parser.blocks,
+    }
+}
+
+#[derive(Default)]
+struct Parser<'a> {
+    mentions: &'a [Mention],
+    emojis: &'a [Emoji],
+    blocks: Vec<Block>,
+    kind: Option<BlockKind>,
+    spans: Vec<Span>,
+    bold: usize,
+    italic: usize,
+    code: usize,
+    strikethrough: usize,
+    underline: usize,
+    link: Option<Link>,
+    /// Whether each open `<span>` hides its text (Mastodon's `invisible`
+    /// class, used to shorten long links) or ends in an ellipsis.
+    span_classes: Vec<SpanClass>,
+    quote_depth: usize,
+    /// Open lists, with the next item's number if ordered.
+    lists: Vec<Option<usize>>,
+    preformatted: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SpanClass {
+    Plain,
+    Invisible,
+    Ellipsis,
+}
+
+impl Parser<'_> {
+    fn tag(&mut self, tag: &str) {
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/').trim_end_matches('/');
+        let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        let name = name.to_ascii_lowercase();
+        match (name.as_str(), closing) {
+            ("p" | "div", _) => self.end_block(),
+            ("br", _) => self.push_text("\n"),
+            ("blockquote", false) => {
+                self.end_block();
+                self.quote_depth += 1;
+            }
+            ("blockquote", true) => {
+                self.end_block();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            ("pre", false) => {
+                self.end_block();
+                self.kind = Some(BlockKind::Code);
+                self.preformatted = true;
+            }
+            ("pre", true) => {
+                self.end_block();
+                self.preformatted = false;
+            }
+            ("ul" | "ol", false) => {
+                self.end_block();
+                self.lists.push((name == "ol").then_some(1));
+            }
+            ("ul" | "ol", true) => {
+                self.end_block();
+                self.lists.pop();
+            }
+            ("li", false) => {
+                self.end_block();
+                let number = self.lists.last_mut().and_then(|next| {
+                    let number = *next;
+                    if let Some(next) = next {
+                        *next += 1;
+                    }
+                    number
+                });
+                self.kind = Some(BlockKind::ListItem(number));
+            }
+            ("li", true) => self.end_block(),
+            ("strong" | "b", false) => self.bold += 1,
+            ("strong" | "b", true) => self.bold = self.bold.saturating_sub(1),
+            ("em" | "i", false) => self.italic += 1,
+            ("em" | "i", true) => self.italic = self.italic.saturating_sub(1),
+            ("code", false) => self.code += 1,
+            ("code", true) => self.code = self.code.saturating_sub(1),
+            ("del" | "s", false) => self.strikethrough += 1,
+            ("del" | "s", true) => self.strikethrough = self.strikethrough.saturating_sub(1),
+            ("u", false) => self.underline += 1,
+            ("u", true) => self.underline = self.underline.saturating_sub(1),
+            ("a", false) => self.link = self.link_for(attributes),
+            ("a", true) => self.link = None,
+            ("span", false) => {
+                let classes = attribute(attributes, "class").unwrap_or_default();
+                let class = if has_class(&classes, "invisible") {
+                    SpanClass::Invisible
+                } else if has_class(&classes, "ellipsis") {
+                    SpanClass::Ellipsis
+                } else {
+                    SpanClass::Plain
+                };
+                self.span_classes.push(class);
+            }
+            ("span", true) => {
+                if self.span_classes.pop() == Some(SpanClass::Ellipsis) {
+                    self.push_text("…");
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn link_for(&self, attributes: &str) -> Option<Link> {
+        let href = attribute(attributes, "href")?;
+        let classes = attribute(attributes, "class").unwrap_or_default();
+        let rel = attribute(attributes, "rel").unwrap_or_default();
+        if has_class(&classes, "hashtag") || has_class(&rel, "tag") {
+            if let Some((_, name)) = href.rsplit_once("/tags/") {
+                return Some(Link::Tag(percent_decode(name)));
+            }
+        }
+        if has_class(&classes, "mention") {
+            if let Some(mention) = self.mentions.iter().find(|mention| mention.url == href) {
+                return Some(Link::Mention(mention.id.clone()));
+            }
+        }
+        Some(Link::Url(href))
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.preformatted {
+            self.push_text(text);
+            return;
+        }
+        // Outside <pre>, runs of whitespace (including source newlines)
+        // render as a single space, like in a browser.
+        let mut collapsed = String::with_capacity(text.len());
+        for c in text.chars() {
+            if c.is_whitespace() && c != '\u{a0}' {
+                if !collapsed.ends_with(' ') {
+                    collapsed.push(' ');
+                }
+            } else {
+                collapsed.push(c);
+            }
+        }
+        self.push_text(&collapsed);
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() || self.span_classes.contains(&SpanClass::Invisible) {
+            return;
+        }
+        for piece in emoji::split(text, self.emojis) {
+            match piece {
+                emoji::Piece::Text(text) => self.push_run(text, None),
+                emoji::Piece::Emoji(emoji) => {
+                    self.push_run(&format!(":{}:", emoji.shortcode), Some(emoji.url.clone()))
+                }
+            }
+        }
+    }
+
+    fn push_run(&mut self, text: &str, emoji: Option<String>) {
+        let style = Style {
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            code: self.code > 0,
+            strikethrough: self.strikethrough > 0,
+            underline: self.underline > 0,
+        };
+        match self.spans.last_mut() {
+            Some(last)
+                if emoji.is_none()
+                    && last.emoji.is_none()
+                    && last.style == style
+                    && last.link == self.link =>
+            {
+                last.text.push_str(text)
+            }
+            _ => self.spans.push(Span {
+                text: text.to_string(),
+                style,
+                link: self.link.clone(),
+                emoji,
+            }),
+        }
+    }
+
+    fn end_block(&mut self) {
+        let kind = self.kind.take().unwrap_or(BlockKind::Paragraph);
+        let mut spans = std::mem::take(&mut self.spans);
+        if kind != BlockKind::Code {
+            if let Some(first) = spans.first_mut() {
+                first.text = first.text.trim_start().to_string();
+            }
+            if let Some(last) = spans.last_mut() {
+                last.text = last.text.trim_end().to_string();
+            }
+            spans.retain(|span| !span.text.is_empty());
+        }
+        if spans.iter().all(|span| span.text.trim().is_empty()) {
+            return;
+        }
+        self.blocks.push(Block {
+            kind,
+            quote_depth: self.quote_depth,
+            list_depth: self.lists.len(),
+            spans,
+        });
+    }
+}
+
+/// The value of `name="..."` in a tag's attributes, with entities decoded.
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(index) = rest.find(name) {
+        let preceded_by_space = rest[..index]
+            .chars()
+            .last()
+            .map_or(true, char::is_whitespace);
+        let after = rest[index + name.len()..].trim_start();
+        if preceded_by_space {
+            if let Some(value) = after.strip_prefix('=') {
+                let value = value.trim_start();
+                let (quote, value) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => (Some(quote), &value[1..]),
+                    _ => (None, value),
+                };
+                let end = match quote {
+                    Some(quote) => value.find(quote),
+                    None => value.find(char::is_whitespace),
+                }
+                .unwrap_or(value.len());
+                return Some(decode_entities(&value[..end]));
+            }
+        }
+        rest = &rest[index + name.len()..];
+    }
+    None
+}
+
+fn has_class(classes: &str, class: &str) -> bool {
+    classes
+        .split_whitespace()
+        .any(|candidate| candidate == class)
+}
+
+/// Decode a URL path segment, so non-ASCII hashtags get their real name.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| segment.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decode the character references that show up in sanitized post HTML.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
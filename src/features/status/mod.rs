@@ -1,16 +1,26 @@
+pub mod emoji;
+pub mod html;
+pub mod timestamp;
+pub mod translate;
+
+use std::borrow::Cow;
+
 use cosmic::{
     app::Task,
     iced::widget::scrollable::{Direction, Scrollbar},
-    iced::{mouse::Interaction, Alignment, Length},
-    widget, Apply, Element,
+    iced::widget::{rich_text, span, text::Span},
+    iced::{self, font, mouse::Interaction, Alignment, Color, ContentFit, Font, Length, Padding},
+    widget::{self, image::Handle},
+    Apply, Element,
 };
-use megalodon::entities::{Account, Status};
+use megalodon::entities::{status::StatusVisibility, Account, Status};
 
 use crate::{
     app,
     cache::{self, Cache},
     config::FeedDensity,
     features::compose,
+    fl,
 };
 
 #[derive(Debug, Clone)]
@@ -21,14 +31,43 @@ pub enum Message {
     Favorite(String, bool),
     Boost(String, bool),
     Bookmark(String, bool),
+    /// Open a link from a post or its preview card. Links to other posts
+    /// and profiles open in the app.
     OpenLink(String),
-    /// Show an image attachment in an overlay: (cached preview URL, original URL).
-    ViewImage(String, String),
+    /// Open a mentioned account's profile, by id.
+    OpenMention(String),
+    /// Open a hashtag's timeline, by name.
+    OpenTag(String),
+    /// Open a status's media in the viewer: (status id, index of the
+    /// attachment clicked).
+    ViewMedia(String, usize),
+    /// Expand (`true`) or collapse a post behind a content warning: (status
+    /// id, shown).
+    ShowContent(String, bool),
+    /// Uncover (`true`) or cover again (`false`) a status's media: (status
+    /// id, shown).
+    ShowMedia(String, bool),
+    /// Show a status translated (`true`) or in its own language: (status
+    /// id, translated).
+    Translate(String, bool),
+    /// Reveal (`true`) or hide an attachment's alt text: (attachment id,
+    /// shown).
+    ShowAltText(String, bool),
     /// Request to delete one of the authenticated user's own statuses;
     /// opens a confirmation dialog rather than deleting immediately.
     Delete(String),
+    /// Add (`false`) or remove (`true`) an emoji reaction: (status id,
+    /// emoji, already reacted). Only offered on servers with reactions.
+    React(String, String, bool),
 }
 
+/// How many levels of quotes and lists are indented; deeper ones line up
+/// with the last, so hostile nesting can't push text off the page.
+const MAX_INDENT_DEPTH: usize = 8;
+
+/// Emoji offered by the "add reaction" picker.
+const REACTIONS: [&str; 6] = ["👍", "❤️", "😆", "😮", "😢", "🎉"];
+
 #[derive(Debug, Copy, Clone)]
 pub struct StatusOptions {
     media: bool,
@@ -70,14 +109,18 @@ pub fn status<'a>(
         .unwrap_or(status);
 
     let density = cache.feed_density;
+    let shown = cache.is_content_shown(status);
 
     widget::column![
         reblog_button,
         header(status, cache, density),
-        content(status, options),
-        card(status, cache, density),
-        media(status, cache, options, density),
-        tags(status, options),
+        content_warning(status, cache),
+        shown.then(|| content(status, options, cache)),
+        translation(status, cache).filter(|_| shown),
+        card(status, cache, density).filter(|_| shown),
+        media(status, cache, options, density).filter(|_| shown),
+        tags(status, options).filter(|_| shown),
+        reactions(status, options, cache),
         actions(status, options, cache),
     ]
     .padding(spacing.space_xs)
@@ -86,6 +129,77 @@ pub fn status<'a>(
     .into()
 }
 
+/// A post's content warning, with a button to show or hide what's behind it.
+fn content_warning<'a>(status: &'a Status, cache: &'a Cache) -> Option<Element<'a, Message>> {
+    if status.spoiler_text.is_empty() {
+        return None;
+    }
+    let spacing = cosmic::theme::active().cosmic().spacing;
+    let shown = cache.is_content_shown(status);
+    let spoiler_text = match cache.translation(status) {
+        Some(translation) => &translation.spoiler_text,
+        None => &status.spoiler_text,
+    };
+
+    let warning = widget::row![
+        widget::icon::from_name("dialog-warning-symbolic")
+            .size(16)
+            .icon(),
+        emoji::text(spoiler_text, &status.emojis, cache, EMOJI_TEXT_SIZE),
+    ]
+    .align_y(Alignment::Center)
+    .spacing(spacing.space_xs)
+    .width(Length::Fill);
+    let toggle = widget::button::standard(if shown {
+        fl!("show-less")
+    } else {
+        fl!("show-more")
+    })
+    .on_press(Message::ShowContent(status.id.clone(), !shown));
+
+    let warning = widget::row![warning, toggle]
+        .align_y(Alignment::Center)
+        .spacing(spacing.space_xs)
+        .apply(widget::container)
+        .padding(spacing.space_xs)
+        .class(cosmic::style::Container::Card);
+    Some(warning.into())
+}
+
+/// A post's Translate / Show original toggle, for posts in another language
+/// than the UI's.
+fn translation<'a>(status: &'a Status, cache: &'a Cache) -> Option<Element<'a, Message>> {
+    if !cache.can_translate(status) {
+        return None;
+    }
+    let spacing = cosmic::theme::active().cosmic().spacing;
+
+    let toggle: Element<_> = match cache.translation(status) {
+        Some(translation) => {
+            let provider = translation.provider.as_str();
+            let label = match translation.source_language.as_deref() {
+                Some(language) => fl!("translated-from", language = language, provider = provider),
+                None => fl!("translated", provider = provider),
+            };
+            widget::row![
+                widget::text::caption(label),
+                widget::button::link(fl!("show-original"))
+                    .on_press(Message::Translate(status.id.clone(), false)),
+            ]
+            .align_y(Alignment::Center)
+            .spacing(spacing.space_xs)
+            .into()
+        }
+        None if cache.translating.contains(&status.id) => {
+            widget::text::caption(fl!("translating")).into()
+        }
+        None => widget::button::link(fl!("translate"))
+            .on_press(Message::Translate(status.id.clone(), true))
+            .into(),
+    };
+    Some(toggle)
+}
+
 fn card<'a>(
     status: &'a Status,
     cache: &'a Cache,
@@ -168,18 +282,89 @@ pub fn update(message: Message) -> Task<app::Message> {
         Message::Bookmark(status_id, bookmarked) => cosmic::task::message(app::Message::Status(
             Message::Bookmark(status_id, bookmarked),
         )),
-        Message::OpenLink(url) => cosmic::task::message(app::Message::Open(url.to_string())),
-        Message::ViewImage(preview_url, original_url) => {
-            cosmic::task::message(app::Message::Dialog(app::DialogAction::Open(
-                app::Dialog::Image(preview_url, original_url),
-            )))
+        Message::OpenLink(url) => cosmic::task::message(app::Message::OpenLink(url)),
+        Message::OpenMention(id) => cosmic::task::message(app::Message::OpenMention(id)),
+        Message::OpenTag(name) => cosmic::task::message(app::Message::OpenTag(name)),
+        Message::ViewMedia(status_id, index) => {
+            cosmic::task::message(app::Message::ViewMedia(status_id, index))
+        }
+        Message::ShowContent(status_id, shown) => {
+            cosmic::task::message(app::Message::Status(Message::ShowContent(status_id, shown)))
+        }
+        Message::ShowMedia(status_id, shown) => {
+            cosmic::task::message(app::Message::Status(Message::ShowMedia(status_id, shown)))
+        }
+        Message::Translate(status_id, translated) => cosmic::task::message(app::Message::Status(
+            Message::Translate(status_id, translated),
+        )),
+        Message::ShowAltText(media_id, shown) => {
+            cosmic::task::message(app::Message::Status(Message::ShowAltText(media_id, shown)))
         }
         Message::Delete(status_id) => cosmic::task::message(app::Message::Dialog(
             app::DialogAction::Open(app::Dialog::DeleteStatus(status_id)),
         )),
+        Message::React(status_id, emoji, reacted) => cosmic::task::message(app::Message::Status(
+            Message::React(status_id, emoji, reacted),
+        )),
     }
 }
 
+fn reactions<'a>(
+    status: &'a Status,
+    options: StatusOptions,
+    cache: &'a Cache,
+) -> Option<Element<'a, Message>> {
+    if !options.actions || !cache.software.supports_reactions() {
+        return None;
+    }
+    let spacing = cosmic::theme::active().cosmic().spacing;
+
+    let mut reactions: Vec<Element<Message>> = status
+        .emoji_reactions
+        .iter()
+        .flatten()
+        .map(|reaction| {
+            let image = reaction
+                .url
+                .as_ref()
+                .and_then(|url| cache.emoji_handle(url));
+            let label: Element<_> = match image {
+                Some(handle) => widget::row![
+                    widget::image(handle).height(f32::from(EMOJI_TEXT_SIZE) * 1.3),
+                    widget::text(reaction.count.to_string()),
+                ]
+                .align_y(Alignment::Center)
+                .spacing(spacing.space_xxs)
+                .into(),
+                None => widget::text(format!("{} {}", reaction.name, reaction.count)).into(),
+            };
+            widget::button::custom(label)
+                .class(if reaction.me {
+                    cosmic::theme::Button::Suggested
+                } else {
+                    cosmic::theme::Button::Standard
+                })
+                .on_press(Message::React(
+                    status.id.clone(),
+                    reaction.name.clone(),
+                    reaction.me,
+                ))
+                .into()
+        })
+        .collect();
+
+    let id = status.id.clone();
+    reactions.push(
+        widget::dropdown(&REACTIONS, None, move |index| {
+            Message::React(id.clone(), REACTIONS[index].to_string(), false)
+        })
+        .placeholder("React")
+        .into(),
+    );
+
+    Some(widget::flex_row(reactions).spacing(spacing.space_xxs).into())
+}
+
 fn actions<'a>(
     status: &'a Status,
     options: StatusOptions,
@@ -270,52 +455,174 @@ fn media<'a>(
     cache: &'a Cache,
     options: StatusOptions,
     density: FeedDensity,
-) -> Option<cosmic::iced::widget::Scrollable<'a, Message, cosmic::Theme>> {
-    if density == FeedDensity::TextOnly {
+) -> Option<Element<'a, Message>> {
+    if density == FeedDensity::TextOnly || status.media_attachments.is_empty() || !options.media {
         return None;
     }
     let compact = density == FeedDensity::Compact;
+    let covers = cache.covers_media(status);
+    let covered = covers && !cache.shown_media.contains(&status.id);
 
     let spacing = cosmic::theme::active().cosmic().spacing;
 
     let attachments = status
         .media_attachments
         .iter()
-        .map(|media| {
-            let is_picture = matches!(
-                media.r#type,
-                megalodon::entities::attachment::AttachmentType::Image
-                    | megalodon::entities::attachment::AttachmentType::Gifv
-            );
-            let on_press = match &media.preview_url {
-                Some(preview_url) if is_picture => {
-                    Message::ViewImage(preview_url.clone(), media.url.clone())
-                }
-                _ => Message::OpenLink(media.url.clone()),
-            };
-
-            let button = widget::button::image(
-                media
-                    .preview_url
-                    .as_ref()
-                    .and_then(|url| cache.handles.get(url))
-                    .cloned()
-                    .unwrap_or(crate::cache::fallback_handle()),
-            )
-            .on_press(on_press);
-            if compact {
-                button.width(80.0).height(80.0).into()
+        .enumerate()
+        .map(|(index, media)| {
+            let preview = media
+                .preview_url
+                .as_ref()
+                .and_then(|url| cache.handles.get(url))
+                .filter(|_| !covered);
+            let size = if compact {
+                Some((80.0, 80.0))
             } else {
-                button.into()
+                placeholder_size(media)
+            };
+            let view = (!covered).then(|| Message::ViewMedia(status.id.clone(), index));
+            let thumbnail: Element<_> = match preview {
+                Some(handle) if compact => widget::image(handle.clone())
+                    .content_fit(ContentFit::Cover)
+                    .width(80.0)
+                    .height(80.0)
+                    .into(),
+                Some(handle) => widget::image(handle.clone()).into(),
+                None => placeholder(cache.blurhash(media), size),
+            };
+            let button = widget::button::custom(thumbnail)
+                .padding(0)
+                .class(cosmic::style::Button::Image)
+                .on_press_maybe(view);
+            // Covered media keeps its description to itself too.
+            if covered {
+                return button.into();
             }
+            let button = describe(button, alt_text(media));
+
+            let badge: Element<_> = match alt_text(media) {
+                Some(_) => {
+                    let shown = cache.shown_alt_text.contains(&media.id);
+                    widget::button::custom(widget::text::caption(fl!("alt-badge")))
+                        .padding([2, 6])
+                        .class(cosmic::style::Button::Standard)
+                        .on_press(Message::ShowAltText(media.id.clone(), !shown))
+                        .into()
+                }
+                None if cache.flag_missing_alt_text => {
+                    widget::container(widget::text::caption(fl!("no-alt-badge")))
+                        .padding([2, 6])
+                        .class(cosmic::style::Container::Card)
+                        .into()
+                }
+                None => return button.into(),
+            };
+            let badge = widget::container(badge)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_y(iced::alignment::Vertical::Bottom)
+                .padding(spacing.space_xxs);
+            iced::widget::stack![button, badge].into()
         })
         .collect::<Vec<Element<Message>>>();
-
-    let media = (!status.media_attachments.is_empty() && options.media).then_some({
-        widget::scrollable(widget::row(attachments).spacing(spacing.space_xxs))
-            .direction(Direction::Horizontal(Scrollbar::new()))
+    let attachments = widget::scrollable(widget::row(attachments).spacing(spacing.space_xxs))
+        .direction(Direction::Horizontal(Scrollbar::new()));
+    // Alt text the user revealed, under the media it describes.
+    let alt_texts = (!covered).then(|| {
+        status
+            .media_attachments
+            .iter()
+            .filter(|media| cache.shown_alt_text.contains(&media.id))
+            .filter_map(alt_text)
+            .map(|description| widget::text::caption(description).into())
+            .collect::<Vec<Element<Message>>>()
     });
+    let attachments: Element<_> = match alt_texts {
+        Some(alt_texts) if !alt_texts.is_empty() => widget::column![attachments]
+            .extend(alt_texts)
+            .spacing(spacing.space_xxs)
+            .into(),
+        _ => attachments.into(),
+    };
+
+    let media = if covered {
+        let label = if status.sensitive {
+            fl!("show-sensitive-media")
+        } else {
+            fl!("show-hidden-media")
+        };
+        let show = widget::button::standard(label)
+            .on_press(Message::ShowMedia(status.id.clone(), true))
+            .apply(widget::container)
+            .center(Length::Fill);
+        iced::widget::stack![attachments, show].into()
+    } else if covers {
+        widget::column![
+            widget::button::text(fl!("hide-media"))
+                .on_press(Message::ShowMedia(status.id.clone(), false)),
+            attachments,
+        ]
+        .spacing(spacing.space_xxs)
+        .into()
+    } else {
+        attachments.into()
+    };
+    Some(media)
+}
+
+/// An attachment's alt text, if it has any.
+fn alt_text(media: &megalodon::entities::Attachment) -> Option<&str> {
     media
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+}
+
+/// Give screen readers an attachment's alt text as the thumbnail's name.
+#[cfg(target_os = "linux")]
+fn describe<'a>(
+    button: widget::Button<'a, Message>,
+    alt_text: Option<&'a str>,
+) -> widget::Button<'a, Message> {
+    match alt_text {
+        Some(alt_text) => button.name(alt_text),
+        None => button.name(fl!("media-without-alt-text")),
+    }
+}
+
+/// Accessibility support is only built on Linux.
+#[cfg(not(target_os = "linux"))]
+fn describe<'a>(
+    button: widget::Button<'a, Message>,
+    _alt_text: Option<&'a str>,
+) -> widget::Button<'a, Message> {
+    button
+}
+
+/// How big to draw an attachment that isn't shown: the preview's size, when
+/// the server reports it.
+fn placeholder_size(media: &megalodon::entities::Attachment) -> Option<(f32, f32)> {
+    let small = media.meta.as_ref()?.small.as_ref()?;
+    Some((small.width? as f32, small.height? as f32))
+}
+
+/// Stands in for an attachment whose preview is loading or covered: its
+/// blurhash, or a blank card when it has none.
+fn placeholder<'a>(blurhash: Option<&Handle>, size: Option<(f32, f32)>) -> Element<'a, Message> {
+    let (width, height) = size.unwrap_or((200.0, 150.0));
+    match blurhash {
+        Some(handle) => widget::image(handle.clone())
+            .content_fit(ContentFit::Fill)
+            .width(width)
+            .height(height)
+            .into(),
+        None => widget::container(widget::space::horizontal())
+            .width(width)
+            .height(height)
+            .class(cosmic::style::Container::Card)
+            .into(),
+    }
 }
 
 fn tags(status: &Status, options: StatusOptions) -> Option<Element<'_, Message>> {
@@ -327,7 +634,7 @@ fn tags(status: &Status, options: StatusOptions) -> Option<Element<'_, Message>>
             .iter()
             .map(|tag| {
                 widget::button::suggested(format!("#{}", tag.name.clone()))
-                    .on_press(Message::OpenLink(tag.url.clone()))
+                    .on_press(Message::OpenTag(tag.name.clone()))
                     .into()
             })
             .collect::<Vec<Element<Message>>>();
@@ -360,29 +667,100 @@ fn header<'a>(
         .height(avatar_size)
         .on_press(Message::OpenAccount(status.account.clone())),
         widget::column![]
-            .push_maybe(
-                (!status.account.display_name.is_empty())
-                    .then(|| { widget::text(status.account.display_name.clone()).size(18) })
-            )
+            .push_maybe((!status.account.display_name.is_empty()).then(|| {
+                emoji::text(
+                    &status.account.display_name,
+                    &status.account.emojis,
+                    cache,
+                    18,
+                )
+            }))
             .push(
                 widget::button::link(format!("@{}", status.account.username.clone()))
                     .on_press(Message::OpenAccount(status.account.clone())),
             )
             .align_x(Alignment::Center)
             .spacing(spacing.space_xs),
+        widget::space::horizontal(),
+        posted(status, cache, density),
     ]
     .align_y(Alignment::Center)
     .spacing(spacing.space_xs);
     header
 }
 
-fn content(status: &Status, options: StatusOptions) -> Element<'_, Message> {
-    let mut status_text: Element<_> = widget::text(
-        html2text::config::rich()
-            .string_from_read(status.content.as_bytes(), 700)
-            .unwrap(),
-    )
-    .into();
+/// When and how a post was made: its visibility, its time (in full on
+/// hover), whether it was edited since, and the app it was posted with.
+fn posted<'a>(status: &'a Status, cache: &'a Cache, density: FeedDensity) -> Element<'a, Message> {
+    let spacing = cosmic::theme::active().cosmic().spacing;
+
+    let visibility = widget::tooltip(
+        widget::icon::from_name(visibility_icon(&status.visibility))
+            .size(16)
+            .icon(),
+        widget::text(compose::visibility_label(&status.visibility)),
+        widget::tooltip::Position::Bottom,
+    );
+    let time = widget::tooltip(
+        widget::text::caption(timestamp::short(
+            status.created_at,
+            cache.now,
+            cache.timestamp_style,
+        )),
+        widget::text(timestamp::full(status.created_at)),
+        widget::tooltip::Position::Bottom,
+    );
+    let edited = status.edited_at.map(|edited_at| {
+        widget::tooltip(
+            widget::text::caption(fl!("edited")),
+            widget::text(fl!("edited-at", time = timestamp::full(edited_at))),
+            widget::tooltip::Position::Bottom,
+        )
+    });
+    let application = status
+        .application
+        .as_ref()
+        .filter(|_| density == FeedDensity::Full)
+        .map(|application| {
+            widget::text::caption(fl!("posted-via", application = application.name.as_str()))
+        });
+
+    let time = widget::row![visibility, time]
+        .push_maybe(edited)
+        .align_y(Alignment::Center)
+        .spacing(spacing.space_xxs);
+    widget::column![time]
+        .push_maybe(application)
+        .align_x(Alignment::End)
+        .into()
+}
+
+fn visibility_icon(visibility: &StatusVisibility) -> &'static str {
+    match visibility {
+        StatusVisibility::Public => "web-browser-symbolic",
+        StatusVisibility::Unlisted => "view-conceal-symbolic",
+        StatusVisibility::Private => "system-lock-screen-symbolic",
+        StatusVisibility::Direct => "mail-unread-symbolic",
+        StatusVisibility::Local => "user-home-symbolic",
+    }
+}
+
+fn content<'a>(
+    status: &'a Status,
+    options: StatusOptions,
+    cache: &'a Cache,
+) -> Element<'a, Message> {
+    let spacing = cosmic::theme::active().cosmic().spacing;
+    let content = match cache.translation(status) {
+        Some(translation) => Cow::Borrowed(&translation.content),
+        None => cache.content(status),
+    };
+    let blocks: Vec<Element<_>> = content
+        .blocks
+        .iter()
+        .map(|content| block(content, cache))
+        .collect();
+    let mut status_text: Element<_> = widget::column(blocks).spacing(spacing.space_xs).into();
 
     if options.expand {
         status_text = widget::MouseArea::new(status_text)
@@ -393,6 +771,120 @@ fn content(status: &Status, options: StatusOptions) -> Element<'_, Message> {
     status_text
 }
 
+/// One paragraph, list item, quote or code block of a post, with clickable
+/// links, mentions and hashtags.
+fn block<'a>(block: &html::Block, cache: &'a Cache) -> Element<'a, Message> {
+    let spacing = cosmic::theme::active().cosmic().spacing;
+    let code = block.kind == html::BlockKind::Code;
+    let marker = match block.kind {
+        html::BlockKind::ListItem(Some(number)) => Some(format!("{number}. ")),
+        html::BlockKind::ListItem(None) => Some("• ".to_string()),
+        _ => None,
+    };
+
+    let text: Element<_> = if block.spans.iter().any(|text| text.emoji.is_some()) {
+        // Rich text can't hold images, so lay custom emoji out between the
+        // words instead, one row per line.
+        let mut lines: Vec<Vec<Element<'a, Message>>> = vec![vec![]];
+        if let Some(marker) = marker {
+            lines[0].push(widget::text(marker).into());
+        }
+        for text in &block.spans {
+            if let Some(url) = &text.emoji {
+                let shortcode = text.text.trim_matches(':');
+                lines.last_mut().unwrap().push(emoji::image(
+                    url,
+                    shortcode,
+                    cache,
+                    EMOJI_TEXT_SIZE,
+                ));
+                continue;
+            }
+            for (index, line) in text.text.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(vec![]);
+                }
+                lines.last_mut().unwrap().extend(
+                    line.split_inclusive(char::is_whitespace)
+                        .map(|word| linked_text(vec![styled_span(word, text, code)])),
+                );
+            }
+        }
+        widget::column(lines.into_iter().map(|line| widget::flex_row(line).into())).into()
+    } else {
+        let mut spans: Vec<Span<'a, html::Link, Font>> = marker.into_iter().map(span).collect();
+        spans.extend(
+            block
+                .spans
+                .iter()
+                .map(|text| styled_span(&text.text, text, code)),
+        );
+        linked_text(spans)
+    };
+
+    let depth = (block.quote_depth + block.list_depth).min(MAX_INDENT_DEPTH);
+    let indent = depth as f32 * f32::from(spacing.space_m);
+    if code {
+        widget::container(text)
+            .padding(spacing.space_xs)
+            .width(Length::Fill)
+            .class(cosmic::style::Container::Card)
+            .apply(widget::container)
+            .padding(Padding {
+                left: indent,
+                ..Padding::ZERO
+            })
+            .into()
+    } else {
+        widget::container(text)
+            .padding(Padding {
+                left: indent,
+                ..Padding::ZERO
+            })
+            .into()
+    }
+}
+
+/// Size of post text, which inline custom emoji are scaled to match.
+const EMOJI_TEXT_SIZE: u16 = 14;
+
+/// `text`, styled like the post span it comes from.
+fn styled_span<'a>(text: &str, from: &html::Span, code_block: bool) -> Span<'a, html::Link, Font> {
+    let mut font = if from.style.code || code_block {
+        cosmic::font::mono()
+    } else {
+        cosmic::font::default()
+    };
+    if from.style.bold {
+        font.weight = font::Weight::Bold;
+    }
+    if from.style.italic {
+        font.style = font::Style::Italic;
+    }
+    let styled = span(text.to_string())
+        .font(font)
+        .underline(from.style.underline)
+        .strikethrough(from.style.strikethrough);
+    match &from.link {
+        Some(link) => {
+            let link_color = Color::from(cosmic::theme::active().cosmic().accent_text_color());
+            styled.link(link.clone()).color(link_color)
+        }
+        None => styled,
+    }
+}
+
+/// Rich text whose links open in the app where they can.
+fn linked_text<'a>(spans: Vec<Span<'a, html::Link, Font>>) -> Element<'a, Message> {
+    rich_text(spans)
+        .on_link_click(|link| match link {
+            html::Link::Url(url) => Message::OpenLink(url),
+            html::Link::Mention(id) => Message::OpenMention(id),
+            html::Link::Tag(name) => Message::OpenTag(name),
+        })
+        .into()
+}
+
 fn reblog_button<'a>(cache: &'a Cache, status: &'a Status) -> Option<widget::Button<'a, Message>> {
     let spacing = cosmic::theme::active().cosmic().spacing;
 
@@ -404,7 +896,12 @@ fn reblog_button<'a>(cache: &'a Cache, status: &'a Status) -> Option<widget::But
                     .get(&status.account.avatar)
                     .map(|avatar| widget::image(avatar).width(20).height(20))
                     .unwrap_or(crate::cache::fallback_avatar().width(20).height(20)),
-                widget::text(format!("{} boosted", status.account.display_name)),
+                emoji::text(
+                    &format!("{} boosted", status.account.display_name),
+                    &status.account.emojis,
+                    cache,
+                    EMOJI_TEXT_SIZE,
+                ),
             ]
             .spacing(spacing.space_xs),
         )
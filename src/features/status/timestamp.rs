@@ -0,0 +1,74 @@
+//! When a post was made, as shown in its header: compact relative times
+//! ("5m", "2h", "3d") or the local date and time, in the UI language.
+
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Datelike, Local, Locale, Utc};
+use i18n_embed::LanguageLoader;
+
+use crate::{config::TimestampStyle, fl, i18n::LANGUAGE_LOADER};
+
+/// chrono's locale for the UI language, for month and weekday names: the
+/// requested region's, else the language's home country's (`nl` as
+/// `nl_NL`), else POSIX, i.e. English.
+static LOCALE: LazyLock<Locale> = LazyLock::new(|| {
+    let language = LANGUAGE_LOADER.current_language();
+    let code = language.language.as_str();
+    let country = match code {
+        "en" => "US".to_string(),
+        "sv" => "SE".to_string(),
+        _ => code.to_uppercase(),
+    };
+    let requested = language.region.map(|region| format!("{code}_{region}"));
+    requested
+        .into_iter()
+        .chain([format!("{code}_{country}")])
+        .find_map(|name| Locale::try_from(name.as_str()).ok())
+        .unwrap_or(Locale::POSIX)
+});
+
+/// `time` in the header's style. Relative times count from `now`, so they
+/// move on as the caller refreshes it.
+pub fn short(time: DateTime<Utc>, now: DateTime<Utc>, style: TimestampStyle) -> String {
+    match style {
+        TimestampStyle::Relative => relative(time, now),
+        TimestampStyle::Absolute => format(time, &fl!("timestamp-date-time")),
+    }
+}
+
+/// `time` in full, in local time, e.g. "Monday, March 3, 2025 at 14:05".
+pub fn full(time: DateTime<Utc>) -> String {
+    format(time, &fl!("timestamp-full"))
+}
+
+/// How long ago `time` was, down to the minute, for the past week; the date
+/// after that.
+fn relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(time);
+    if elapsed.num_minutes() < 1 {
+        fl!("timestamp-now")
+    } else if elapsed.num_hours() < 1 {
+        fl!("timestamp-minutes", count = elapsed.num_minutes())
+    } else if elapsed.num_days() < 1 {
+        fl!("timestamp-hours", count = elapsed.num_hours())
+    } else if elapsed.num_days() < 7 {
+        fl!("timestamp-days", count = elapsed.num_days())
+    } else if time.with_timezone(&Local).year() == now.with_timezone(&Local).year() {
+        format(time, &fl!("timestamp-date"))
+    } else {
+        format(time, &fl!("timestamp-date-year"))
+    }
+}
+
+/// `time` in local time, by a translation's chrono `format` string and with
+/// the UI language's month and weekday names. A malformed format falls back
+/// to RFC 3339 rather than failing the view.
+fn format(time: DateTime<Utc>, format: &str) -> String {
+    let local = time.with_timezone(&Local);
+    let mut formatted = String::new();
+    match write!(formatted, "{}", local.format_localized(format, *LOCALE)) {
+        Ok(()) => formatted,
+        Err(_) => local.to_rfc3339(),
+    }
+}
@@ -0,0 +1,169 @@
+//! Translating posts into the UI language: with the instance's translation
+//! service when it has one, or else a LibreTranslate-compatible service
+//! configured in the settings (which can run locally).
+
+use megalodon::entities::Status;
+
+use super::html;
+use crate::client::Client;
+use crate::error::{self, Error};
+
+/// A status's text in another language.
+#[derive(Debug, Clone)]
+pub struct Translation {
+    pub content: html::Content,
+    /// The translated content warning; empty if the status has none.
+    pub spoiler_text: String,
+    /// The language translated from, if known.
+    pub source_language: Option<String>,
+    /// Who translated it, e.g. "DeepL.com".
+    pub provider: String,
+}
+
+/// A LibreTranslate-compatible translation service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    /// The service's address, e.g. `http://localhost:5000`.
+    pub url: String,
+    pub api_key: String,
+}
+
+impl Service {
+    /// The service set up in the settings, if any.
+    pub fn configured(url: &str, api_key: &str) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        (!url.is_empty()).then(|| Self {
+            url: url.to_string(),
+            api_key: api_key.trim().to_string(),
+        })
+    }
+}
+
+/// Whether `language` is a different language from `target`, ignoring
+/// region and script (`en-GB` is still English).
+pub fn is_foreign(language: &str, target: &str) -> bool {
+    let primary = |language: &str| {
+        language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase()
+    };
+    !language.is_empty() && primary(language) != primary(target)
+}
+
+/// Translate `status` into `language`, asking the instance first when it
+/// can translate (`server`) and falling back to `service` if it can't.
+pub async fn translate(
+    mastodon: Client,
+    status: Status,
+    language: String,
+    server: bool,
+    service: Option<Service>,
+) -> Result<Translation, Error> {
+    if mastodon.is_authenticated() && server {
+        match mastodon.translate_status(&status.id, &language).await {
+            Ok(translation) => {
+                return Ok(Translation {
+                    content: html::parse(&translation.content, &status.mentions, &status.emojis),
+                    spoiler_text: translation.spoiler_text,
+                    source_language: translation
+                        .detected_source_language
+                        .or_else(|| status.language.clone()),
+                    provider: translation.provider.unwrap_or_else(|| instance(&mastodon)),
+                })
+            }
+            Err(err) if service.is_some() => {
+                tracing::debug!("instance couldn't translate, trying the fallback: {err}");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    let Some(service) = service else {
+        return Err(Error::Validation(
+            "Your server can't translate posts; set up a translation service in the settings"
+                .to_string(),
+        ));
+    };
+
+    let source = status.language.clone();
+    let content = libretranslate(
+        &service,
+        &status.content,
+        source.as_deref(),
+        &language,
+        true,
+    );
+    let spoiler_text = async {
+        if status.spoiler_text.is_empty() {
+            return Ok(String::new());
+        }
+        libretranslate(
+            &service,
+            &status.spoiler_text,
+            source.as_deref(),
+            &language,
+            false,
+        )
+        .await
+    };
+    let (content, spoiler_text) = futures_util::try_join!(content, spoiler_text)?;
+    Ok(Translation {
+        content: html::parse(&content, &status.mentions, &status.emojis),
+        spoiler_text,
+        source_language: source,
+        provider: url::Url::parse(&service.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or(service.url),
+    })
+}
+
+/// The instance's domain, naming it as the translator when it doesn't say
+/// which service it used.
+fn instance(mastodon: &Client) -> String {
+    mastodon
+        .base_url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .to_string()
+}
+
+/// Translate `text` (HTML if `html`) with a LibreTranslate-compatible
+/// service, detecting the source language if it isn't known.
+async fn libretranslate(
+    service: &Service,
+    text: &str,
+    source: Option<&str>,
+    target: &str,
+    html: bool,
+) -> Result<String, Error> {
+    #[derive(serde::Serialize)]
+    struct Request<'a> {
+        q: &'a str,
+        source: &'a str,
+        target: &'a str,
+        format: &'a str,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        api_key: &'a str,
+    }
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Response {
+        translated_text: String,
+    }
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/translate", service.url))
+        .json(&Request {
+            q: text,
+            source: source.unwrap_or("auto"),
+            target,
+            format: if html { "html" } else { "text" },
+            api_key: &service.api_key,
+        })
+        .send()
+        .await?;
+    let response: Response = error::check(response).await?.json().await?;
+    Ok(response.translated_text)
+}
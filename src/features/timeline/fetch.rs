@@ -1,12 +1,18 @@
 use cosmic::iced::{stream, Subscription};
 use futures_util::SinkExt;
-use megalodon::megalodon::{
-    GetBookmarksInputOptions, GetFavouritesInputOptions, GetHomeTimelineInputOptions,
-    GetListTimelineInputOptions, GetLocalTimelineInputOptions, GetPublicTimelineInputOptions,
-    GetTagTimelineInputOptions,
+use megalodon::{
+    entities::Status,
+    megalodon::{
+        GetBookmarksInputOptions, GetFavouritesInputOptions, GetHomeTimelineInputOptions,
+        GetListTimelineInputOptions, GetLocalTimelineInputOptions, GetPublicTimelineInputOptions,
+        GetTagTimelineInputOptions, SearchInputOptions,
+    },
 };
 
-use crate::client::Client;
+use crate::{
+    client::Client,
+    error::{self, Error},
+};
 
 use super::{Message, TimelineKind};
 
@@ -16,95 +22,11 @@ pub fn timeline(mastodon: Client, kind: TimelineKind, max_id: Option<String>) ->
         let kind = kind.clone();
         let max_id = max_id.clone();
         stream::channel(1, move |mut output: futures_channel::mpsc::Sender<Message>| async move {
-            let result = match &kind {
-                TimelineKind::Home => {
-                    let options = GetHomeTimelineInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_home_timeline(Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-                TimelineKind::Local => {
-                    let options = GetLocalTimelineInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_local_timeline(Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-                TimelineKind::Public => {
-                    let options = GetPublicTimelineInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_public_timeline(Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-                TimelineKind::Federated => {
-                    // megalodon has no "remote only" flag on the public timeline endpoint,
-                    // so federated posts are derived by filtering out local ones: a status's
-                    // account `acct` only carries an `@instance` suffix for remote accounts.
-                    let options = GetPublicTimelineInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon.get_public_timeline(Some(&options)).await.map(|response| {
-                        response
-                            .json
-                            .into_iter()
-                            .filter(|status| status.account.acct.contains('@'))
-                            .collect()
-                    })
-                }
-                TimelineKind::Favorites => {
-                    let options = GetFavouritesInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_favourites(Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-                TimelineKind::Bookmarks => {
-                    let options = GetBookmarksInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_bookmarks(Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-                TimelineKind::Tag(tag) => {
-                    let options = GetTagTimelineInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_tag_timeline(tag.clone(), Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-                TimelineKind::List(id) => {
-                    let options = GetListTimelineInputOptions {
-                        max_id,
-                        ..Default::default()
-                    };
-                    mastodon
-                        .get_list_timeline(id.clone(), Some(&options))
-                        .await
-                        .map(|response| response.json)
-                }
-            };
-
+            let result = error::retry(|| async {
+                let result = fetch(&mastodon, &kind, max_id.clone()).await;
+                mastodon.with_rate_limit_reset(result).await
+            })
+            .await;
             match result {
                 Ok(statuses) => {
                     for status in statuses {
@@ -113,6 +35,18 @@ pub fn timeline(mastodon: Client, kind: TimelineKind, max_id: Option<String>) ->
                         }
                     }
                 }
+                Err(Error::Unauthorized) => {
+                    if let Err(err) = output.send(Message::Unauthorized).await {
+                        tracing::warn!("failed to send unauthorized: {}", err);
+                    }
+                }
+                Err(Error::Network(err) | Error::TimedOut(err)) => {
+                    tracing::warn!("offline: {}", err);
+                    if let Err(err) = output.send(Message::Offline).await {
+                        tracing::warn!("failed to send offline: {}", err);
+                    }
+                    return std::future::pending().await;
+                }
                 Err(err) => {
                     tracing::warn!("failed to get {:?} timeline: {}", kind, err);
                 }
@@ -125,3 +59,110 @@ pub fn timeline(mastodon: Client, kind: TimelineKind, max_id: Option<String>) ->
         })
     })
 }
+
+/// One page of `kind`'s statuses, older than `max_id` if given.
+async fn fetch(
+    mastodon: &Client,
+    kind: &TimelineKind,
+    max_id: Option<String>,
+) -> Result<Vec<Status>, Error> {
+    let statuses = match kind {
+        TimelineKind::Home => {
+            let options = GetHomeTimelineInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_home_timeline(Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::Local => {
+            let options = GetLocalTimelineInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_local_timeline(Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::Public => {
+            let options = GetPublicTimelineInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_public_timeline(Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::Federated => {
+            // megalodon has no "remote only" flag on the public timeline endpoint,
+            // so federated posts are derived by filtering out local ones: a status's
+            // account `acct` only carries an `@instance` suffix for remote accounts.
+            let options = GetPublicTimelineInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon.get_public_timeline(Some(&options)).await.map(|response| {
+                response
+                    .json
+                    .into_iter()
+                    .filter(|status| status.account.acct.contains('@'))
+                    .collect()
+            })
+        }
+        TimelineKind::Favorites => {
+            let options = GetFavouritesInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_favourites(Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::Bookmarks => {
+            let options = GetBookmarksInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_bookmarks(Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::Tag(tag) => {
+            let options = GetTagTimelineInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_tag_timeline(tag.clone(), Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::List(id) => {
+            let options = GetListTimelineInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .get_list_timeline(id.clone(), Some(&options))
+                .await
+                .map(|response| response.json)
+        }
+        TimelineKind::Search(query) => {
+            let options = SearchInputOptions {
+                max_id,
+                ..Default::default()
+            };
+            mastodon
+                .search(query.clone(), Some(&options))
+                .await
+                .map(|response| response.json.statuses)
+        }
+    }?;
+    Ok(statuses)
+}
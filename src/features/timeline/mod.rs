@@ -1,6 +1,7 @@
-//! Feed-shaped timelines: Home, Explore (public), Local, and Federated.
+//! Feed-shaped timelines: Home, Explore (public), Local, Federated, and the
+//! favourites/bookmarks/hashtag/list/saved-search feeds.
 //!
-//! All four share the same fetch/paginate/render shape, so they're modeled as
+//! They all share the same fetch/paginate/render shape, so they're modeled as
 //! one [`Timeline`] parameterized by [`TimelineKind`] instead of four
 //! near-identical structs.
 
@@ -42,6 +43,8 @@ pub enum TimelineKind {
     Tag(String),
     /// A user list's timeline.
     List(String),
+    /// Posts matching a saved search query.
+    Search(String),
 }
 
 impl TimelineKind {
@@ -56,6 +59,13 @@ impl TimelineKind {
             TimelineKind::Bookmarks => "bookmarks".to_string(),
             TimelineKind::Tag(name) => format!("tag-{name}"),
             TimelineKind::List(id) => format!("list-{id}"),
+            TimelineKind::Search(query) => format!(
+                "search-{}",
+                query
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect::<String>()
+            ),
         }
     }
 }
@@ -72,6 +82,8 @@ pub struct Timeline {
     /// Whether at least one fetch has completed, so an empty `statuses` can
     /// be told apart from "hasn't tried yet" (still loading).
     has_loaded: bool,
+    /// Streamed posts that arrived since the timeline was last viewed.
+    unread: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +96,10 @@ pub enum Message {
     LoadMore(bool),
     /// A fetch's result stream has ended (successfully, even if empty).
     LoadComplete,
+    /// A fetch was rejected because the session's token is no longer valid.
+    Unauthorized,
+    /// A fetch couldn't reach the server. Sent instead of `LoadComplete`.
+    Offline,
 }
 
 impl Timeline {
@@ -95,6 +111,7 @@ impl Timeline {
             max_id: None,
             loading: false,
             has_loaded: false,
+            unread: 0,
         }
     }
 
@@ -102,6 +119,10 @@ impl Timeline {
         self.mastodon.is_authenticated()
     }
 
+    pub fn kind(&self) -> &TimelineKind {
+        &self.kind
+    }
+
     /// Switch to a different account's client and drop this feed's current
     /// content, so a stale account's posts don't linger after switching.
     /// Call [`Timeline::load_cached`] afterward to repopulate from disk.
@@ -111,6 +132,15 @@ impl Timeline {
         self.max_id = None;
         self.loading = false;
         self.has_loaded = false;
+        self.unread = 0;
+    }
+
+    pub fn unread(&self) -> usize {
+        self.unread
+    }
+
+    pub fn mark_read(&mut self) {
+        self.unread = 0;
     }
 
     /// Whether the initial fetch is still in flight (no content yet, and no
@@ -119,11 +149,10 @@ impl Timeline {
         self.statuses.is_empty() && !self.has_loaded
     }
 
-    /// Load this feed's last-saved snapshot from disk (if any) so the view
+    /// Load this feed's last-saved statuses from disk (if any) so the view
     /// has something to render immediately, before the network fetch lands.
     pub fn load_cached(&mut self) -> Task<app::Message> {
-        let cached: Vec<Status> =
-            crate::persistence::load_snapshot(&self.mastodon.base_url, &self.kind.slug());
+        let cached = crate::persistence::load_timeline(&self.mastodon.base_url, &self.kind.slug());
         let mut tasks = vec![];
         for status in cached {
             if !self.statuses.contains(&status.id) {
@@ -137,18 +166,30 @@ impl Timeline {
         Task::batch(tasks)
     }
 
-    /// Persist the currently-cached statuses for this feed to disk.
+    /// Persist which cached statuses make up this feed. The statuses
+    /// themselves are saved by the app's flush as they change.
     pub fn save_cached(&self, cache: &Cache) {
-        let statuses: Vec<Status> = self
+        let ids: Vec<String> = self
             .statuses
             .iter()
-            .filter_map(|id| cache.statuses.get(id).cloned())
+            .filter(|id| cache.statuses.contains_key(*id))
+            .cloned()
             .collect();
-        crate::persistence::save_status_snapshot(
-            &self.mastodon.base_url,
-            &self.kind.slug(),
-            &statuses,
-        );
+        crate::persistence::save_timeline(&self.mastodon.base_url, &self.kind.slug(), &ids);
+    }
+
+    /// Ids of the statuses this feed shows, which must stay in the cache.
+    pub fn status_ids(&self) -> impl Iterator<Item = &String> {
+        self.statuses.iter()
+    }
+
+    /// Every image this feed's loaded statuses show.
+    pub fn image_urls(&self, cache: &Cache) -> Vec<String> {
+        self.statuses
+            .iter()
+            .filter_map(|id| cache.statuses.get(id))
+            .flat_map(cache::extract_status_images)
+            .collect()
     }
 
     pub fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, Message> {
@@ -192,6 +233,7 @@ impl Timeline {
             })
             .apply(widget::container)
             .max_width(700)
+            .width(Length::Fill)
             .height(Length::Fill)
             .into()
     }
@@ -221,6 +263,7 @@ impl Timeline {
             Message::PrependStatus(status) => {
                 if !self.statuses.contains(&status.id) {
                     self.statuses.push_front(status.id.clone());
+                    self.unread += 1;
                 }
                 tasks.push(cosmic::task::message(app::Message::CacheStatus(status)));
             }
@@ -229,6 +272,16 @@ impl Timeline {
                 self.loading = false;
                 self.has_loaded = true;
             }
+            Message::Unauthorized => {
+                tasks.push(cosmic::task::message(app::Message::SessionExpired));
+            }
+            Message::Offline => {
+                // Fetch again once back online; until then the cached
+                // content stays up without a loading indicator.
+                self.loading = false;
+                self.has_loaded = false;
+                tasks.push(cosmic::task::message(app::Message::Offline));
+            }
             Message::Status(message) => tasks.push(status::update(message)),
         }
         Task::batch(tasks)
@@ -237,7 +290,10 @@ impl Timeline {
     pub fn subscription(&self) -> Subscription<Message> {
         let requires_auth = matches!(
             self.kind,
-            TimelineKind::Home | TimelineKind::Favorites | TimelineKind::Bookmarks
+            TimelineKind::Home
+                | TimelineKind::Favorites
+                | TimelineKind::Bookmarks
+                | TimelineKind::Search(_)
         );
         if requires_auth && !self.is_authenticated() {
             return Subscription::none();
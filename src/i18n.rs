@@ -49,3 +49,9 @@ macro_rules! fl {
         i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id, $($args), *)
     }};
 }
+
+/// The language the UI is shown in, e.g. `en`: the one posts are
+/// translated into.
+pub fn language() -> String {
+    LANGUAGE_LOADER.current_language().language.to_string()
+}
@@ -9,7 +9,9 @@ mod config;
 mod error;
 mod features;
 mod i18n;
+mod oauth;
 mod persistence;
+mod session_store;
 mod settings;
 mod streaming;
 
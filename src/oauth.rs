@@ -0,0 +1,332 @@
+//! The browser half of logging in: a temporary localhost listener that
+//! catches the OAuth redirect, and PKCE for servers that support it.
+
+use std::{sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use megalodon::{default::NO_REDIRECT, megalodon::AppInputOptions};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{
+    client::{AppCredentials, Client, Software},
+    error::Error,
+};
+
+/// The OAuth scopes toot asks for.
+pub const SCOPES: [&str; 3] = ["read", "write", "follow"];
+
+/// How long to wait for the user to approve the login in their browser.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(600);
+
+const CALLBACK_PAGE: &str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Toot</title></head>\
+<body style=\"font-family: sans-serif; text-align: center; margin-top: 4em\">\
+<h1>You're logged in</h1><p>You can close this window and return to Toot.</p></body></html>";
+
+/// The reply to a callback that didn't log in.
+const BAD_REQUEST: &str = "HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n";
+
+/// An app registered with the instance being logged into, kept until the
+/// authorization code arrives and is exchanged for a token.
+#[derive(Debug, Clone)]
+pub struct Registration {
+    pub base_url: String,
+    pub software: Software,
+    pub app: AppCredentials,
+    /// The authorize URL the server returned when registering, if the app
+    /// was just registered; reused registrations build their own.
+    pub url: Option<String>,
+    /// Random value round-tripped through the browser, checked on callback.
+    pub state: String,
+    pub pkce: Option<Pkce>,
+    /// `None` when the listener couldn't bind and the user has to paste the
+    /// code by hand.
+    pub loopback: Option<Loopback>,
+}
+
+impl Registration {
+    /// The page to open in the browser, with the state and PKCE challenge
+    /// added to the URL the server handed back.
+    pub fn authorize_url(&self) -> Option<String> {
+        let mut url = match &self.url {
+            Some(url) => url::Url::parse(url).ok()?,
+            None => {
+                let authorize = format!("{}/oauth/authorize", self.base_url);
+                let mut url = url::Url::parse(&authorize).ok()?;
+                url.query_pairs_mut()
+                    .append_pair("client_id", &self.app.client_id)
+                    .append_pair("response_type", "code")
+                    .append_pair("redirect_uri", &self.app.redirect_uri)
+                    .append_pair("scope", &SCOPES.join(" "));
+                url
+            }
+        };
+        // Misskey's session-based flow has no OAuth query to extend.
+        if self.software != Software::Misskey {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("state", &self.state);
+            if let Some(pkce) = &self.pkce {
+                query
+                    .append_pair("code_challenge", &pkce.challenge)
+                    .append_pair("code_challenge_method", "S256");
+            }
+        }
+        Some(url.into())
+    }
+}
+
+/// Register toot with `base_url` (or reuse the `saved` registration) and
+/// get ready to receive the authorization code.
+pub async fn register(
+    base_url: String,
+    saved: Option<AppCredentials>,
+) -> Result<Registration, Error> {
+    let software = match Software::detect(&base_url).await {
+        Ok(software) => software,
+        Err(err) => {
+            tracing::warn!("Couldn't detect server software, assuming Mastodon: {err}");
+            Software::default()
+        }
+    };
+    // Misskey registers a fresh auth session every time, so only other
+    // servers can reuse a registration.
+    let saved = saved.filter(|_| software != Software::Misskey);
+
+    // A saved registration is reusable if its redirect URI can be served
+    // again: its loopback port is free, or it was registered for manual code
+    // entry and the listener can't start now either.
+    let rebound = match &saved {
+        Some(app) => Loopback::rebind(&app.redirect_uri).await,
+        None => None,
+    };
+    let (loopback, reused) = match rebound {
+        Some(loopback) => (Some(loopback), saved),
+        None => match Loopback::bind().await {
+            Ok(loopback) => (Some(loopback), None),
+            Err(err) => {
+                tracing::warn!("Couldn't start login listener: {err}");
+                (None, saved.filter(|app| app.redirect_uri == NO_REDIRECT))
+            }
+        },
+    };
+    let pkce = (software != Software::Misskey && Pkce::is_supported(&base_url).await)
+        .then(Pkce::new);
+
+    let (app, url) = match reused {
+        Some(app) => (app, None),
+        None => {
+            // Without a listener, fall back to the server showing the code
+            // for the user to paste.
+            let redirect_uri = loopback
+                .as_ref()
+                .map(|loopback| loopback.redirect_uri.clone())
+                .unwrap_or_else(|| NO_REDIRECT.to_string());
+            let options = AppInputOptions {
+                scopes: Some(SCOPES.into_iter().map(String::from).collect()),
+                redirect_uris: Some(redirect_uri.clone()),
+                ..Default::default()
+            };
+            let app_data = Client::new(software, base_url.clone(), None)
+                .register_app("Toot".to_string(), &options)
+                .await?;
+            let app = AppCredentials {
+                client_id: app_data.client_id,
+                client_secret: app_data.client_secret,
+                redirect_uri,
+            };
+            (app, app_data.url)
+        }
+    };
+
+    Ok(Registration {
+        base_url,
+        software,
+        app,
+        url,
+        state: random_string(32),
+        pkce,
+        loopback,
+    })
+}
+
+/// A PKCE (RFC 7636) verifier and its S256 challenge.
+#[derive(Debug, Clone)]
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn new() -> Self {
+        let verifier = random_string(64);
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+
+    /// Whether the server advertises S256 PKCE in its OAuth metadata.
+    /// Servers without the metadata endpoint are assumed not to.
+    pub async fn is_supported(base_url: &str) -> bool {
+        #[derive(serde::Deserialize)]
+        struct Metadata {
+            #[serde(default)]
+            code_challenge_methods_supported: Vec<String>,
+        }
+
+        let metadata = async {
+            reqwest::get(format!("{base_url}/.well-known/oauth-authorization-server"))
+                .await?
+                .error_for_status()?
+                .json::<Metadata>()
+                .await
+        };
+        metadata.await.is_ok_and(|metadata| {
+            metadata
+                .code_challenge_methods_supported
+                .iter()
+                .any(|method| method == "S256")
+        })
+    }
+}
+
+pub fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// A listener on a random localhost port that the server redirects the
+/// browser back to once the user approves the login.
+#[derive(Debug, Clone)]
+pub struct Loopback {
+    listener: Arc<TcpListener>,
+    pub redirect_uri: String,
+}
+
+impl Loopback {
+    pub async fn bind() -> Result<Self, Error> {
+        Self::bind_port(0).await
+    }
+
+    /// Listen on the port of a previously registered loopback redirect URI,
+    /// so that registration can be reused. `None` if the URI isn't a
+    /// loopback one or the port is taken.
+    pub async fn rebind(redirect_uri: &str) -> Option<Self> {
+        let url = url::Url::parse(redirect_uri).ok()?;
+        if url.host_str() != Some("127.0.0.1") {
+            return None;
+        }
+        Self::bind_port(url.port()?).await.ok()
+    }
+
+    async fn bind_port(port: u16) -> Result<Self, Error> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let port = listener.local_addr()?.port();
+        Ok(Self {
+            listener: Arc::new(listener),
+            redirect_uri: format!("http://127.0.0.1:{port}/callback"),
+        })
+    }
+
+    /// Wait for the browser to hit the callback, and return the
+    /// authorization code from it. Requests for other paths (e.g. the
+    /// browser asking for a favicon) are answered and ignored. The callback
+    /// must bring back `state`, unless there is none to check (Misskey's
+    /// session flow doesn't round-trip one).
+    pub async fn wait_for_code(&self, state: Option<&str>) -> Result<String, Error> {
+        tokio::time::timeout(CALLBACK_TIMEOUT, self.accept_callback(state))
+            .await
+            .map_err(|_| Error::Login("timed out waiting for the browser".into()))?
+    }
+
+    async fn accept_callback(&self, state: Option<&str>) -> Result<String, Error> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await?;
+            let mut buffer = vec![0; 8192];
+            let read = stream.read(&mut buffer).await?;
+            let request = String::from_utf8_lossy(&buffer[..read]);
+            let Some(path) = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+            else {
+                continue;
+            };
+            let Ok(url) = url::Url::parse(&format!("http://127.0.0.1{path}")) else {
+                continue;
+            };
+            if url.path() != "/callback" {
+                stream
+                    .write_all(b"HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n")
+                    .await?;
+                continue;
+            }
+
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
+            // A callback without our state didn't come from this login;
+            // ignore it rather than letting it end the real one.
+            if state.is_some_and(|state| param("state").as_deref() != Some(state)) {
+                stream.write_all(BAD_REQUEST.as_bytes()).await?;
+                continue;
+            }
+            if let Some(error) = param("error") {
+                stream.write_all(BAD_REQUEST.as_bytes()).await?;
+                let description = param("error_description").unwrap_or(error);
+                return Err(Error::Login(description));
+            }
+            // Misskey calls it `token`, everything else `code`.
+            let Some(code) = param("code").or_else(|| param("token")) else {
+                stream.write_all(BAD_REQUEST.as_bytes()).await?;
+                continue;
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{CALLBACK_PAGE}",
+                CALLBACK_PAGE.len()
+            );
+            stream.write_all(response.as_bytes()).await?;
+            return Ok(code);
+        }
+    }
+}
+
+/// Exchange an authorization code for an access token directly, for PKCE
+/// logins (megalodon's `fetch_access_token` can't send a code verifier).
+pub async fn fetch_token_with_pkce(
+    registration: &Registration,
+    pkce: &Pkce,
+    code: &str,
+) -> Result<String, Error> {
+    #[derive(serde::Deserialize)]
+    struct Token {
+        access_token: String,
+    }
+
+    let token: Token = reqwest::Client::new()
+        .post(format!("{}/oauth/token", registration.base_url))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("client_id", &registration.app.client_id),
+            ("client_secret", &registration.app.client_secret),
+            ("redirect_uri", &registration.app.redirect_uri),
+            ("code_verifier", &pkce.verifier),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(token.access_token)
+}
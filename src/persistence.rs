@@ -1,27 +1,58 @@
-//! Disk-persisted snapshots of feed content, so a restart can render the
-//! last-seen posts/notifications immediately instead of a blank screen while
-//! the network catches up.
+//! Disk-persisted feed content, so a restart can render the last-seen
+//! posts/notifications immediately instead of a blank screen while the
+//! network catches up. Everything lives in one embedded SQLite database,
+//! keyed by account, and is upserted as it changes rather than rewritten
+//! feed by feed.
 
-use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime};
 
+use chrono::Utc;
 use cosmic::Application;
+use megalodon::entities::{Account, Notification, Relationship, Status};
+use rusqlite::{params, Connection};
 use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::app::AppModel;
 
-/// Cap on how many items are persisted per feed, matching existing
-/// pagination page sizes.
+/// Cap on how many items are kept per feed, matching existing pagination
+/// page sizes.
 const MAX_STATUSES: usize = 200;
 const MAX_NOTIFICATIONS: usize = 100;
-/// Cap on how many distinct hashtag/list snapshot files are kept.
-const MAX_SCOPED_FILES: usize = 5;
+/// Cap on how many distinct hashtag/list/search timelines are kept.
+const MAX_SCOPED_TIMELINES: usize = 5;
+/// Cap on statuses kept per account outside of any feed (opened threads,
+/// profiles, search results), newest-seen first.
+const MAX_LOOSE_STATUSES: usize = 5000;
+/// Cap on local search results.
+const MAX_SEARCH_RESULTS: usize = 40;
+/// Wide enough that indexed text is never wrapped mid-phrase.
+const INDEX_TEXT_WIDTH: usize = 10_000;
+/// Length of a cached image's filename: a hex-encoded SHA-256 digest.
+const IMAGE_NAME_LEN: usize = 64;
+
+/// Schema changes, applied in order. `PRAGMA user_version` records how many
+/// have run, so only append to this list; never edit a shipped migration.
+const MIGRATIONS: &[fn(&Connection) -> rusqlite::Result<()>] =
+    &[create_tables, create_search_index];
+
+/// Opened on first use. `None` if the database couldn't be opened at all,
+/// in which case nothing is persisted and every feed starts cold.
+static DB: LazyLock<Option<Mutex<Connection>>> = LazyLock::new(|| match open() {
+    Ok(conn) => Some(Mutex::new(conn)),
+    Err(err) => {
+        tracing::warn!("failed to open cache database: {err}");
+        None
+    }
+});
 
 fn cache_root() -> Option<PathBuf> {
     dirs::cache_dir().map(|dir| dir.join(AppModel::APP_ID))
 }
 
-/// Sanitize an account's base URL into a filesystem-safe directory name.
+/// Sanitize an account's base URL into the key its rows are stored under.
 fn account_slug(base_url: &str) -> String {
     base_url
         .trim_start_matches("https://")
@@ -31,118 +62,545 @@ fn account_slug(base_url: &str) -> String {
         .collect()
 }
 
-fn account_dir(base_url: &str) -> Option<PathBuf> {
-    Some(cache_root()?.join(account_slug(base_url)))
+/// Open the database in the cache directory (or in memory, without one),
+/// bringing its schema up to date.
+fn open() -> rusqlite::Result<Connection> {
+    let root = cache_root();
+    let mut conn = match &root {
+        Some(root) => {
+            if let Err(err) = std::fs::create_dir_all(root) {
+                tracing::warn!("failed to create cache directory: {err}");
+            }
+            Connection::open(root.join("cache.sqlite3"))?
+        }
+        None => Connection::open_in_memory()?,
+    };
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    migrate(&mut conn)?;
+    if let Some(root) = root {
+        import_json_snapshots(&mut conn, &root);
+    }
+    Ok(conn)
 }
 
-/// Path for a named feed snapshot within an account's cache directory.
-fn snapshot_path(base_url: &str, slug: &str) -> Option<PathBuf> {
-    Some(account_dir(base_url)?.join(format!("{slug}.json")))
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", index as i64 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
 }
 
-/// Save a bounded slice of items to disk as JSON. Errors are logged, not
-/// surfaced — a failed cache write should never interrupt the user.
-pub fn save_snapshot<T: Serialize>(base_url: &str, slug: &str, items: &[T], max_items: usize) {
-    let Some(path) = snapshot_path(base_url, slug) else {
-        return;
-    };
-    let Some(parent) = path.parent() else { return };
-    if let Err(err) = std::fs::create_dir_all(parent) {
-        tracing::warn!("failed to create cache directory: {err}");
-        return;
+fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE statuses (
+            account TEXT NOT NULL,
+            id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            seen_at INTEGER NOT NULL,
+            PRIMARY KEY (account, id)
+        );
+        CREATE TABLE accounts (
+            account TEXT NOT NULL,
+            id TEXT NOT NULL,
+            acct TEXT NOT NULL,
+            data TEXT NOT NULL,
+            PRIMARY KEY (account, id)
+        );
+        CREATE TABLE notifications (
+            account TEXT NOT NULL,
+            id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (account, id)
+        );
+        CREATE TABLE relationships (
+            account TEXT NOT NULL,
+            id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            PRIMARY KEY (account, id)
+        );
+        CREATE TABLE timelines (
+            account TEXT NOT NULL,
+            timeline TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (account, timeline)
+        );
+        CREATE TABLE timeline_entries (
+            account TEXT NOT NULL,
+            timeline TEXT NOT NULL,
+            status_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (account, timeline, status_id)
+        );
+        CREATE INDEX timeline_entries_position ON timeline_entries (account, timeline, position);",
+    )
+}
+
+/// A full-text index over each status's text, content warning, author and
+/// hashtags, sharing `statuses`' rowids. Upserts reindex a status (see
+/// [`index_status`]); deleting it drops it from the index.
+fn create_search_index(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE status_search USING fts5(
+            content,
+            spoiler_text,
+            author,
+            tags,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+        CREATE TRIGGER statuses_unindex AFTER DELETE ON statuses BEGIN
+            DELETE FROM status_search WHERE rowid = old.rowid;
+        END;",
+    )?;
+    let mut stmt = conn.prepare("SELECT rowid, data FROM statuses")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for (rowid, data) in rows.filter_map(Result::ok) {
+        if let Ok(status) = serde_json::from_str::<Status>(&data) {
+            index_status(conn, rowid, &status)?;
+        }
     }
+    Ok(())
+}
 
-    // `items` is ordered newest-first (matches `Timeline`/`Notifications`'
-    // front-to-back id ordering), so bound by keeping the front (newest).
-    let bounded = &items[..max_items.min(items.len())];
-    match serde_json::to_vec(bounded) {
-        Ok(data) => {
-            if let Err(err) = std::fs::write(&path, data) {
-                tracing::warn!("failed to write cache snapshot {}: {err}", path.display());
+/// Move feeds that earlier versions saved as one JSON file each (under
+/// `<cache>/<account>/<feed>.json`) into the database, so the first start
+/// after upgrading still renders instantly.
+fn import_json_snapshots(conn: &mut Connection, root: &Path) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    for dir in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let Some(account) = dir.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Ok(files) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in files.filter_map(Result::ok).map(|entry| entry.path()) {
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(timeline) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let data = std::fs::read(&path).unwrap_or_default();
+            let result = if timeline == "notifications" {
+                let notifications: Vec<Notification> =
+                    serde_json::from_slice(&data).unwrap_or_default();
+                write(conn, |tx| upsert_notifications(tx, account, &notifications))
+            } else {
+                let statuses: Vec<Status> = serde_json::from_slice(&data).unwrap_or_default();
+                let ids: Vec<String> = statuses.iter().map(|status| status.id.clone()).collect();
+                write(conn, |tx| {
+                    upsert_statuses(tx, account, &statuses)?;
+                    set_timeline(tx, account, timeline, &ids)
+                })
+            };
+            match result {
+                Ok(()) => {
+                    let _ = std::fs::remove_file(&path);
+                }
+                Err(err) => tracing::warn!("failed to import {}: {err}", path.display()),
             }
         }
-        Err(err) => tracing::warn!("failed to serialize cache snapshot: {err}"),
+        // Only succeeds once every snapshot in it was imported.
+        let _ = std::fs::remove_dir(&dir);
     }
 }
 
-pub fn save_status_snapshot(base_url: &str, slug: &str, items: &[megalodon::entities::Status]) {
-    save_snapshot(base_url, slug, items, MAX_STATUSES);
-    prune_scoped_snapshots(base_url);
+/// Run `f` in a transaction on `conn`.
+fn write(
+    conn: &mut Connection,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<()>,
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    f(&tx)?;
+    tx.commit()
 }
 
-pub fn save_notification_snapshot(
-    base_url: &str,
-    items: &[megalodon::entities::Notification],
-) {
-    save_snapshot(base_url, "notifications", items, MAX_NOTIFICATIONS);
+/// Run `f` in a transaction on the shared database. Errors are logged, not
+/// surfaced — a failed cache write should never interrupt the user.
+fn with_write(f: impl FnOnce(&Connection) -> rusqlite::Result<()>) {
+    let Some(db) = DB.as_ref() else { return };
+    let Ok(mut conn) = db.lock() else { return };
+    if let Err(err) = write(&mut conn, f) {
+        tracing::warn!("failed to write cache database: {err}");
+    }
 }
 
-/// Load a previously-saved snapshot, if any. Returns an empty `Vec` on any
-/// error (missing file, corrupt JSON, etc.) — a cold cache is not a failure.
-pub fn load_snapshot<T: DeserializeOwned>(base_url: &str, slug: &str) -> Vec<T> {
-    let Some(path) = snapshot_path(base_url, slug) else {
+/// Run a query on the shared database. Returns an empty `Vec` on any error
+/// (no database, corrupt rows, etc.) — a cold cache is not a failure.
+fn with_read<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<Vec<T>>) -> Vec<T> {
+    let Some(db) = DB.as_ref() else {
         return Vec::new();
     };
-    match std::fs::read(&path) {
-        Ok(data) => serde_json::from_slice(&data).unwrap_or_default(),
-        Err(_) => Vec::new(),
+    let Ok(conn) = db.lock() else {
+        return Vec::new();
+    };
+    f(&conn).unwrap_or_else(|err| {
+        tracing::warn!("failed to read cache database: {err}");
+        Vec::new()
+    })
+}
+
+/// Deserialize each row's JSON `data` column, skipping rows that no longer
+/// match the entity's shape.
+fn query_json<T: DeserializeOwned>(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> rusqlite::Result<Vec<T>> {
+    let mut stmt = conn.prepare_cached(sql)?;
+    let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+    Ok(rows
+        .filter_map(Result::ok)
+        .filter_map(|data| serde_json::from_str(&data).ok())
+        .collect())
+}
+
+fn upsert_statuses<'a>(
+    conn: &Connection,
+    account: &str,
+    statuses: impl IntoIterator<Item = &'a Status>,
+) -> rusqlite::Result<()> {
+    let seen_at = Utc::now().timestamp();
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO statuses (account, id, data, seen_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (account, id) DO UPDATE SET data = excluded.data, seen_at = excluded.seen_at
+         RETURNING rowid",
+    )?;
+    for status in statuses {
+        let Ok(data) = serde_json::to_string(status) else {
+            continue;
+        };
+        let rowid = stmt.query_row(params![account, status.id, data, seen_at], |row| row.get(0))?;
+        index_status(conn, rowid, status)?;
+        upsert_account(conn, account, &status.account)?;
     }
+    Ok(())
 }
 
-/// Keep only the `MAX_SCOPED_FILES` most-recently-modified hashtag/list
-/// snapshots, so browsing many tags/lists doesn't accumulate cache files
-/// forever. Called after every status snapshot save (cheap: a handful of
-/// directory entries at most).
-fn prune_scoped_snapshots(base_url: &str) {
-    for prefix in ["tag-", "list-"] {
-        prune_prefixed(base_url, prefix);
+/// (Re)index a status for local search. Boosts aren't indexed themselves:
+/// the boosted status is cached, and indexed, on its own.
+fn index_status(conn: &Connection, rowid: i64, status: &Status) -> rusqlite::Result<()> {
+    conn.prepare_cached("DELETE FROM status_search WHERE rowid = ?1")?
+        .execute([rowid])?;
+    if status.reblog.is_some() {
+        return Ok(());
     }
+    let content = html2text::config::plain()
+        .string_from_read(status.content.as_bytes(), INDEX_TEXT_WIDTH)
+        .unwrap_or_else(|_| status.content.clone());
+    let author = format!("{} {}", status.account.display_name, status.account.acct);
+    let tags = status
+        .tags
+        .iter()
+        .map(|tag| tag.name.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    conn.prepare_cached(
+        "INSERT INTO status_search (rowid, content, spoiler_text, author, tags)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?
+    .execute(params![rowid, content, status.spoiler_text, author, tags])?;
+    Ok(())
 }
 
-fn prune_prefixed(base_url: &str, prefix: &str) {
-    let Some(dir) = account_dir(base_url) else {
-        return;
-    };
-    let Ok(entries) = std::fs::read_dir(&dir) else {
-        return;
+fn upsert_account(conn: &Connection, account: &str, entity: &Account) -> rusqlite::Result<()> {
+    let Ok(data) = serde_json::to_string(entity) else {
+        return Ok(());
     };
+    conn.prepare_cached(
+        "INSERT INTO accounts (account, id, acct, data) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (account, id) DO UPDATE SET acct = excluded.acct, data = excluded.data",
+    )?
+    .execute(params![account, entity.id, entity.acct, data])?;
+    Ok(())
+}
 
-    let mut files: Vec<(PathBuf, std::time::SystemTime)> = entries
-        .filter_map(Result::ok)
-        .map(|entry| entry.path())
-        .filter(|path| {
-            path.file_name()
-                .and_then(|name| name.to_str())
-                .is_some_and(|name| name.starts_with(prefix))
-        })
-        .filter_map(|path| {
-            let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
-            Some((path, modified))
-        })
-        .collect();
+fn upsert_notifications<'a>(
+    conn: &Connection,
+    account: &str,
+    notifications: impl IntoIterator<Item = &'a Notification>,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO notifications (account, id, data, created_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (account, id) DO UPDATE SET data = excluded.data",
+    )?;
+    for notification in notifications {
+        let Ok(data) = serde_json::to_string(notification) else {
+            continue;
+        };
+        let created_at = notification.created_at.timestamp_millis();
+        stmt.execute(params![account, notification.id, data, created_at])?;
+        if let Some(entity) = &notification.account {
+            upsert_account(conn, account, entity)?;
+        }
+    }
+    // Keep only the newest notifications.
+    conn.execute(
+        "DELETE FROM notifications WHERE account = ?1 AND id NOT IN (
+             SELECT id FROM notifications WHERE account = ?1
+             ORDER BY created_at DESC LIMIT ?2
+         )",
+        params![account, MAX_NOTIFICATIONS as i64],
+    )?;
+    Ok(())
+}
 
-    if files.len() <= MAX_SCOPED_FILES {
-        return;
+/// Make `ids` (newest-first, bounded to [`MAX_STATUSES`]) the membership of
+/// `timeline`, upserting positions and dropping entries no longer in it.
+fn set_timeline(
+    conn: &Connection,
+    account: &str,
+    timeline: &str,
+    ids: &[String],
+) -> rusqlite::Result<()> {
+    let ids = &ids[..MAX_STATUSES.min(ids.len())];
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO timeline_entries (account, timeline, status_id, position)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (account, timeline, status_id) DO UPDATE SET position = excluded.position",
+    )?;
+    for (position, id) in ids.iter().enumerate() {
+        stmt.execute(params![account, timeline, id, position as i64])?;
     }
+    conn.execute(
+        "DELETE FROM timeline_entries WHERE account = ?1 AND timeline = ?2
+         AND status_id NOT IN (SELECT value FROM json_each(?3))",
+        params![
+            account,
+            timeline,
+            serde_json::Value::from(ids.to_vec()).to_string()
+        ],
+    )?;
+    conn.execute(
+        "INSERT INTO timelines (account, timeline, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT (account, timeline) DO UPDATE SET updated_at = excluded.updated_at",
+        params![account, timeline, Utc::now().timestamp_millis()],
+    )?;
+    prune_scoped_timelines(conn, account)
+}
 
-    files.sort_by_key(|(_, modified)| *modified);
-    let excess = files.len() - MAX_SCOPED_FILES;
-    for (path, _) in files.into_iter().take(excess) {
-        let _ = std::fs::remove_file(path);
+/// Keep only the `MAX_SCOPED_TIMELINES` most recently saved hashtag, list
+/// and search timelines, so browsing many tags/lists doesn't accumulate
+/// entries forever.
+fn prune_scoped_timelines(conn: &Connection, account: &str) -> rusqlite::Result<()> {
+    for prefix in ["tag-", "list-", "search-"] {
+        let pattern = format!("{prefix}%");
+        let stale = "SELECT timeline FROM timelines WHERE account = ?1 AND timeline LIKE ?2
+                     ORDER BY updated_at DESC LIMIT -1 OFFSET ?3";
+        conn.execute(
+            &format!("DELETE FROM timeline_entries WHERE account = ?1 AND timeline IN ({stale})"),
+            params![account, pattern, MAX_SCOPED_TIMELINES as i64],
+        )?;
+        conn.execute(
+            &format!("DELETE FROM timelines WHERE account = ?1 AND timeline IN ({stale})"),
+            params![account, pattern, MAX_SCOPED_TIMELINES as i64],
+        )?;
     }
+    Ok(())
+}
+
+/// Drop the oldest-seen statuses that aren't in any feed, beyond
+/// [`MAX_LOOSE_STATUSES`].
+fn prune_loose_statuses(conn: &Connection, account: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM statuses WHERE account = ?1
+         AND id NOT IN (SELECT status_id FROM timeline_entries WHERE account = ?1)
+         AND id NOT IN (
+             SELECT id FROM statuses WHERE account = ?1 ORDER BY seen_at DESC LIMIT ?2
+         )",
+        params![account, MAX_LOOSE_STATUSES as i64],
+    )?;
+    Ok(())
+}
+
+/// Upsert statuses (and their authors) that changed since the last save.
+pub fn save_statuses<'a>(base_url: &str, statuses: impl IntoIterator<Item = &'a Status>) {
+    let account = account_slug(base_url);
+    with_write(|conn| {
+        upsert_statuses(conn, &account, statuses)?;
+        prune_loose_statuses(conn, &account)
+    });
+}
+
+/// A single cached status, e.g. one evicted from memory that's needed again.
+pub fn load_status(base_url: &str, id: &str) -> Option<Status> {
+    let account = account_slug(base_url);
+    with_read(|conn| {
+        query_json(
+            conn,
+            "SELECT data FROM statuses WHERE account = ?1 AND id = ?2",
+            params![account, id],
+        )
+    })
+    .into_iter()
+    .next()
+}
+
+/// Save which statuses make up a feed, newest first.
+pub fn save_timeline(base_url: &str, timeline: &str, ids: &[String]) {
+    let account = account_slug(base_url);
+    with_write(|conn| set_timeline(conn, &account, timeline, ids));
+}
+
+/// Load a feed's last-saved statuses, newest first.
+pub fn load_timeline(base_url: &str, timeline: &str) -> Vec<Status> {
+    let account = account_slug(base_url);
+    with_read(|conn| {
+        query_json(
+            conn,
+            "SELECT s.data FROM timeline_entries e
+             JOIN statuses s ON s.account = e.account AND s.id = e.status_id
+             WHERE e.account = ?1 AND e.timeline = ?2
+             ORDER BY e.position",
+            params![account, timeline],
+        )
+    })
+}
+
+/// Cached statuses of the account matching every word of `query` (as a
+/// prefix) in their text, content warning, author or hashtags, best match
+/// first.
+pub fn search_statuses(base_url: &str, query: &str) -> Vec<Status> {
+    // Quote each word so user input can't be read as FTS5 query syntax.
+    let query = query
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('#').replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{word}\"*"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let account = account_slug(base_url);
+    with_read(|conn| {
+        query_json(
+            conn,
+            "SELECT s.data FROM status_search
+             JOIN statuses s ON s.rowid = status_search.rowid
+             WHERE status_search MATCH ?2 AND s.account = ?1
+             ORDER BY status_search.rank
+             LIMIT ?3",
+            params![account, query, MAX_SEARCH_RESULTS as i64],
+        )
+    })
+}
+
+pub fn save_notifications<'a>(
+    base_url: &str,
+    notifications: impl IntoIterator<Item = &'a Notification>,
+) {
+    let account = account_slug(base_url);
+    with_write(|conn| upsert_notifications(conn, &account, notifications));
+}
+
+/// Load the last-saved notifications, newest first.
+pub fn load_notifications(base_url: &str) -> Vec<Notification> {
+    let account = account_slug(base_url);
+    with_read(|conn| {
+        query_json(
+            conn,
+            "SELECT data FROM notifications WHERE account = ?1 ORDER BY created_at DESC",
+            params![account],
+        )
+    })
+}
+
+pub fn save_relationships<'a>(
+    base_url: &str,
+    relationships: impl IntoIterator<Item = &'a Relationship>,
+) {
+    let account = account_slug(base_url);
+    with_write(|conn| {
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO relationships (account, id, data) VALUES (?1, ?2, ?3)
+             ON CONFLICT (account, id) DO UPDATE SET data = excluded.data",
+        )?;
+        for relationship in relationships {
+            if let Ok(data) = serde_json::to_string(relationship) {
+                stmt.execute(params![account, relationship.id, data])?;
+            }
+        }
+        Ok(())
+    });
+}
+
+pub fn load_relationships(base_url: &str) -> Vec<Relationship> {
+    let account = account_slug(base_url);
+    with_read(|conn| {
+        query_json(
+            conn,
+            "SELECT data FROM relationships WHERE account = ?1",
+            params![account],
+        )
+    })
+}
+
+fn image_cache_dir() -> Option<PathBuf> {
+    Some(cache_root()?.join("images"))
 }
 
 /// Content-addressed disk cache for downloaded images, shared across
-/// accounts since media URLs are already public.
+/// accounts since media URLs are already public. Named by SHA-256 so the
+/// same URL maps to the same file across builds.
 fn image_cache_path(url: &str) -> Option<PathBuf> {
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    url.hash(&mut hasher);
-    let hashed = hasher.finish();
-    Some(cache_root()?.join("images").join(format!("{hashed:x}")))
+    let hashed: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Some(image_cache_dir()?.join(hashed))
 }
 
+/// Read a cached image, bumping its modification time so pruning treats it
+/// as recently used.
 pub fn load_image(url: &str) -> Option<Vec<u8>> {
-    std::fs::read(image_cache_path(url)?).ok()
+    let path = image_cache_path(url)?;
+    let bytes = std::fs::read(&path).ok()?;
+    if let Err(err) = std::fs::File::options()
+        .append(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        tracing::debug!("failed to touch cached image {}: {err}", path.display());
+    }
+    Some(bytes)
+}
+
+/// Where a downloaded video or audio attachment is cached, if it is. Media
+/// shares the image cache, so it's pruned along with the images.
+#[cfg(feature = "playback")]
+pub fn cached_media_path(url: &str) -> Option<PathBuf> {
+    let path = image_cache_path(url)?;
+    if let Err(err) = std::fs::File::options()
+        .append(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        tracing::debug!("media not cached at {}: {err}", path.display());
+        return None;
+    }
+    Some(path)
+}
+
+/// Where to download a video or audio attachment to, with the cache
+/// directory created.
+#[cfg(feature = "playback")]
+pub fn media_download_path(url: &str) -> Option<PathBuf> {
+    let path = image_cache_path(url)?;
+    if let Err(err) = std::fs::create_dir_all(path.parent()?) {
+        tracing::warn!("failed to create image cache directory: {err}");
+        return None;
+    }
+    Some(path)
 }
 
 pub fn save_image(url: &str, bytes: &[u8]) {
@@ -159,9 +617,130 @@ pub fn save_image(url: &str, bytes: &[u8]) {
     }
 }
 
-/// Remove all cache files for an account, called on logout/account removal.
+/// Every file in the image cache: (path, size in bytes, last used).
+fn cached_images() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Some(entries) = image_cache_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| {
+                let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                (entry.path(), metadata.len(), used)
+            })
+        })
+        .collect()
+}
+
+/// Delete images not shown within `max_age`, then the least recently shown
+/// ones until the cache fits in `max_bytes`. Files named by the old
+/// non-SHA-256 scheme are never looked up again, so they go too. Returns
+/// the cache's size afterward.
+pub fn prune_image_cache(max_bytes: u64, max_age: Duration) -> u64 {
+    let now = SystemTime::now();
+    let (mut kept, stale): (Vec<_>, Vec<_>) =
+        cached_images().into_iter().partition(|(path, _, used)| {
+            let current = path
+                .file_name()
+                .is_some_and(|name| name.len() == IMAGE_NAME_LEN);
+            current && now.duration_since(*used).unwrap_or_default() <= max_age
+        });
+    for (path, _, _) in &stale {
+        remove_image(path);
+    }
+
+    kept.sort_by_key(|(_, _, used)| *used);
+    let mut usage: u64 = kept.iter().map(|(_, size, _)| size).sum();
+    for (path, size, _) in &kept {
+        if usage <= max_bytes {
+            break;
+        }
+        remove_image(path);
+        usage -= size;
+    }
+    usage
+}
+
+/// Delete every cached image.
+pub fn clear_image_cache() -> std::io::Result<()> {
+    let Some(dir) = image_cache_dir() else {
+        return Ok(());
+    };
+    match std::fs::remove_dir_all(&dir) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn remove_image(path: &Path) {
+    if let Err(err) = std::fs::remove_file(path) {
+        tracing::warn!("failed to remove cached image {}: {err}", path.display());
+    }
+}
+
+/// Pending outbox actions live in the data directory rather than the cache:
+/// unlike cached feed content, losing them loses something the user did.
+fn outbox_path(base_url: &str) -> Option<PathBuf> {
+    Some(
+        dirs::data_dir()?
+            .join(AppModel::APP_ID)
+            .join(account_slug(base_url))
+            .join("outbox.json"),
+    )
+}
+
+pub fn save_outbox<T: Serialize>(base_url: &str, items: &[T]) {
+    let Some(path) = outbox_path(base_url) else {
+        return;
+    };
+    let Some(parent) = path.parent() else { return };
+    if let Err(err) = std::fs::create_dir_all(parent) {
+        tracing::warn!("failed to create outbox directory: {err}");
+        return;
+    }
+    match serde_json::to_vec(items) {
+        Ok(data) => {
+            if let Err(err) = std::fs::write(&path, data) {
+                tracing::warn!("failed to write outbox {}: {err}", path.display());
+            }
+        }
+        Err(err) => tracing::warn!("failed to serialize outbox: {err}"),
+    }
+}
+
+pub fn load_outbox<T: DeserializeOwned>(base_url: &str) -> Vec<T> {
+    let Some(path) = outbox_path(base_url) else {
+        return Vec::new();
+    };
+    match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Remove an account's cached content and pending outbox, called on
+/// logout/account removal.
 pub fn clear_account(base_url: &str) {
-    if let Some(dir) = account_dir(base_url) {
-        let _ = std::fs::remove_dir_all(dir);
+    let account = account_slug(base_url);
+    with_write(|conn| {
+        for table in [
+            "statuses",
+            "accounts",
+            "notifications",
+            "relationships",
+            "timelines",
+            "timeline_entries",
+        ] {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE account = ?1"),
+                [&account],
+            )?;
+        }
+        Ok(())
+    });
+    if let Some(path) = outbox_path(base_url) {
+        let _ = std::fs::remove_file(path);
     }
 }
@@ -0,0 +1,164 @@
+//! Where saved accounts ([`Sessions`]) live between runs: the system keyring
+//! when there is one, otherwise a passphrase-encrypted file in the config
+//! directory.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cosmic::Application;
+use rand::RngCore;
+
+use crate::{app::AppModel, client::Sessions, error::Error};
+
+pub trait SessionStore {
+    /// The saved sessions, or `None` if nothing has been saved yet.
+    fn load(&self) -> Result<Option<Sessions>, Error>;
+    fn save(&self, sessions: &Sessions) -> Result<(), Error>;
+}
+
+/// The Secret Service (or platform equivalent) via `keytar`.
+pub struct KeyringStore;
+
+impl KeyringStore {
+    const ACCOUNT: &'static str = "mastodon-data";
+}
+
+impl SessionStore for KeyringStore {
+    fn load(&self) -> Result<Option<Sessions>, Error> {
+        let data = keytar::get_password(AppModel::APP_ID, Self::ACCOUNT)
+            .map_err(|err| Error::Keyring(err.to_string()))?;
+        Ok(data
+            .success
+            .then(|| Sessions::parse(&data.password))
+            .flatten())
+    }
+
+    fn save(&self, sessions: &Sessions) -> Result<(), Error> {
+        let data = serde_json::to_string(sessions)?;
+        keytar::set_password(AppModel::APP_ID, Self::ACCOUNT, &data)
+            .map_err(|err| Error::Keyring(err.to_string()))
+    }
+}
+
+/// AES-256-GCM encrypted JSON, keyed by Argon2 from a passphrase the user
+/// enters at startup. A fresh salt and nonce are written on every save.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedFileStore {
+    /// `None` if there's no config directory to put the file in.
+    pub fn new(passphrase: String) -> Option<Self> {
+        Some(Self {
+            path: Self::path()?,
+            passphrase,
+        })
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(AppModel::APP_ID).join("sessions.enc"))
+    }
+
+    /// Whether the fallback can be used at all on this system.
+    pub fn is_available() -> bool {
+        Self::path().is_some()
+    }
+
+    /// Whether a previous run saved sessions that need unlocking.
+    pub fn exists() -> bool {
+        Self::path().is_some_and(|path| path.exists())
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<Aes256Gcm, Error> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|_| Error::Decrypt)?;
+        Aes256Gcm::new_from_slice(&key).map_err(|_| Error::Decrypt)
+    }
+}
+
+impl SessionStore for EncryptedFileStore {
+    fn load(&self) -> Result<Option<Sessions>, Error> {
+        let data = match std::fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let file: EncryptedFile = serde_json::from_str(&data)?;
+        let decode = |field: &str| STANDARD.decode(field).map_err(|_| Error::Decrypt);
+        let salt = decode(&file.salt)?;
+        let nonce = decode(&file.nonce)?;
+        let ciphertext = decode(&file.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(Error::Decrypt);
+        }
+        let plaintext = self
+            .cipher(&salt)?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| Error::Decrypt)?;
+        let plaintext = String::from_utf8(plaintext).map_err(|_| Error::Decrypt)?;
+        Ok(Sessions::parse(&plaintext))
+    }
+
+    fn save(&self, sessions: &Sessions) -> Result<(), Error> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let plaintext = serde_json::to_string(sessions)?;
+        let ciphertext = self
+            .cipher(&salt)?
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| Error::Encrypt)?;
+        let file = EncryptedFile {
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Written in full to a temporary file beside it, then renamed over
+        // it, so a crash mid-write never leaves the accounts unreadable.
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        // Owner-only from the moment it's created, never readable in between.
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut handle = options.open(&temp)?;
+        // `mode` only applies to new files; tighten one left over from an
+        // earlier, interrupted save before writing to it.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            handle.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        handle.write_all(serde_json::to_string(&file)?.as_bytes())?;
+        handle.sync_all()?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
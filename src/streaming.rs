@@ -1,11 +1,17 @@
 //! Real-time streaming of the authenticated user's events (new posts,
-//! notifications, deletions), pushed into the relevant feature's message type.
+//! notifications, deletions) and of individual list/hashtag timelines, pushed
+//! into the relevant feature's message type.
 
 use cosmic::iced::{stream, Subscription};
 use futures_util::SinkExt;
 use megalodon::streaming::Message as StreamMessage;
 
-use crate::{app, client::Client, features::notifications, features::timeline};
+use crate::{
+    app,
+    client::Client,
+    features::notifications,
+    features::timeline::{self, TimelineKind},
+};
 
 pub fn stream_user_events(mastodon: Client) -> Subscription<app::Message> {
     Subscription::run_with(mastodon, |mastodon| {
@@ -58,3 +64,41 @@ pub fn stream_user_events(mastodon: Client) -> Subscription<app::Message> {
         })
     })
 }
+
+/// Stream new and deleted posts for a list or hashtag timeline, e.g. a
+/// pinned sidebar page or a deck column. Other kinds have no dedicated
+/// stream (Home is covered by [`stream_user_events`]).
+pub fn stream_timeline(mastodon: Client, kind: TimelineKind) -> Subscription<timeline::Message> {
+    if !matches!(kind, TimelineKind::List(_) | TimelineKind::Tag(_)) {
+        return Subscription::none();
+    }
+    Subscription::run_with((mastodon, kind), |(mastodon, kind)| {
+        let mastodon = mastodon.clone();
+        let kind = kind.clone();
+        stream::channel(1, |output: futures_channel::mpsc::Sender<timeline::Message>| async move {
+            let streaming = match &kind {
+                TimelineKind::List(id) => mastodon.list_streaming(id.clone()).await,
+                TimelineKind::Tag(name) => mastodon.tag_streaming(name.clone()).await,
+                _ => return std::future::pending().await,
+            };
+
+            streaming
+                .listen(Box::new(move |message| {
+                    let mut output = output.clone();
+                    Box::pin(async move {
+                        let message = match message {
+                            StreamMessage::Update(status) => timeline::Message::PrependStatus(status),
+                            StreamMessage::Delete(id) => timeline::Message::DeleteStatus(id),
+                            _ => return,
+                        };
+                        if let Err(err) = output.send(message).await {
+                            tracing::warn!("failed to send post: {}", err);
+                        }
+                    })
+                }))
+                .await;
+
+            std::future::pending().await
+        })
+    })
+}